@@ -0,0 +1,26 @@
+use std::env;
+use std::process::Command;
+
+/// `#[diagnostic::on_unimplemented]` is stable since Rust 1.78, and an error on older compilers.
+/// Proc macros run on the compiler that compiles them, so the generated code can depend on its version.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(entrait_diagnostic_namespace)");
+
+    if rustc_minor_version().map_or(false, |minor| minor >= 78) {
+        println!("cargo:rustc-cfg=entrait_diagnostic_namespace");
+    }
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+
+    // i.e. `rustc 1.78.0 (9b00956e5 2024-04-29)`
+    let mut pieces = version.split_whitespace().nth(1)?.split('.');
+    if pieces.next() != Some("1") {
+        return None;
+    }
+    pieces.next()?.parse().ok()
+}
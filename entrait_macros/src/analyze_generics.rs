@@ -1,20 +1,30 @@
-use crate::generics::{FnDeps, TraitDependencyMode, TraitGenerics};
+use crate::generics::{self, FnDeps, TraitDependencyMode, TraitGenerics};
 use crate::idents::{CrateIdents, GenericIdents};
 use crate::input::FnInputMode;
-use crate::opt::Opts;
+use crate::opt::{FutureSend, Opts};
 use crate::signature::ImplReceiverKind;
 use crate::signature::{converter::SignatureConverter, EntraitSignature, InputSig};
 use crate::token_util::TokenPair;
 
 use proc_macro2::Span;
+use quote::ToTokens;
 use syn::spanned::Spanned;
 
 #[derive(Clone)]
 pub struct TraitFn {
     pub deps: FnDeps,
+    /// The dependency parameter of the original fn, or its ident when there is none
+    pub deps_span: Span,
     pub attrs: Vec<syn::Attribute>,
     pub entrait_sig: EntraitSignature,
+    /// An `async fn`, or a fn returning `impl Future`
     pub originally_async: bool,
+    /// The original fn ident, when the trait method was given another name
+    pub renamed_from: Option<syn::Ident>,
+    /// Future `Send`-ness specified for this fn, overriding the trait-level option
+    pub future_send: Option<FutureSend>,
+    /// The default body of a provided trait method
+    pub default: Option<syn::Block>,
 }
 
 impl TraitFn {
@@ -22,6 +32,15 @@ impl TraitFn {
         &self.entrait_sig.sig
     }
 
+    /// The ident of the original fn implementing this trait method
+    pub fn source_ident(&self) -> &syn::Ident {
+        self.renamed_from.as_ref().unwrap_or(&self.sig().ident)
+    }
+
+    pub fn future_send(&self, opts: &Opts) -> FutureSend {
+        self.future_send.unwrap_or_else(|| opts.future_send())
+    }
+
     pub fn opt_dot_await(&self, span: Span) -> Option<impl quote::ToTokens> {
         if self.originally_async {
             Some(TokenPair(syn::token::Dot(span), syn::token::Await(span)))
@@ -54,11 +73,20 @@ impl TraitFnAnalyzer<'_> {
             impl_receiver_kind: self.impl_receiver_kind,
         }
         .convert_fn_to_trait_fn();
+        let originally_async = entrait_sig.sig.asyncness.is_some();
+        let deps_span = match (&deps, input_sig.inputs.first()) {
+            (FnDeps::NoDeps, _) | (_, None) => input_sig.ident.span(),
+            (_, Some(deps_arg)) => deps_arg.span(),
+        };
         Ok(TraitFn {
             deps,
+            deps_span,
             attrs: vec![],
             entrait_sig,
-            originally_async: input_sig.asyncness.is_some(),
+            originally_async,
+            renamed_from: None,
+            future_send: self.opts.future_send.map(|opt| opt.0),
+            default: None,
         })
     }
 }
@@ -66,21 +94,28 @@ impl TraitFnAnalyzer<'_> {
 pub(super) fn detect_trait_dependency_mode<'t, 'c>(
     input_mode: &FnInputMode,
     trait_fns: &'t [TraitFn],
+    opts: &Opts,
     crate_idents: &'c CrateIdents,
     span: proc_macro2::Span,
 ) -> syn::Result<TraitDependencyMode<'t, 'c>> {
+    if opts.concrete_value() {
+        return detect_concrete_leaf(input_mode, trait_fns, opts);
+    }
+
     for trait_fn in trait_fns {
         if let FnDeps::Concrete(ty) = &trait_fn.deps {
             return match input_mode {
                 FnInputMode::SingleFn(_) => Ok(TraitDependencyMode::Concrete(ty.as_ref())),
                 FnInputMode::Module(_) => Err(syn::Error::new(
                     ty.span(),
-                    "Using concrete dependencies in a module is an anti-pattern. Instead, write a trait manually, use the #[entrait] attribute on it, and implement it for your application type",
-                )),
-                FnInputMode::ImplBlock(_) => Err(syn::Error::new(
-                    ty.span(),
-                    "Cannot (yet) use concrete dependency in an impl block"
+                    format!(
+                        "`{}` uses a concrete dependency. Using concrete dependencies in a module is an anti-pattern, unless the module is a leaf. Either pass `concrete` to entrait to make all functions in the module implement a trait for the concrete type, or write a trait manually, use the #[entrait] attribute on it, and implement it for your application type",
+                        trait_fn.sig().ident,
+                    ),
                 )),
+                // Impl blocks are always generic over `Impl<T>`,
+                // concrete dependencies are projected out of `T` per function.
+                FnInputMode::ImplBlock(_) => continue,
                 FnInputMode::RawTrait(_) => panic!("Should not detect dependencies for this input mode")
             };
         }
@@ -92,6 +127,62 @@ pub(super) fn detect_trait_dependency_mode<'t, 'c>(
     )))
 }
 
+/// With the `concrete` option, every function must depend on the same concrete type,
+/// which becomes the implementor of the generated leaf trait.
+fn detect_concrete_leaf<'t, 'c>(
+    input_mode: &FnInputMode,
+    trait_fns: &'t [TraitFn],
+    opts: &Opts,
+) -> syn::Result<TraitDependencyMode<'t, 'c>> {
+    if let FnInputMode::ImplBlock(_) | FnInputMode::RawTrait(_) = input_mode {
+        return Err(syn::Error::new(
+            opts.default_option(opts.concrete, true).1,
+            "`concrete` is only supported for functions and modules",
+        ));
+    }
+
+    let mut leaf_ty: Option<&'t syn::Type> = None;
+
+    for trait_fn in trait_fns {
+        let fn_ident = &trait_fn.sig().ident;
+
+        match &trait_fn.deps {
+            FnDeps::Concrete(ty) => match leaf_ty {
+                None => {
+                    leaf_ty = Some(ty.as_ref());
+                }
+                Some(leaf_ty) => {
+                    if leaf_ty.to_token_stream().to_string() != ty.to_token_stream().to_string() {
+                        return Err(syn::Error::new(
+                            ty.span(),
+                            format!(
+                                "`{fn_ident}` depends on `{}`, but the leaf trait is implemented for `{}`. All functions with a dependency must depend on the same concrete type when using `concrete`",
+                                ty.to_token_stream(),
+                                leaf_ty.to_token_stream(),
+                            ),
+                        ));
+                    }
+                }
+            },
+            FnDeps::Generic { .. } => {
+                return Err(syn::Error::new(
+                    trait_fn.deps_span,
+                    format!("`{fn_ident}` has generic dependencies. All functions with a dependency must depend on the same concrete type when using `concrete`"),
+                ));
+            }
+            FnDeps::NoDeps => {}
+        }
+    }
+
+    match leaf_ty {
+        Some(ty) => Ok(TraitDependencyMode::Concrete(ty)),
+        None => Err(syn::Error::new(
+            opts.default_option(opts.concrete, true).1,
+            "`concrete` requires at least one function with a concrete dependency",
+        )),
+    }
+}
+
 pub struct GenericsAnalyzer {
     trait_generics: TraitGenerics,
 }
@@ -111,10 +202,19 @@ impl GenericsAnalyzer {
     }
 
     pub fn analyze_fn_deps(&mut self, input_sig: InputSig<'_>, opts: &Opts) -> syn::Result<FnDeps> {
-        if opts.no_deps_value() {
-            return self.deps_with_generics(FnDeps::NoDeps, &input_sig.generics);
+        let deps = if opts.no_deps_value() {
+            FnDeps::NoDeps
+        } else {
+            self.analyze_deps_param(input_sig)?
+        };
+        if !opts.method_generics_value() {
+            self.hoist_generics(&deps, &input_sig.generics);
         }
 
+        Ok(deps)
+    }
+
+    fn analyze_deps_param(&mut self, input_sig: InputSig<'_>) -> syn::Result<FnDeps> {
         let first_input =
             match input_sig.inputs.first() {
                 Some(fn_arg) => fn_arg,
@@ -137,21 +237,61 @@ impl GenericsAnalyzer {
         self.extract_deps_from_type(input_sig, pat_type.ty.as_ref())
     }
 
+    /// The generic params of the function, apart from the dependency, become params of the trait
+    fn hoist_generics(&mut self, deps: &FnDeps, generics: &syn::Generics) {
+        let deps_ident = match deps {
+            FnDeps::Generic {
+                generic_param: Some(generic_param),
+                ..
+            } => Some(generic_param),
+            _ => None,
+        };
+
+        for param in &generics.params {
+            match param {
+                syn::GenericParam::Type(type_param) if Some(&type_param.ident) == deps_ident => {}
+                syn::GenericParam::Lifetime(_) => {}
+                _ => {
+                    self.trait_generics.params.push(param.clone());
+                }
+            }
+        }
+
+        if let Some(where_clause) = &generics.where_clause {
+            for predicate in &where_clause.predicates {
+                // With a generic dependency param, predicates on plain type params only stay on the method
+                match predicate {
+                    syn::WherePredicate::Type(predicate_type)
+                        if deps_ident.is_some()
+                            && single_ident_type(&predicate_type.bounded_ty).is_some() => {}
+                    _ => {
+                        self.trait_generics.where_predicates.push(predicate.clone());
+                    }
+                }
+            }
+        }
+    }
+
     fn extract_deps_from_type(
         &mut self,
         input_sig: InputSig<'_>,
         ty: &syn::Type,
     ) -> syn::Result<FnDeps> {
+        if let Some((_, inner)) = generics::arc_deps(ty) {
+            // `Arc<impl Trait>` is taken as `self: Arc<Self>`, an `Arc` of a concrete type stays concrete
+            return match self.extract_deps_from_type(input_sig, inner)? {
+                deps @ FnDeps::Generic { .. } => Ok(deps),
+                _ => Ok(FnDeps::Concrete(Box::new(ty.clone()))),
+            };
+        }
+
         match ty {
             syn::Type::ImplTrait(type_impl_trait) => {
                 // Simple case, bounds are actually inline, no lookup necessary
-                self.deps_with_generics(
-                    FnDeps::Generic {
-                        generic_param: None,
-                        trait_bounds: extract_trait_bounds(&type_impl_trait.bounds),
-                    },
-                    &input_sig.generics,
-                )
+                Ok(FnDeps::Generic {
+                    generic_param: None,
+                    trait_bounds: extract_trait_bounds(&type_impl_trait.bounds),
+                })
             }
             syn::Type::Path(type_path) => {
                 // Type path. Should be defined as a generic parameter.
@@ -165,29 +305,21 @@ impl GenericsAnalyzer {
                     ));
                 }
                 if type_path.path.segments.len() != 1 {
-                    return self.deps_with_generics(
-                        FnDeps::Concrete(Box::new(ty.clone())),
-                        &input_sig.generics,
-                    );
+                    return Ok(FnDeps::Concrete(Box::new(ty.clone())));
                 }
 
                 let first_segment = type_path.path.segments.first().unwrap();
 
                 match self.find_deps_generic_bounds(input_sig, &first_segment.ident) {
                     Some(generics) => Ok(generics),
-                    None => self.deps_with_generics(
-                        FnDeps::Concrete(Box::new(ty.clone())),
-                        &input_sig.generics,
-                    ),
+                    None => Ok(FnDeps::Concrete(Box::new(ty.clone()))),
                 }
             }
             syn::Type::Reference(type_reference) => {
                 self.extract_deps_from_type(input_sig, type_reference.elem.as_ref())
             }
             syn::Type::Paren(paren) => self.extract_deps_from_type(input_sig, paren.elem.as_ref()),
-            ty => {
-                self.deps_with_generics(FnDeps::Concrete(Box::new(ty.clone())), &input_sig.generics)
-            }
+            ty => Ok(FnDeps::Concrete(Box::new(ty.clone()))),
         }
     }
 
@@ -197,58 +329,22 @@ impl GenericsAnalyzer {
         generic_param_ident: &syn::Ident,
     ) -> Option<FnDeps> {
         let generics = &input_sig.generics;
-        let generic_params = &generics.params;
-
-        let (matching_index, matching_type_param) = generic_params
-            .into_iter()
-            .enumerate()
-            .find_map(|(index, param)| match param {
-                syn::GenericParam::Type(type_param) => {
-                    if &type_param.ident == generic_param_ident {
-                        Some((index, type_param))
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            })?;
 
-        for (index, param) in generic_params.iter().enumerate() {
-            if index != matching_index && !(matches!(param, &syn::GenericParam::Lifetime(_))) {
-                self.trait_generics.params.push(param.clone());
+        let matching_type_param = generics.params.iter().find_map(|param| match param {
+            syn::GenericParam::Type(type_param) if &type_param.ident == generic_param_ident => {
+                Some(type_param)
             }
-        }
+            _ => None,
+        })?;
 
         // Extract "direct" bounds, not from where clause
         let mut deps_trait_bounds = extract_trait_bounds(&matching_type_param.bounds);
 
         if let Some(where_clause) = &generics.where_clause {
             for predicate in &where_clause.predicates {
-                match predicate {
-                    syn::WherePredicate::Type(predicate_type) => match &predicate_type.bounded_ty {
-                        syn::Type::Path(type_path) => {
-                            if type_path.qself.is_some() || type_path.path.leading_colon.is_some() {
-                                self.trait_generics.where_predicates.push(predicate.clone());
-                                continue;
-                            }
-                            if type_path.path.segments.len() != 1 {
-                                self.trait_generics.where_predicates.push(predicate.clone());
-                                continue;
-                            }
-                            let first_segment = type_path.path.segments.first().unwrap();
-
-                            if &first_segment.ident == generic_param_ident {
-                                let where_paths = extract_trait_bounds(&predicate_type.bounds);
-
-                                deps_trait_bounds.extend(where_paths);
-                            }
-                        }
-                        _ => {
-                            self.trait_generics.where_predicates.push(predicate.clone());
-                        }
-                    },
-                    _ => {
-                        self.trait_generics.where_predicates.push(predicate.clone());
+                if let syn::WherePredicate::Type(predicate_type) = predicate {
+                    if single_ident_type(&predicate_type.bounded_ty) == Some(generic_param_ident) {
+                        deps_trait_bounds.extend(extract_trait_bounds(&predicate_type.bounds));
                     }
                 }
             }
@@ -259,31 +355,18 @@ impl GenericsAnalyzer {
             trait_bounds: deps_trait_bounds,
         })
     }
+}
 
-    fn deps_with_generics(
-        &mut self,
-        deps: FnDeps,
-        generics: &syn::Generics,
-    ) -> syn::Result<FnDeps> {
-        for param in &generics.params {
-            match param {
-                syn::GenericParam::Type(_) => {
-                    self.trait_generics.params.push(param.clone());
-                }
-                syn::GenericParam::Const(_) => {
-                    self.trait_generics.params.push(param.clone());
-                }
-                syn::GenericParam::Lifetime(_) => {}
-            }
-        }
-
-        if let Some(where_clause) = &generics.where_clause {
-            for predicate in &where_clause.predicates {
-                self.trait_generics.where_predicates.push(predicate.clone());
-            }
+fn single_ident_type(ty: &syn::Type) -> Option<&syn::Ident> {
+    match ty {
+        syn::Type::Path(type_path)
+            if type_path.qself.is_none()
+                && type_path.path.leading_colon.is_none()
+                && type_path.path.segments.len() == 1 =>
+        {
+            Some(&type_path.path.segments[0].ident)
         }
-
-        Ok(deps)
+        _ => None,
     }
 }
 
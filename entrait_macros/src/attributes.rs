@@ -4,6 +4,7 @@ use crate::idents::CrateIdents;
 use crate::input::FnInputMode;
 use crate::opt::{MockApiIdent, Opts};
 use crate::token_util::{comma_sep, push_tokens};
+use crate::trait_codegen::AssocItem;
 
 use proc_macro2::{Span, TokenStream};
 use quote::ToTokens;
@@ -54,6 +55,10 @@ impl<P: ToTokens + IsEmpty> ToTokens for ExportGatedAttr<'_, P> {
 
 pub struct EntraitForTraitParams<'a> {
     pub crate_idents: &'a CrateIdents,
+    /// Passes on the `context` option of the leaf trait
+    pub context: bool,
+    /// Passes on the `leaf_accessor` option of the leaf trait
+    pub leaf_accessor: bool,
 }
 
 impl ToTokens for EntraitForTraitParams<'_> {
@@ -79,6 +84,20 @@ impl ToTokens for EntraitForTraitParams<'_> {
                 Eq::default(),
                 syn::LitBool::new(false, Span::call_site())
             );
+            if self.context {
+                push_tokens!(
+                    stream,
+                    Comma::default(),
+                    Ident::new("context", Span::call_site())
+                );
+            }
+            if self.leaf_accessor {
+                push_tokens!(
+                    stream,
+                    Comma::default(),
+                    Ident::new("leaf_accessor", Span::call_site())
+                );
+            }
         });
     }
 }
@@ -90,6 +109,7 @@ pub struct UnimockAttrParams<'s> {
     pub trait_indirection: TraitIndirection,
     pub crate_idents: &'s CrateIdents,
     pub trait_fns: &'s [TraitFn],
+    pub assoc_items: &'s [AssocItem],
     pub(super) fn_input_mode: &'s FnInputMode<'s>,
     pub span: Span,
 }
@@ -158,6 +178,20 @@ impl ToTokens for UnimockAttrParams<'_> {
                     });
                 }
             }
+
+            // type Assoc = MockType; const ASSOC: Ty = value;
+            let mock_items: Vec<_> = self
+                .assoc_items
+                .iter()
+                .filter_map(AssocItem::gen_mock_item)
+                .collect();
+            if !mock_items.is_empty() {
+                punctuator.push_fn(|stream| {
+                    for mock_item in &mock_items {
+                        push_tokens!(stream, mock_item);
+                    }
+                });
+            }
         });
     }
 }
@@ -175,7 +209,7 @@ impl UnimockAttrParams<'_> {
             let mut punctuator = comma_sep(stream, span);
 
             for trait_fn in self.trait_fns {
-                let fn_ident = &trait_fn.sig().ident;
+                let fn_ident = trait_fn.source_ident();
 
                 match &trait_fn.deps {
                     generics::FnDeps::Generic { .. } => {
@@ -184,7 +218,7 @@ impl UnimockAttrParams<'_> {
                     generics::FnDeps::Concrete(_) => {
                         punctuator.push(Underscore(span));
                     }
-                    generics::FnDeps::NoDeps { .. } => {
+                    generics::FnDeps::NoDeps => {
                         // fn_ident(a, b, c)
                         punctuator.push_fn(|stream| {
                             push_tokens!(stream, fn_ident);
@@ -207,17 +241,18 @@ impl UnimockAttrParams<'_> {
     }
 }
 
-pub struct MockallAutomockParams {
+pub struct MockallAutomockParams<'s> {
+    pub assoc_items: &'s [AssocItem],
     pub span: Span,
 }
 
-impl IsEmpty for MockallAutomockParams {
+impl IsEmpty for MockallAutomockParams<'_> {
     fn is_empty(&self) -> bool {
         false
     }
 }
 
-impl ToTokens for MockallAutomockParams {
+impl ToTokens for MockallAutomockParams<'_> {
     fn to_tokens(&self, stream: &mut TokenStream) {
         let span = self.span;
         push_tokens!(
@@ -227,9 +262,25 @@ impl ToTokens for MockallAutomockParams {
             syn::token::PathSep(span),
             syn::Ident::new("automock", span)
         );
+
+        // (type Assoc = MockType;), mockall uses the default values of associated consts
+        let mock_types: Vec<_> = self
+            .assoc_items
+            .iter()
+            .filter(|assoc_item| matches!(assoc_item, AssocItem::Type { .. }))
+            .filter_map(AssocItem::gen_mock_item)
+            .collect();
+        if !mock_types.is_empty() {
+            syn::token::Paren(span).surround(stream, |stream| {
+                for mock_type in &mock_types {
+                    push_tokens!(stream, mock_type);
+                }
+            });
+        }
     }
 }
 
+#[expect(unused)]
 pub struct AsyncTraitParams<'a> {
     pub crate_idents: &'a CrateIdents,
     pub span: Span,
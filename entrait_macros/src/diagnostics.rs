@@ -0,0 +1,329 @@
+//! Compiler diagnostics for generated traits.
+//!
+//! A missing implementation deep down in the dependency graph is reported by rustc as the root of a long chain
+//! of unsatisfied bounds. `#[diagnostic::on_unimplemented]` on the generated traits makes that root error
+//! name what the application type is missing.
+
+use crate::analyze_generics::TraitFn;
+use crate::generics::{FnDeps, TraitDependencyMode};
+use crate::opt::{Delegate, RefDelegate};
+
+use quote::ToTokens;
+
+/// `#[diagnostic::on_unimplemented(..)]`, which is only generated on compilers supporting it
+pub enum OnUnimplemented {
+    Generated {
+        message: String,
+        label: String,
+        note: String,
+    },
+    /// Written on an entraited trait, which takes precedence
+    HandWritten(syn::Attribute),
+}
+
+impl OnUnimplemented {
+    /// On an entraited trait, which is implemented for `implemented_for`
+    pub fn entraited_trait(trait_ident: &syn::Ident, implemented_for: &str) -> Self {
+        Self::Generated {
+            message: format!("`{{Self}}` does not implement `{trait_ident}`"),
+            label: format!("`{trait_ident}` is not implemented for `{{Self}}`"),
+            note: format!("`{trait_ident}` is implemented for {implemented_for}"),
+        }
+    }
+
+    /// On the `TraitImpl` trait of `delegate_by`, implemented by `#[entrait] impl TraitImpl for Type` blocks
+    pub fn impl_trait(
+        impl_trait_ident: &syn::Ident,
+        trait_ident: &syn::Ident,
+        delegate: &Delegate,
+    ) -> Self {
+        let entrait_attr = match delegate {
+            Delegate::ByRef(_) => "#[entrait(ref)]",
+            _ => "#[entrait]",
+        };
+        Self::Generated {
+            message: format!("`{{Self}}` is not an implementation of `{impl_trait_ident}`"),
+            label: format!("`{impl_trait_ident}` is not implemented for `{{Self}}`"),
+            note: format!(
+                "`{trait_ident}` is delegated to implementations of `{impl_trait_ident}`, \
+                written as `{entrait_attr} impl {impl_trait_ident} for {{Self}}`"
+            ),
+        }
+    }
+
+    /// On the `delegate_by = DelegateTrait` trait selecting the implementation of `impl_trait_ident`
+    pub fn delegation_trait(
+        delegation_ident: &syn::Ident,
+        trait_ident: &syn::Ident,
+        impl_trait_ident: &syn::Ident,
+    ) -> Self {
+        Self::Generated {
+            message: format!(
+                "`{{Self}}` does not select an implementation of `{impl_trait_ident}`, which `{trait_ident}` is delegated to"
+            ),
+            label: format!("`{delegation_ident}` is not implemented for `{{Self}}`"),
+            note: format!(
+                "implement `{delegation_ident}<{{Self}}>` for `{{Self}}` with a `Target` implementing `{impl_trait_ident}`, \
+                or derive `Wire` and annotate a field with `#[wire(delegate = {delegation_ident})]`"
+            ),
+        }
+    }
+
+    /// On the accessor trait of `delegate_by = Arc`, `Box` or `enum(..)`
+    pub fn accessor_trait(
+        accessor_ident: &syn::Ident,
+        trait_ident: &syn::Ident,
+        wire: &str,
+    ) -> Self {
+        Self::Generated {
+            message: format!(
+                "`{{Self}}` does not hand out the implementation that `{trait_ident}` is delegated to"
+            ),
+            label: format!("`{accessor_ident}` is not implemented for `{{Self}}`"),
+            note: format!(
+                "implement `{accessor_ident}` for `{{Self}}`, \
+                or derive `Wire` and annotate a field with `#[wire({wire})]`"
+            ),
+        }
+    }
+
+    /// On the `{Trait}Leaf` accessor trait of a leaf trait
+    pub fn leaf_accessor_trait(accessor_ident: &syn::Ident, trait_ident: &syn::Ident) -> Self {
+        Self::Generated {
+            message: format!("`{{Self}}` does not implement the leaf trait `{trait_ident}`"),
+            label: format!("`{trait_ident}` is not implemented for `{{Self}}`"),
+            note: format!(
+                "implement `{trait_ident}` or `{accessor_ident}` for `{{Self}}`, \
+                or derive `Wire` and annotate a field with `#[wire(leaf = {trait_ident})]`"
+            ),
+        }
+    }
+}
+
+impl ToTokens for OnUnimplemented {
+    fn to_tokens(&self, stream: &mut proc_macro2::TokenStream) {
+        match self {
+            #[cfg(entrait_diagnostic_namespace)]
+            Self::Generated {
+                message,
+                label,
+                note,
+            } => stream.extend(quote::quote! {
+                #[diagnostic::on_unimplemented(
+                    message = #message,
+                    label = #label,
+                    note = #note
+                )]
+            }),
+            #[cfg(not(entrait_diagnostic_namespace))]
+            Self::Generated { .. } => {}
+            Self::HandWritten(attr) => attr.to_tokens(stream),
+        }
+    }
+}
+
+/// What `Impl<T>` needs from `T` to implement a trait, i.e. "`Impl<T>` when `T` implements `AsRef<dyn Trait>`"
+pub fn delegated_to(
+    trait_ident: &syn::Ident,
+    delegate: Option<&Delegate>,
+    impl_trait_ident: Option<&syn::Ident>,
+    dyn_target_ident: &syn::Ident,
+) -> String {
+    // The impl trait is generic over the `T` in `Impl<T>`
+    let t = if impl_trait_ident.is_some() {
+        "<T>"
+    } else {
+        ""
+    };
+    let target = match impl_trait_ident {
+        Some(impl_trait_ident) => format!("{impl_trait_ident}<T>"),
+        None => trait_ident.to_string(),
+    };
+
+    let requirement = match delegate {
+        None | Some(Delegate::BySelf) => format!("implements `{trait_ident}`"),
+        Some(Delegate::ByRef(RefDelegate::AsRef)) => {
+            format!("implements `AsRef<dyn {dyn_target_ident}{t}>`")
+        }
+        Some(Delegate::ByRef(RefDelegate::Borrow)) => {
+            format!("implements `Borrow<dyn {dyn_target_ident}{t}>`")
+        }
+        Some(Delegate::ByRef(RefDelegate::Deref)) => {
+            format!("implements `Deref` with a `Target` implementing `{target}`")
+        }
+        Some(Delegate::ByRef(RefDelegate::Enum(_))) => {
+            format!("implements `As{trait_ident}Choice`")
+        }
+        Some(Delegate::ByRef(ref_delegate)) => format!(
+            "implements `{trait_ident}{}{t}`",
+            ref_delegate.accessor_pointer().unwrap_or_default()
+        ),
+        Some(Delegate::ByTrait(delegation_ident, _)) => {
+            format!("implements `{delegation_ident}<T>` with a `Target` implementing `{target}`")
+        }
+    };
+
+    format!("`Impl<T>` when `T` {requirement}")
+}
+
+/// The types implementing the trait of entraited functions, i.e. "`Impl<T>` when it implements the bounds of `deps`".
+/// Types and bounds are not spelled out, as token streams don't print the way they are written.
+pub fn fn_deps_implementors(
+    trait_dependency_mode: &TraitDependencyMode,
+    trait_fns: &[TraitFn],
+) -> String {
+    match trait_dependency_mode {
+        TraitDependencyMode::Concrete(_) => {
+            "the concrete type of `deps`, and for `Impl<T>` when `T` implements it".to_string()
+        }
+        TraitDependencyMode::Generic(_) => {
+            let has_bounds = trait_fns.iter().any(|trait_fn| {
+                matches!(&trait_fn.deps, FnDeps::Generic { trait_bounds, .. } if !trait_bounds.is_empty())
+            });
+            if has_bounds {
+                "`Impl<T>` when it implements the bounds of `deps`".to_string()
+            } else {
+                "`Impl<T>`".to_string()
+            }
+        }
+    }
+}
+
+/// A hand-written `#[diagnostic::on_unimplemented]` among the attributes of a trait
+pub fn find_on_unimplemented(attrs: &[syn::Attribute]) -> Option<&syn::Attribute> {
+    attrs.iter().find(|attr| {
+        let mut segments = attr.path().segments.iter();
+        matches!(
+            (segments.next(), segments.next(), segments.next()),
+            (Some(first), Some(second), None) if first.ident == "diagnostic" && second.ident == "on_unimplemented"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idents::{CrateIdents, GenericIdents};
+
+    use proc_macro2::Span;
+
+    fn ident(name: &str) -> syn::Ident {
+        syn::Ident::new(name, Span::call_site())
+    }
+
+    fn delegated_to_expect(delegate: Option<Delegate>, impl_trait: Option<&str>, expected: &str) {
+        let dyn_target_ident = ident(impl_trait.unwrap_or("Foo"));
+        assert_eq!(
+            delegated_to(
+                &ident("Foo"),
+                delegate.as_ref(),
+                impl_trait.map(ident).as_ref(),
+                &dyn_target_ident,
+            ),
+            expected,
+        );
+    }
+
+    #[test]
+    fn should_name_what_leaf_traits_need() {
+        delegated_to_expect(None, None, "`Impl<T>` when `T` implements `Foo`");
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::AsRef)),
+            None,
+            "`Impl<T>` when `T` implements `AsRef<dyn Foo>`",
+        );
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::Deref)),
+            None,
+            "`Impl<T>` when `T` implements `Deref` with a `Target` implementing `Foo`",
+        );
+    }
+
+    #[test]
+    fn should_name_what_delegating_traits_need() {
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::Borrow)),
+            Some("FooImpl"),
+            "`Impl<T>` when `T` implements `Borrow<dyn FooImpl<T>>`",
+        );
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::Arc)),
+            Some("FooImpl"),
+            "`Impl<T>` when `T` implements `FooArc<T>`",
+        );
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::Swap)),
+            Some("FooImpl"),
+            "`Impl<T>` when `T` implements `FooSwap<T>`",
+        );
+        delegated_to_expect(
+            Some(Delegate::ByRef(RefDelegate::Enum(vec![]))),
+            Some("FooImpl"),
+            "`Impl<T>` when `T` implements `AsFooChoice`",
+        );
+        delegated_to_expect(
+            Some(Delegate::ByTrait(ident("DelegateFoo"), vec![])),
+            Some("FooImpl"),
+            "`Impl<T>` when `T` implements `DelegateFoo<T>` with a `Target` implementing `FooImpl<T>`",
+        );
+    }
+
+    #[test]
+    fn should_not_spell_out_deps_types() {
+        let crate_idents = CrateIdents::new(Span::call_site());
+        let generic_idents = GenericIdents::new(&crate_idents, Span::call_site());
+        let ty: syn::Type = syn::parse_quote! { &'a (dyn A + B) };
+
+        assert_eq!(
+            fn_deps_implementors(&TraitDependencyMode::Concrete(&ty), &[]),
+            "the concrete type of `deps`, and for `Impl<T>` when `T` implements it",
+        );
+        assert_eq!(
+            fn_deps_implementors(&TraitDependencyMode::Generic(generic_idents), &[]),
+            "`Impl<T>`",
+        );
+    }
+
+    #[test]
+    fn should_keep_hand_written_on_unimplemented() {
+        let attrs: Vec<syn::Attribute> = vec![
+            syn::parse_quote! { #[doc = "Foo"] },
+            syn::parse_quote! { #[diagnostic::on_unimplemented(message = "hand written")] },
+        ];
+        let hand_written = find_on_unimplemented(&attrs).unwrap();
+
+        assert_eq!(
+            OnUnimplemented::HandWritten(hand_written.clone())
+                .to_token_stream()
+                .to_string(),
+            attrs[1].to_token_stream().to_string(),
+        );
+    }
+
+    #[cfg(entrait_diagnostic_namespace)]
+    #[test]
+    fn should_generate_on_unimplemented_on_rust_1_78() {
+        let expected: syn::Attribute = syn::parse_quote! {
+            #[diagnostic::on_unimplemented(
+                message = "`{Self}` does not implement `Foo`",
+                label = "`Foo` is not implemented for `{Self}`",
+                note = "`Foo` is implemented for `Impl<T>`"
+            )]
+        };
+
+        assert_eq!(
+            OnUnimplemented::entraited_trait(&ident("Foo"), "`Impl<T>`")
+                .to_token_stream()
+                .to_string(),
+            expected.to_token_stream().to_string(),
+        );
+    }
+
+    #[cfg(not(entrait_diagnostic_namespace))]
+    #[test]
+    fn should_not_generate_on_unimplemented_before_rust_1_78() {
+        assert!(OnUnimplemented::entraited_trait(&ident("Foo"), "`Impl<T>`")
+            .to_token_stream()
+            .is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+//! # entrait on `const` and `static` items
+//!
+//! Generates a single-method accessor trait for the value.
+//! The value is handled as an entraited `no_deps` fn returning it.
+
+use crate::entrait_fn;
+use crate::entrait_fn::input_attr::EntraitFnAttr;
+use crate::input::{InputConst, InputFn};
+use crate::opt::SpanOpt;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::visit_mut::VisitMut;
+
+pub fn output_tokens(mut attr: EntraitFnAttr, input_const: InputConst) -> syn::Result<TokenStream> {
+    let unsupported = [
+        attr.opts.no_deps.map(|opt| opt.1),
+        attr.opts.concrete.map(|opt| opt.1),
+        attr.opts.future_send.map(|opt| opt.1),
+        attr.recursive.map(|opt| opt.1),
+        attr.delegation_kind.as_ref().map(|opt| opt.1),
+    ];
+    if let Some(span) = unsupported.into_iter().flatten().next() {
+        return Err(syn::Error::new(
+            span,
+            "Unsupported option for a const or static",
+        ));
+    }
+
+    let const_ident = input_const.ident();
+    attr.opts.no_deps = Some(SpanOpt(true, const_ident.span()));
+
+    let method_ident = syn::Ident::new(&const_ident.to_string().to_lowercase(), const_ident.span());
+    let accessor =
+        entrait_fn::entrait_for_accessor_fn(&attr, accessor_fn(&input_const), &method_ident)?;
+
+    Ok(quote! {
+        #input_const
+        #accessor
+    })
+}
+
+/// `fn __CONST() -> Type { CONST }`, or `fn __STATIC() -> &'static Type { &STATIC }`
+fn accessor_fn(input_const: &InputConst) -> InputFn {
+    let const_ident = input_const.ident();
+    let mut ty = input_const.ty().clone();
+    StaticLifetimes.visit_type_mut(&mut ty);
+    let ty = &ty;
+    let span = const_ident.span();
+    let fn_ident = format_ident!("__{}", const_ident);
+
+    let (output, expr) = match input_const {
+        InputConst::Const(_) => (quote! { #ty }, quote! { #const_ident }),
+        InputConst::Static(_) => (quote! { &'static #ty }, quote! { &#const_ident }),
+    };
+
+    InputFn {
+        fn_attrs: vec![syn::parse_quote! { #[allow(non_snake_case)] }],
+        fn_vis: syn::Visibility::Inherited,
+        fn_sig: syn::parse_quote_spanned! { span=>
+            fn #fn_ident() -> #output
+        },
+        fn_body: quote_spanned! { span=>
+            {
+                #expr
+            }
+        },
+    }
+}
+
+/// Elided lifetimes in the type of a `const` or `static` are `'static`,
+/// but have to be spelled out in the signature of the accessor.
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(syn::Lifetime::new("'static", reference.and_token.span));
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = syn::Lifetime::new("'static", lifetime.apostrophe);
+        }
+    }
+}
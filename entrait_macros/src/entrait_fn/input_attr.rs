@@ -9,9 +9,29 @@ pub struct EntraitFnAttr {
     pub trait_ident: syn::Ident,
     pub opts: Opts,
 
+    /// Whether inline submodules of a module become nested traits
+    pub recursive: Option<SpanOpt<Recursive>>,
+
+    /// Delegate the `Impl<T>` implementation to a generated `{Trait}Impl` trait
+    pub delegation_kind: Option<SpanOpt<Delegate>>,
+
     pub crate_idents: CrateIdents,
 }
 
+impl EntraitFnAttr {
+    pub fn recursive_value(&self) -> Recursive {
+        self.opts.default_option(self.recursive, Recursive::No).0
+    }
+
+    /// The delegation kind, unless `Impl<T>` implements the trait by calling the functions directly
+    pub fn delegation(&self) -> Option<&SpanOpt<Delegate>> {
+        match &self.delegation_kind {
+            Some(SpanOpt(Delegate::BySelf, _)) | None => None,
+            Some(delegation_kind) => Some(delegation_kind),
+        }
+    }
+}
+
 impl Parse for EntraitFnAttr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let span = input.span();
@@ -20,25 +40,36 @@ impl Parse for EntraitFnAttr {
         let trait_ident: syn::Ident = input.parse()?;
 
         let mut no_deps = None;
+        let mut concrete = None;
+        let mut context = None;
+        let mut leaf_accessor = None;
+        let mut method_generics = None;
         let mut debug = None;
         let mut export = None;
         let mut future_send = None;
         let mut mock_api = None;
         let mut unimock = None;
         let mut mockall = None;
+        let mut recursive = None;
+        let mut delegation_kind = None;
 
         while input.peek(syn::token::Comma) {
             input.parse::<syn::token::Comma>()?;
 
             match input.parse::<EntraitOpt>()? {
                 EntraitOpt::NoDeps(opt) => no_deps = Some(opt),
+                EntraitOpt::Concrete(opt) => concrete = Some(opt),
+                EntraitOpt::Context(opt) => context = Some(opt),
+                EntraitOpt::LeafAccessor(opt) => leaf_accessor = Some(opt),
+                EntraitOpt::MethodGenerics(opt) => method_generics = Some(opt),
                 EntraitOpt::Debug(opt) => debug = Some(opt),
                 EntraitOpt::Export(opt) => export = Some(opt),
                 EntraitOpt::MaybeSend(send) => future_send = Some(send),
                 EntraitOpt::MockApi(ident) => mock_api = Some(ident),
                 EntraitOpt::Unimock(opt) => unimock = Some(opt),
                 EntraitOpt::Mockall(opt) => mockall = Some(opt),
-                opt => return Err(syn::Error::new(opt.span(), "Unsupported option")),
+                EntraitOpt::Recursive(opt) => recursive = Some(opt),
+                EntraitOpt::DelegateBy(kind) => delegation_kind = Some(kind),
             };
         }
 
@@ -50,6 +81,10 @@ impl Parse for EntraitFnAttr {
             opts: Opts {
                 default_span,
                 no_deps,
+                concrete,
+                context,
+                leaf_accessor,
+                method_generics,
                 debug,
                 export,
                 future_send,
@@ -57,7 +92,113 @@ impl Parse for EntraitFnAttr {
                 unimock,
                 mockall,
             },
+            recursive,
+            delegation_kind,
             crate_idents: CrateIdents::new(span),
         })
     }
 }
+
+/// The `#[entrait(..)]` attribute on a function inside an entraited module
+#[derive(Default)]
+pub struct ModFnAttr {
+    pub skip: bool,
+    pub rename: Option<syn::Ident>,
+    pub no_deps: Option<SpanOpt<bool>>,
+    pub future_send: Option<SpanOpt<FutureSend>>,
+    pub mock_api: Option<MockApiIdent>,
+}
+
+impl ModFnAttr {
+    /// Remove all entrait attributes from the function, and merge them into one.
+    pub fn extract(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Self> {
+        let mut mod_fn_attr = ModFnAttr::default();
+        let mut error: Option<syn::Error> = None;
+
+        attrs.retain(|attr| {
+            if !is_entrait_attr(attr) {
+                return true;
+            }
+
+            if let Err(err) =
+                attr.parse_args_with(|input: ParseStream| mod_fn_attr.parse_into(input))
+            {
+                match &mut error {
+                    Some(error) => error.combine(err),
+                    None => error = Some(err),
+                }
+            }
+
+            false
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(mod_fn_attr),
+        }
+    }
+
+    /// Whether the attribute holds per-function options, as opposed to a standalone `entrait` invocation
+    pub fn is_options_attr(attr: &syn::Attribute) -> bool {
+        is_entrait_attr(attr)
+            && attr
+                .parse_args_with(|input: ParseStream| ModFnAttr::default().parse_into(input))
+                .is_ok()
+    }
+
+    fn parse_into(&mut self, input: ParseStream) -> syn::Result<()> {
+        loop {
+            let fork = input.fork();
+            let ident = fork
+                .parse::<syn::Ident>()
+                .ok()
+                .map(|ident| ident.to_string());
+
+            match ident.as_deref() {
+                Some("skip") => {
+                    input.parse::<syn::Ident>()?;
+                    self.skip = true;
+                }
+                Some("rename") => {
+                    input.parse::<syn::Ident>()?;
+                    input.parse::<syn::token::Eq>()?;
+                    self.rename = Some(input.parse()?);
+                }
+                _ => match input.parse::<EntraitOpt>()? {
+                    EntraitOpt::NoDeps(opt) => self.no_deps = Some(opt),
+                    EntraitOpt::MaybeSend(send) => self.future_send = Some(send),
+                    EntraitOpt::MockApi(ident) => self.mock_api = Some(ident),
+                    opt => {
+                        return Err(syn::Error::new(
+                            opt.span(),
+                            "Unsupported option for a function inside a module",
+                        ))
+                    }
+                },
+            }
+
+            if input.is_empty() {
+                return Ok(());
+            }
+            input.parse::<syn::token::Comma>()?;
+        }
+    }
+
+    /// The options of the module, with the overrides of this function applied
+    pub fn fn_opts(&self, mod_opts: &Opts) -> Opts {
+        Opts {
+            no_deps: self.no_deps.or(mod_opts.no_deps),
+            future_send: self.future_send.or(mod_opts.future_send),
+            mock_api: None,
+            ..*mod_opts
+        }
+    }
+}
+
+pub fn is_entrait_attr(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .map(|segment| segment.ident == "entrait")
+        .unwrap_or(false)
+}
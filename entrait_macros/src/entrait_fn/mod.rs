@@ -7,15 +7,19 @@ pub mod input_attr;
 
 use crate::analyze_generics;
 use crate::analyze_generics::GenericsAnalyzer;
+use crate::analyze_generics::TraitFn;
 use crate::analyze_generics::TraitFnAnalyzer;
+use crate::entrait_trait;
 use crate::fn_delegation_codegen;
 use crate::generics;
 use crate::input::FnInputMode;
 use crate::input::{InputFn, InputMod, ModItem};
+use crate::opt::{Delegate, MockApiIdent, Opts, Recursive, SpanOpt};
 use crate::signature;
 use crate::sub_attributes::analyze_sub_attributes;
 use crate::trait_codegen::Supertraits;
 use crate::trait_codegen::TraitCodegen;
+use crate::trait_codegen::TraitVisibility;
 use input_attr::*;
 
 use proc_macro2::TokenStream;
@@ -24,24 +28,57 @@ use quote::quote;
 use crate::analyze_generics::detect_trait_dependency_mode;
 
 pub fn entrait_for_single_fn(attr: &EntraitFnAttr, input_fn: InputFn) -> syn::Result<TokenStream> {
+    if let Some(recursive) = attr.recursive {
+        return Err(syn::Error::new(
+            recursive.1,
+            "`recursive` is only supported for modules",
+        ));
+    }
+
+    gen_single_fn(attr, input_fn, None)
+}
+
+/// Entrait a generated accessor fn, with a trait method named `method_ident` instead of the fn ident
+pub fn entrait_for_accessor_fn(
+    attr: &EntraitFnAttr,
+    input_fn: InputFn,
+    method_ident: &syn::Ident,
+) -> syn::Result<TokenStream> {
+    gen_single_fn(attr, input_fn, Some(method_ident))
+}
+
+fn gen_single_fn(
+    attr: &EntraitFnAttr,
+    input_fn: InputFn,
+    method_ident: Option<&syn::Ident>,
+) -> syn::Result<TokenStream> {
     let fn_input_mode = FnInputMode::SingleFn(&input_fn.fn_sig.ident);
     let mut generics_analyzer = GenericsAnalyzer::new();
 
-    let trait_fns = [TraitFnAnalyzer {
+    let mut trait_fn = TraitFnAnalyzer {
         impl_receiver_kind: signature::ImplReceiverKind::SelfRef,
         trait_span: attr.trait_ident.span(),
         crate_idents: &attr.crate_idents,
         opts: &attr.opts,
     }
-    .analyze(input_fn.input_sig(), &mut generics_analyzer)?];
+    .analyze(input_fn.input_sig(), &mut generics_analyzer)?;
+
+    if let Some(method_ident) = method_ident {
+        trait_fn.renamed_from = Some(input_fn.fn_sig.ident.clone());
+        trait_fn.entrait_sig.sig.ident = method_ident.clone();
+    }
+
+    let trait_fns = [trait_fn];
     let sub_attributes = analyze_sub_attributes(&input_fn.fn_attrs);
 
     let trait_dependency_mode = detect_trait_dependency_mode(
         &fn_input_mode,
         &trait_fns,
+        &attr.opts,
         &attr.crate_idents,
         attr.trait_ident.span(),
     )?;
+    check_context_deps(&attr.opts, &trait_dependency_mode)?;
     let trait_generics = generics_analyzer.into_trait_generics();
     let trait_def = TraitCodegen {
         opts: &attr.opts,
@@ -49,28 +86,52 @@ pub fn entrait_for_single_fn(attr: &EntraitFnAttr, input_fn: InputFn) -> syn::Re
         trait_indirection: generics::TraitIndirection::Plain,
         trait_dependency_mode: &trait_dependency_mode,
         sub_attributes: &sub_attributes,
+        on_unimplemented: Some(entrait_trait::fn_trait_on_unimplemented(
+            &attr.trait_ident,
+            attr.delegation(),
+            &trait_fns,
+            &sub_attributes,
+            &trait_dependency_mode,
+        )),
     }
     .gen_trait_def(
         &attr.trait_visibility,
         &attr.trait_ident,
         &trait_generics,
         &Supertraits::None,
+        &[],
         &trait_fns,
         &fn_input_mode,
     )?;
 
-    let impl_block = fn_delegation_codegen::FnDelegationCodegen {
-        opts: &attr.opts,
-        crate_idents: &attr.crate_idents,
-        trait_ref: &attr.trait_ident,
-        trait_span: attr.trait_ident.span(),
-        impl_indirection: generics::ImplIndirection::None,
-        trait_generics: &trait_generics,
-        fn_input_mode: &fn_input_mode,
-        trait_dependency_mode: &trait_dependency_mode,
-        sub_attributes: &sub_attributes,
-    }
-    .gen_impl_block(&trait_fns);
+    let impl_block = match attr.delegation() {
+        Some(delegation_kind) => {
+            check_delegation_deps(delegation_kind, &trait_dependency_mode)?;
+            entrait_trait::output_tokens_for_fns(
+                &attr.trait_visibility,
+                &attr.trait_ident,
+                &trait_generics,
+                &Supertraits::None,
+                &trait_fns,
+                delegation_kind,
+                &attr.opts,
+                &sub_attributes,
+            )?
+        }
+        None => fn_delegation_codegen::FnDelegationCodegen {
+            opts: &attr.opts,
+            crate_idents: &attr.crate_idents,
+            trait_ref: &attr.trait_ident,
+            trait_span: attr.trait_ident.span(),
+            impl_indirection: generics::ImplIndirection::None,
+            trait_generics: &trait_generics,
+            fn_input_mode: &fn_input_mode,
+            trait_dependency_mode: &trait_dependency_mode,
+            sub_attributes: &sub_attributes,
+            assoc_items: &[],
+        }
+        .gen_impl_block(&trait_fns),
+    };
 
     let InputFn {
         fn_attrs,
@@ -92,59 +153,306 @@ pub fn entrait_for_single_fn(attr: &EntraitFnAttr, input_fn: InputFn) -> syn::Re
 }
 
 pub fn entrait_for_mod(attr: &EntraitFnAttr, input_mod: InputMod) -> syn::Result<TokenStream> {
+    if attr.recursive_value() != Recursive::No && attr.opts.mockall.is_some() {
+        return Err(syn::Error::new(
+            attr.opts.default_option(attr.recursive, Recursive::No).1,
+            "`recursive` is not supported with mockall, as mockall does not implement the nested traits",
+        ));
+    }
+
+    let trait_vis = &attr.trait_visibility;
+    let trait_ident = &attr.trait_ident;
+    let mod_ident = input_mod.ident.clone();
+
+    // The generated delegation traits are used outside the module too
+    let (module, delegation_trait_idents) = gen_mod(attr, trait_ident, &attr.opts, input_mod, 1)?;
+
+    Ok(quote! {
+        #module
+
+        #trait_vis use #mod_ident::#trait_ident;
+        #(#trait_vis use #mod_ident::#delegation_trait_idents;)*
+    })
+}
+
+/// With `delegate_by`, the functions are implemented for `Impl<T>` through the generated `{Trait}Impl`,
+/// so there is no concrete type to implement the trait for.
+fn check_delegation_deps(
+    delegation_kind: &SpanOpt<Delegate>,
+    trait_dependency_mode: &generics::TraitDependencyMode,
+) -> syn::Result<()> {
+    match trait_dependency_mode {
+        generics::TraitDependencyMode::Generic(_) => Ok(()),
+        generics::TraitDependencyMode::Concrete(ty) => Err(syn::Error::new(
+            delegation_kind.1,
+            format!(
+                "`delegate_by` requires generic dependencies, but `{}` is a concrete dependency",
+                quote::ToTokens::to_token_stream(ty),
+            ),
+        )),
+    }
+}
+
+/// `context` and `leaf_accessor` mark a leaf trait, which only functions over a concrete dependency generate.
+fn check_context_deps(
+    opts: &Opts,
+    trait_dependency_mode: &generics::TraitDependencyMode,
+) -> syn::Result<()> {
+    if let generics::TraitDependencyMode::Generic(_) = trait_dependency_mode {
+        if let Some(SpanOpt(true, span)) = opts.context {
+            return Err(syn::Error::new(
+                span,
+                "`context` requires a concrete dependency, i.e. the context type of `Scoped`",
+            ));
+        }
+        if let Some(SpanOpt(true, span)) = opts.leaf_accessor {
+            return Err(syn::Error::new(
+                span,
+                "`leaf_accessor` requires a concrete dependency",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Per-fn `mock_api` names only exist as aliases into a unimock mock API
+fn check_fn_mock_apis(mod_opts: &Opts, mod_fn_attrs: &[ModFnAttr]) -> syn::Result<()> {
+    if mod_opts.default_option(mod_opts.unimock, false).0 {
+        return Ok(());
+    }
+
+    match mod_fn_attrs
+        .iter()
+        .find_map(|mod_fn_attr| mod_fn_attr.mock_api.as_ref())
+    {
+        Some(MockApiIdent(ident)) => Err(syn::Error::new(
+            ident.span(),
+            "`mock_api` on a function inside a module requires `unimock`",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Private functions are not part of the trait, so per-function options on them would have no effect
+fn check_private_fn_attrs(input_mod: &InputMod) -> syn::Result<()> {
+    match input_mod
+        .items
+        .iter()
+        .filter_map(ModItem::private_fn_attrs)
+        .flatten()
+        .find(|attr| ModFnAttr::is_options_attr(attr))
+    {
+        Some(attr) => Err(syn::Error::new_spanned(
+            attr,
+            "Per-function `entrait` options only apply to non-private functions, which are part of the module's trait",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Generate the module, including its trait. `depth` is the nesting level of the trait definition,
+/// relative to the scope where the outermost entraited module is defined.
+fn gen_mod(
+    attr: &EntraitFnAttr,
+    trait_ident: &syn::Ident,
+    mod_opts: &Opts,
+    mut input_mod: InputMod,
+    depth: usize,
+) -> syn::Result<(TokenStream, Vec<syn::Ident>)> {
+    let trait_visibility = nested_trait_visibility(&attr.trait_visibility, depth);
+
+    let mod_fn_attrs = input_mod
+        .items
+        .iter_mut()
+        .filter_map(ModItem::filter_pub_fn_mut)
+        .map(|input_fn| ModFnAttr::extract(&mut input_fn.fn_attrs))
+        .collect::<syn::Result<Vec<_>>>()?;
+    check_fn_mock_apis(mod_opts, &mod_fn_attrs)?;
+    check_private_fn_attrs(&input_mod)?;
+
+    let opts = Opts {
+        mock_api: mod_opts.mock_api.clone().or_else(|| {
+            // Per-fn mock APIs are aliases into a module-level mock API, so one must exist
+            if mod_fn_attrs
+                .iter()
+                .any(|mod_fn_attr| mod_fn_attr.mock_api.is_some())
+            {
+                Some(MockApiIdent(quote::format_ident!("__{}Mock", trait_ident)))
+            } else {
+                None
+            }
+        }),
+        ..*mod_opts
+    };
+    let opts = &opts;
+
+    let submodules = if attr.recursive_value() != Recursive::No {
+        gen_submodules(attr, opts, &mut input_mod, depth)?
+    } else {
+        vec![]
+    };
+
     let fn_input_mode = FnInputMode::Module(&input_mod.ident);
     let mut generics_analyzer = analyze_generics::GenericsAnalyzer::new();
     let trait_fns = input_mod
         .items
         .iter()
         .filter_map(ModItem::filter_pub_fn)
-        .map(|input_fn| {
-            TraitFnAnalyzer {
+        .zip(mod_fn_attrs.iter())
+        .filter(|(_, mod_fn_attr)| !mod_fn_attr.skip)
+        .map(|(input_fn, mod_fn_attr)| {
+            let mut trait_fn = TraitFnAnalyzer {
                 impl_receiver_kind: signature::ImplReceiverKind::SelfRef,
-                trait_span: attr.trait_ident.span(),
+                trait_span: trait_ident.span(),
                 crate_idents: &attr.crate_idents,
-                opts: &attr.opts,
+                opts: &mod_fn_attr.fn_opts(opts),
             }
-            .analyze(input_fn.input_sig(), &mut generics_analyzer)
+            .analyze(input_fn.input_sig(), &mut generics_analyzer)?;
+
+            if let Some(rename) = &mod_fn_attr.rename {
+                trait_fn.renamed_from = Some(input_fn.fn_sig.ident.clone());
+                trait_fn.entrait_sig.sig.ident = rename.clone();
+            }
+
+            Ok(trait_fn)
         })
         .collect::<syn::Result<Vec<_>>>()?;
+
     let sub_attributes = analyze_sub_attributes(&input_mod.attrs);
 
     let trait_dependency_mode = detect_trait_dependency_mode(
         &fn_input_mode,
         &trait_fns,
+        opts,
         &attr.crate_idents,
-        attr.trait_ident.span(),
+        trait_ident.span(),
     )?;
+    check_context_deps(opts, &trait_dependency_mode)?;
 
     let trait_generics = generics_analyzer.into_trait_generics();
+
+    // The submodule traits become supertraits, which the delegating impl also has to require
+    let (supertraits, impl_generics) = if submodules.is_empty()
+        || attr.recursive_value() == Recursive::Flat
+    {
+        (Supertraits::None, trait_generics.clone())
+    } else {
+        let bounds: syn::punctuated::Punctuated<syn::TypeParamBound, syn::token::Plus> = submodules
+            .iter()
+            .map(|submodule| -> syn::TypeParamBound {
+                let mod_ident = &submodule.mod_ident;
+                let trait_ident = &submodule.trait_ident;
+                syn::parse_quote! { #mod_ident::#trait_ident }
+            })
+            .collect();
+
+        let mut impl_generics = trait_generics.clone();
+        impl_generics
+            .where_predicates
+            .push(syn::parse_quote! { Self: #bounds });
+
+        (
+            Supertraits::Some {
+                colon_token: syn::token::Colon(trait_ident.span()),
+                bounds,
+            },
+            impl_generics,
+        )
+    };
+
     let trait_def = TraitCodegen {
-        opts: &attr.opts,
+        opts,
         crate_idents: &attr.crate_idents,
         trait_indirection: generics::TraitIndirection::Plain,
         trait_dependency_mode: &trait_dependency_mode,
         sub_attributes: &sub_attributes,
+        on_unimplemented: Some(entrait_trait::fn_trait_on_unimplemented(
+            trait_ident,
+            attr.delegation(),
+            &trait_fns,
+            &sub_attributes,
+            &trait_dependency_mode,
+        )),
     }
     .gen_trait_def(
-        &attr.trait_visibility,
-        &attr.trait_ident,
+        &trait_visibility,
+        trait_ident,
         &trait_generics,
-        &Supertraits::None,
+        &supertraits,
+        &[],
         &trait_fns,
         &fn_input_mode,
     )?;
-    let impl_block = fn_delegation_codegen::FnDelegationCodegen {
-        opts: &attr.opts,
-        crate_idents: &attr.crate_idents,
-        trait_ref: &attr.trait_ident,
-        trait_span: attr.trait_ident.span(),
-        impl_indirection: generics::ImplIndirection::None,
-        trait_generics: &trait_generics,
-        fn_input_mode: &fn_input_mode,
-        trait_dependency_mode: &trait_dependency_mode,
-        sub_attributes: &sub_attributes,
-    }
-    .gen_impl_block(&trait_fns);
+    let impl_block = match attr.delegation() {
+        Some(delegation_kind) => {
+            check_delegation_deps(delegation_kind, &trait_dependency_mode)?;
+            entrait_trait::output_tokens_for_fns(
+                &trait_visibility,
+                trait_ident,
+                &trait_generics,
+                &supertraits,
+                &trait_fns,
+                delegation_kind,
+                opts,
+                &sub_attributes,
+            )?
+        }
+        None => fn_delegation_codegen::FnDelegationCodegen {
+            opts,
+            crate_idents: &attr.crate_idents,
+            trait_ref: trait_ident,
+            trait_span: trait_ident.span(),
+            impl_indirection: generics::ImplIndirection::None,
+            trait_generics: &impl_generics,
+            fn_input_mode: &fn_input_mode,
+            trait_dependency_mode: &trait_dependency_mode,
+            sub_attributes: &sub_attributes,
+            assoc_items: &[],
+        }
+        .gen_impl_block(&trait_fns),
+    };
+
+    let mock_api_aliases = gen_mock_api_aliases(
+        &trait_visibility,
+        opts,
+        &fn_input_mode,
+        mod_fn_attrs
+            .iter()
+            .filter(|mod_fn_attr| !mod_fn_attr.skip)
+            .zip(trait_fns.iter()),
+    );
+
+    let submodules = submodules
+        .iter()
+        .map(|submodule| {
+            let Submodule {
+                tokens,
+                mod_ident,
+                trait_ident,
+            } = submodule;
+            let reexport_visibility = TraitVisibility {
+                visibility: &nested_trait_visibility(&attr.trait_visibility, depth),
+                fn_input_mode: &fn_input_mode,
+            };
+
+            quote! {
+                #tokens
+
+                #[allow(unused_imports)]
+                #reexport_visibility use #mod_ident::#trait_ident;
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let delegation_trait_idents = match attr.delegation() {
+        Some(delegation_kind) => entrait_trait::fn_delegation_trait_idents(
+            trait_ident,
+            delegation_kind,
+            &trait_fns,
+            &sub_attributes,
+        ),
+        None => vec![],
+    };
 
     let InputMod {
         attrs,
@@ -155,18 +463,132 @@ pub fn entrait_for_mod(attr: &EntraitFnAttr, input_mod: InputMod) -> syn::Result
         ..
     } = input_mod;
 
-    let trait_vis = &attr.trait_visibility;
-    let trait_ident = &attr.trait_ident;
+    Ok((
+        quote! {
+            #(#attrs)*
+            #vis #mod_token #mod_ident {
+                #(#items)*
+                #(#submodules)*
 
-    Ok(quote! {
-        #(#attrs)*
-        #vis #mod_token #mod_ident {
-            #(#items)*
+                #trait_def
+                #impl_block
+                #(#mock_api_aliases)*
+            }
+        },
+        delegation_trait_idents,
+    ))
+}
 
-            #trait_def
-            #impl_block
+/// A nested module generated with the `recursive` option
+struct Submodule {
+    tokens: TokenStream,
+    mod_ident: syn::Ident,
+    trait_ident: syn::Ident,
+}
+
+/// Take the non-private inline submodules out of the module, and generate a trait for each of them.
+/// Submodules with their own `entrait` attribute are left alone.
+fn gen_submodules(
+    attr: &EntraitFnAttr,
+    opts: &Opts,
+    input_mod: &mut InputMod,
+    depth: usize,
+) -> syn::Result<Vec<Submodule>> {
+    let mut submodules = vec![];
+    let mut items = vec![];
+    for item in std::mem::take(&mut input_mod.items) {
+        match item.parse_pub_inline_mod().transpose()? {
+            Some(submodule) if !submodule.attrs.iter().any(is_entrait_attr) => {
+                submodules.push(submodule)
+            }
+            _ => items.push(item),
         }
+    }
+    input_mod.items = items;
 
-        #trait_vis use #mod_ident::#trait_ident;
+    submodules
+        .into_iter()
+        .map(|submodule| {
+            let mod_ident = submodule.ident.clone();
+            let trait_ident = submodule_trait_ident(&mod_ident);
+            let sub_opts = Opts {
+                mock_api: opts
+                    .mock_api
+                    .as_ref()
+                    .map(|_| MockApiIdent(quote::format_ident!("{}Mock", trait_ident))),
+                ..*opts
+            };
+
+            Ok(Submodule {
+                tokens: gen_mod(attr, &trait_ident, &sub_opts, submodule, depth + 1)?.0,
+                mod_ident,
+                trait_ident,
+            })
+        })
+        .collect()
+}
+
+/// `users` becomes `Users`, `order_lines` becomes `OrderLines`
+fn submodule_trait_ident(mod_ident: &syn::Ident) -> syn::Ident {
+    let mod_name = mod_ident.to_string();
+    let trait_name: String = mod_name
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    syn::Ident::new(&trait_name, mod_ident.span())
+}
+
+/// The visibility of a trait defined `depth` modules below the scope of the outermost entraited module.
+///
+/// A private trait must be visible in that scope, but `pub(super)` only reaches one level up.
+fn nested_trait_visibility(visibility: &syn::Visibility, depth: usize) -> syn::Visibility {
+    match visibility {
+        syn::Visibility::Inherited if depth > 1 => {
+            let supers = (0..depth).map(|_| syn::token::Super::default());
+            syn::parse_quote! { pub(in #(#supers)::*) }
+        }
+        _ => visibility.clone(),
+    }
+}
+
+/// Per-fn `mock_api` names are re-exports of the `MockFn`s in the module-level mock API
+fn gen_mock_api_aliases<'a>(
+    trait_visibility: &syn::Visibility,
+    opts: &Opts,
+    fn_input_mode: &FnInputMode,
+    fns: impl Iterator<Item = (&'a ModFnAttr, &'a TraitFn)>,
+) -> Vec<TokenStream> {
+    if !opts.default_option(opts.unimock, false).0 {
+        return vec![];
+    }
+
+    fns.filter_map(|(mod_fn_attr, trait_fn)| {
+        let MockApiIdent(alias) = mod_fn_attr.mock_api.as_ref()?;
+        let MockApiIdent(mock_api) = opts.mock_api.as_ref()?;
+        let method_ident = &trait_fn.sig().ident;
+        let visibility = TraitVisibility {
+            visibility: trait_visibility,
+            fn_input_mode,
+        };
+        let opt_cfg_test = if opts.export_value() {
+            None
+        } else {
+            Some(quote! { #[cfg(test)] })
+        };
+
+        Some(quote! {
+            #opt_cfg_test
+            #visibility use #mock_api::#method_ident as #alias;
+        })
     })
+    .collect()
 }
@@ -51,6 +51,10 @@ impl Parse for EntraitSimpleImplAttr {
             opts: Opts {
                 default_span: span,
                 no_deps: None,
+                concrete: None,
+                context: None,
+                leaf_accessor: None,
+                method_generics: None,
                 debug,
                 export: None,
                 future_send: None,
@@ -96,6 +100,10 @@ impl Parse for EntraitImplAttr {
             opts: Opts {
                 default_span: span,
                 no_deps: None,
+                concrete: None,
+                context: None,
+                leaf_accessor: None,
+                method_generics: None,
                 debug,
                 export: None,
                 future_send: None,
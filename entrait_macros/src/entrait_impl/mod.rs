@@ -2,16 +2,19 @@ pub mod input_attr;
 
 use crate::analyze_generics;
 use crate::analyze_generics::detect_trait_dependency_mode;
+use crate::analyze_generics::TraitFn;
 use crate::analyze_generics::TraitFnAnalyzer;
 use crate::fn_delegation_codegen;
 use crate::generics;
 use crate::input::ImplItem;
+use crate::input::InputFn;
 use crate::input::InputImpl;
 use crate::signature;
 use crate::sub_attributes::analyze_sub_attributes;
 use crate::sub_attributes::SubAttribute;
 
 use quote::quote;
+use quote::ToTokens;
 use syn::spanned::Spanned;
 
 use self::input_attr::EntraitSimpleImplAttr;
@@ -23,7 +26,8 @@ pub fn output_tokens_for_impl(
         attrs,
         unsafety,
         impl_token,
-        trait_path,
+        generics: impl_generics,
+        mut trait_path,
         for_token: _,
         self_ty,
         brace_token: _,
@@ -36,12 +40,28 @@ pub fn output_tokens_for_impl(
         .map(|segment| segment.span())
         .unwrap_or_else(proc_macro2::Span::call_site);
 
+    // The generic arguments of the trait are merged with `EntraitT` in the generated impl
+    let trait_args = match trait_path
+        .segments
+        .last_mut()
+        .map(|segment| std::mem::replace(&mut segment.arguments, syn::PathArguments::None))
+    {
+        Some(syn::PathArguments::AngleBracketed(trait_args)) => Some(trait_args),
+        Some(syn::PathArguments::Parenthesized(arguments)) => {
+            return Err(syn::Error::new(
+                arguments.span(),
+                "Expected angle bracketed generic arguments",
+            ))
+        }
+        Some(syn::PathArguments::None) | None => None,
+    };
+
     let mut generics_analyzer = analyze_generics::GenericsAnalyzer::new();
     let trait_fns = items
         .iter()
         .filter_map(ImplItem::filter_fn)
         .map(|input_fn| {
-            TraitFnAnalyzer {
+            let trait_fn = TraitFnAnalyzer {
                 impl_receiver_kind: match attr.impl_kind {
                     ImplKind::Static => signature::ImplReceiverKind::StaticImpl,
                     ImplKind::DynRef => signature::ImplReceiverKind::DynamicImpl,
@@ -50,7 +70,11 @@ pub fn output_tokens_for_impl(
                 crate_idents: &attr.crate_idents,
                 opts: &attr.opts,
             }
-            .analyze(input_fn.input_sig(), &mut generics_analyzer)
+            .analyze(input_fn.input_sig(), &mut generics_analyzer)?;
+
+            check_deps_reference(input_fn, &trait_fn, attr.impl_kind)?;
+
+            Ok(trait_fn)
         })
         .collect::<syn::Result<Vec<_>>>()?;
     let sub_attributes = analyze_sub_attributes(&attrs);
@@ -58,14 +82,29 @@ pub fn output_tokens_for_impl(
     let trait_generics = generics_analyzer.into_trait_generics();
 
     let fn_input_mode = crate::input::FnInputMode::ImplBlock(&self_ty);
-    let trait_dependency_mode =
-        detect_trait_dependency_mode(&fn_input_mode, &trait_fns, &attr.crate_idents, trait_span)?;
+    let trait_dependency_mode = detect_trait_dependency_mode(
+        &fn_input_mode,
+        &trait_fns,
+        &attr.opts,
+        &attr.crate_idents,
+        trait_span,
+    )?;
 
     let impl_indirection = match attr.impl_kind {
-        ImplKind::Static => generics::ImplIndirection::Static { ty: &self_ty },
-        ImplKind::DynRef => generics::ImplIndirection::Dynamic { ty: &self_ty },
+        ImplKind::Static => generics::ImplIndirection::Static {
+            ty: &self_ty,
+            generics: &impl_generics,
+            trait_args: trait_args.as_ref(),
+        },
+        ImplKind::DynRef => generics::ImplIndirection::Dynamic {
+            ty: &self_ty,
+            generics: &impl_generics,
+            trait_args: trait_args.as_ref(),
+        },
     };
 
+    let assoc_items: Vec<_> = items.iter().filter(|item| item.is_assoc()).collect();
+
     let impl_block = fn_delegation_codegen::FnDelegationCodegen {
         opts: &attr.opts,
         crate_idents: &attr.crate_idents,
@@ -76,6 +115,7 @@ pub fn output_tokens_for_impl(
         fn_input_mode: &fn_input_mode,
         trait_dependency_mode: &trait_dependency_mode,
         sub_attributes: &sub_attributes,
+        assoc_items: &assoc_items,
     }
     .gen_impl_block(&trait_fns);
 
@@ -83,11 +123,131 @@ pub fn output_tokens_for_impl(
         .iter()
         .filter(|sub_attr| !matches!(sub_attr, SubAttribute::AsyncTrait(_)));
 
+    let (inherent_generics, items) = gen_inherent_items(impl_generics, &self_ty, items);
+    let where_clause = &inherent_generics.where_clause;
+
     Ok(quote! {
         #(#inherent_sub_attrs)*
-        #unsafety #impl_token #self_ty {
+        #unsafety #impl_token #inherent_generics #self_ty #where_clause {
             #(#items)*
         }
         #impl_block
     })
 }
+
+/// Params of the impl block that only appear in the trait arguments, like the `K` in
+/// `impl<K> StoreImpl<K> for MyStore`, would be unconstrained in the inherent impl.
+/// Those params, and the where clause, are moved to each function instead.
+fn gen_inherent_items(
+    mut impl_generics: syn::Generics,
+    self_ty: &syn::Type,
+    items: Vec<ImplItem>,
+) -> (syn::Generics, Vec<ImplItem>) {
+    let mut self_ty_idents = vec![];
+    collect_idents(self_ty.to_token_stream(), &mut self_ty_idents);
+
+    let (kept_params, moved_params): (Vec<_>, Vec<_>) =
+        impl_generics.params.into_iter().partition(|param| {
+            let ident = match param {
+                syn::GenericParam::Lifetime(lifetime_param) => &lifetime_param.lifetime.ident,
+                syn::GenericParam::Type(type_param) => &type_param.ident,
+                syn::GenericParam::Const(const_param) => &const_param.ident,
+            };
+            self_ty_idents.contains(ident)
+        });
+    impl_generics.params = kept_params.into_iter().collect();
+
+    let items = items.into_iter().filter(|item| !item.is_assoc());
+    if moved_params.is_empty() {
+        return (impl_generics, items.collect());
+    }
+
+    let where_clause = impl_generics.where_clause.take();
+
+    let items = items
+        .map(|mut item| {
+            if let ImplItem::Fn(input_fn) = &mut item {
+                let fn_generics = &mut input_fn.fn_sig.generics;
+                let is_lifetime =
+                    |param: &&syn::GenericParam| matches!(param, syn::GenericParam::Lifetime(_));
+                let fn_params = std::mem::take(&mut fn_generics.params);
+
+                // Lifetimes must be declared first
+                fn_generics.params = moved_params
+                    .iter()
+                    .filter(is_lifetime)
+                    .chain(fn_params.iter().filter(is_lifetime))
+                    .chain(moved_params.iter().filter(|param| !is_lifetime(param)))
+                    .chain(fn_params.iter().filter(|param| !is_lifetime(param)))
+                    .cloned()
+                    .collect();
+                if fn_generics.lt_token.is_none() {
+                    fn_generics.lt_token = Some(Default::default());
+                    fn_generics.gt_token = Some(Default::default());
+                }
+                if let Some(where_clause) = &where_clause {
+                    fn_generics
+                        .make_where_clause()
+                        .predicates
+                        .extend(where_clause.predicates.iter().cloned());
+                }
+            }
+            item
+        })
+        .collect();
+
+    (impl_generics, items)
+}
+
+fn collect_idents(stream: proc_macro2::TokenStream, idents: &mut Vec<proc_macro2::Ident>) {
+    for token_tree in stream {
+        match token_tree {
+            proc_macro2::TokenTree::Ident(ident) => idents.push(ident),
+            proc_macro2::TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+/// A concrete dependency is projected out of `&Impl<T>`, so it can only be borrowed.
+/// With `ref` delegation, `Impl<T>` is only available by shared reference, not by `&mut` or `Arc`.
+fn check_deps_reference(
+    input_fn: &InputFn,
+    trait_fn: &TraitFn,
+    impl_kind: ImplKind,
+) -> syn::Result<()> {
+    let deps_ty = match input_fn.fn_sig.inputs.first() {
+        Some(syn::FnArg::Typed(pat_type)) => pat_type.ty.as_ref(),
+        _ => return Ok(()),
+    };
+
+    match (&trait_fn.deps, impl_kind, deps_ty) {
+        (generics::FnDeps::NoDeps, _, _) => Ok(()),
+        (generics::FnDeps::Concrete(_), _, syn::Type::Reference(type_reference))
+            if type_reference.mutability.is_some() =>
+        {
+            Err(syn::Error::new(
+                type_reference.span(),
+                "A concrete dependency in an impl block must be taken by shared reference",
+            ))
+        }
+        (generics::FnDeps::Concrete(_), _, syn::Type::Reference(_)) => Ok(()),
+        (generics::FnDeps::Concrete(_), _, ty) => Err(syn::Error::new(
+            ty.span(),
+            "A concrete dependency in an impl block must be taken by reference",
+        )),
+        (_, ImplKind::DynRef, syn::Type::Reference(type_reference))
+            if type_reference.mutability.is_some() =>
+        {
+            Err(syn::Error::new(
+                type_reference.span(),
+                "`&mut` dependencies are not supported with `ref` delegation",
+            ))
+        }
+        (_, ImplKind::DynRef, ty) if generics::arc_deps(ty).is_some() => Err(syn::Error::new(
+            ty.span(),
+            "`Arc` dependencies are not supported with `ref` delegation",
+        )),
+        _ => Ok(()),
+    }
+}
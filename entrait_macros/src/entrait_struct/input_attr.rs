@@ -0,0 +1,93 @@
+use crate::entrait_fn::input_attr::EntraitFnAttr;
+use crate::opt::*;
+
+use syn::parse::{Parse, ParseStream};
+
+/// The `entrait` invocation for structs.
+///
+/// The options are defaults for the traits generated for each field.
+pub struct EntraitStructAttr {
+    pub opts: Opts,
+}
+
+impl Parse for EntraitStructAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        let mut context = None;
+        let mut leaf_accessor = None;
+        let mut debug = None;
+        let mut export = None;
+        let mut unimock = None;
+        let mut mockall = None;
+
+        if !input.is_empty() {
+            loop {
+                match input.parse::<EntraitOpt>()? {
+                    EntraitOpt::Context(opt) => context = Some(opt),
+                    EntraitOpt::LeafAccessor(opt) => leaf_accessor = Some(opt),
+                    EntraitOpt::Debug(opt) => debug = Some(opt),
+                    EntraitOpt::Export(opt) => export = Some(opt),
+                    EntraitOpt::Unimock(opt) => unimock = Some(opt),
+                    EntraitOpt::Mockall(opt) => mockall = Some(opt),
+                    entrait_opt => {
+                        return Err(syn::Error::new(entrait_opt.span(), "Unsupported option"))
+                    }
+                };
+
+                if input.peek(syn::token::Comma) {
+                    input.parse::<syn::token::Comma>()?;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        Ok(Self {
+            opts: Opts {
+                default_span: span,
+                no_deps: None,
+                concrete: None,
+                context,
+                leaf_accessor,
+                method_generics: None,
+                debug,
+                export,
+                future_send: None,
+                mock_api: None,
+                unimock,
+                mockall,
+            },
+        })
+    }
+}
+
+/// The `#[entrait(..)]` attribute on a struct field
+pub struct FieldAttr(pub EntraitFnAttr);
+
+impl FieldAttr {
+    pub fn parse(attr: &syn::Attribute, struct_opts: &Opts) -> syn::Result<Self> {
+        let mut fn_attr: EntraitFnAttr = attr.parse_args()?;
+
+        let unsupported = [
+            fn_attr.opts.no_deps.map(|opt| opt.1),
+            fn_attr.opts.concrete.map(|opt| opt.1),
+            fn_attr.opts.future_send.map(|opt| opt.1),
+            fn_attr.recursive.map(|opt| opt.1),
+            fn_attr.delegation_kind.as_ref().map(|opt| opt.1),
+        ];
+        if let Some(span) = unsupported.into_iter().flatten().next() {
+            return Err(syn::Error::new(span, "Unsupported option for a field"));
+        }
+
+        let opts = &mut fn_attr.opts;
+        opts.context = opts.context.or(struct_opts.context);
+        opts.leaf_accessor = opts.leaf_accessor.or(struct_opts.leaf_accessor);
+        opts.debug = opts.debug.or(struct_opts.debug);
+        opts.export = opts.export.or(struct_opts.export);
+        opts.unimock = opts.unimock.or(struct_opts.unimock);
+        opts.mockall = opts.mockall.or(struct_opts.mockall);
+
+        Ok(Self(fn_attr))
+    }
+}
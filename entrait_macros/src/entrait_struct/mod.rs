@@ -0,0 +1,95 @@
+//! # entrait on structs
+//!
+//! Generates an accessor leaf trait for each annotated field.
+//! Every field is handled as a single `fn` with a concrete dependency on the struct.
+
+pub mod input_attr;
+
+use crate::entrait_fn;
+use crate::entrait_fn::input_attr::is_entrait_attr;
+use crate::input::InputFn;
+use input_attr::*;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+
+pub fn output_tokens(
+    attr: EntraitStructAttr,
+    mut item_struct: syn::ItemStruct,
+) -> syn::Result<TokenStream> {
+    if !item_struct.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_struct.generics.span(),
+            "Generic structs are not supported",
+        ));
+    }
+
+    let struct_ident = item_struct.ident.clone();
+    let mut accessors = vec![];
+
+    for field in item_struct.fields.iter_mut() {
+        let field_attrs = extract_field_attrs(&mut field.attrs);
+
+        for field_attr in field_attrs {
+            let FieldAttr(fn_attr) = FieldAttr::parse(&field_attr, &attr.opts)?;
+
+            let field_ident = match &field.ident {
+                Some(ident) => ident,
+                None => {
+                    return Err(syn::Error::new(
+                        field_attr.span(),
+                        "Only named fields can be entraited",
+                    ))
+                }
+            };
+
+            let input_fn = accessor_fn(&struct_ident, field_ident, &field.ty);
+
+            accessors.push(entrait_fn::entrait_for_accessor_fn(
+                &fn_attr,
+                input_fn,
+                field_ident,
+            )?);
+        }
+    }
+
+    Ok(quote! {
+        #item_struct
+        #(#accessors)*
+    })
+}
+
+fn extract_field_attrs(attrs: &mut Vec<syn::Attribute>) -> Vec<syn::Attribute> {
+    let mut field_attrs = vec![];
+
+    attrs.retain(|attr| {
+        if is_entrait_attr(attr) {
+            field_attrs.push(attr.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    field_attrs
+}
+
+/// `fn __Struct_field(s: &Struct) -> &FieldType { &s.field }`
+fn accessor_fn(struct_ident: &syn::Ident, field_ident: &syn::Ident, ty: &syn::Type) -> InputFn {
+    let span = field_ident.span();
+    let fn_ident = format_ident!("__{}_{}", struct_ident, field_ident);
+
+    InputFn {
+        fn_attrs: vec![syn::parse_quote! { #[allow(non_snake_case)] }],
+        fn_vis: syn::Visibility::Inherited,
+        fn_sig: syn::parse_quote_spanned! { span=>
+            fn #fn_ident(__struct: &#struct_ident) -> &#ty
+        },
+        fn_body: quote_spanned! { span=>
+            {
+                &__struct.#field_ident
+            }
+        },
+    }
+}
@@ -0,0 +1,234 @@
+//! The accessor traits of `delegate_by = Arc`, `Box`, `Swap` and `enum(..)`, i.e. `MailerArc` or `AsRepoChoice`.
+//!
+//! The application implements the accessor trait by handing out the delegation target,
+//! which `Impl<T>` then calls. With `Swap`, the target sits behind a lock and can be replaced at runtime.
+
+use super::candidates::choice_enum_ident;
+use super::input_attr::EntraitTraitAttr;
+use super::out_trait::OutTrait;
+use super::{dyn_bindings, dyn_target_ident, impl_trait_generics, DynShadow};
+use crate::analyze_generics::TraitFn;
+use crate::diagnostics::OnUnimplemented;
+use crate::generics;
+use crate::idents::GenericIdents;
+use crate::opt::{Delegate, RefDelegate, SpanOpt};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+
+/// The generated accessor trait of `delegate_by = Arc`, `Box`, `Swap` or `enum(..)`, e.g. `MailerArc` or `AsRepoChoice`,
+/// and its method, e.g. `mailer_arc` or `repo_choice`
+pub fn accessor_idents(
+    trait_ident: &syn::Ident,
+    ref_delegate: &RefDelegate,
+) -> Option<(syn::Ident, syn::Ident)> {
+    let (accessor_ident, method_suffix) = match ref_delegate {
+        RefDelegate::Enum(_) => (
+            quote::format_ident!("As{}Choice", trait_ident),
+            "choice".to_string(),
+        ),
+        _ => {
+            let pointer = ref_delegate.accessor_pointer()?;
+            (
+                quote::format_ident!("{}{}", trait_ident, pointer),
+                pointer.to_lowercase(),
+            )
+        }
+    };
+
+    Some((
+        accessor_ident,
+        quote::format_ident!("{}_{}", snake_case(trait_ident), method_suffix),
+    ))
+}
+
+/// i.e. `SendEmail` => `send_email`
+pub fn snake_case(trait_ident: &syn::Ident) -> String {
+    let mut snake = String::new();
+    let mut prev_lowercase = false;
+    for char in trait_ident.to_string().chars() {
+        if char.is_uppercase() && prev_lowercase {
+            snake.push('_');
+        }
+        prev_lowercase = char.is_lowercase() || char.is_ascii_digit();
+        snake.extend(char.to_lowercase());
+    }
+    snake
+}
+
+/// The provided method of the accessor trait of `delegate_by = Swap`, which swaps in a new implementation, e.g. `swap_mailer`
+pub fn swap_method_ident(trait_ident: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("swap_{}", snake_case(trait_ident))
+}
+
+/// With `delegate_by = Arc`, `Box`, `Swap` or `enum(..)`, `T` hands out the delegation target through a generated accessor trait
+pub fn gen_accessor_trait_def(
+    out_trait: &OutTrait,
+    generic_idents: &GenericIdents,
+    attr: &EntraitTraitAttr,
+    dyn_shadow: DynShadow,
+) -> Option<TokenStream> {
+    let ref_delegate = match &attr.delegation_kind {
+        Some(SpanOpt(Delegate::ByRef(ref_delegate), _)) => ref_delegate,
+        _ => return None,
+    };
+    let (accessor_ident, method_ident) = accessor_idents(&out_trait.ident, ref_delegate)?;
+    let vis = &out_trait.vis;
+    let wire = match (ref_delegate, &attr.impl_trait) {
+        (RefDelegate::Enum(_), _) => format!("enum = {}", out_trait.ident),
+        (_, Some(_)) => format!(
+            "{} = {}<Self>",
+            ref_delegate.accessor_pointer()?,
+            out_trait.ident
+        ),
+        (_, None) => format!("{} = {}", ref_delegate.accessor_pointer()?, out_trait.ident),
+    };
+    let on_unimplemented =
+        OnUnimplemented::accessor_trait(&accessor_ident, &out_trait.ident, &wire);
+
+    let entrait = &attr.crate_idents.entrait;
+
+    if let RefDelegate::Enum(_) = ref_delegate {
+        let enum_ident = choice_enum_ident(&out_trait.ident);
+        return Some(quote! {
+            #on_unimplemented
+            #vis trait #accessor_ident {
+                fn #method_ident(&self) -> &#enum_ident;
+            }
+
+            impl<EntraitApp: #accessor_ident, EntraitCtx> #accessor_ident for ::#entrait::Scoped<EntraitApp, EntraitCtx> {
+                fn #method_ident(&self) -> &#enum_ident {
+                    <EntraitApp as #accessor_ident>::#method_ident(::#entrait::Scoped::app(self))
+                }
+            }
+        });
+    }
+
+    let target_ident = dyn_target_ident(out_trait, attr, dyn_shadow);
+    let target_generics = match &attr.impl_trait {
+        Some(_) => impl_trait_generics(out_trait, generic_idents),
+        None => out_trait.generics.clone(),
+    };
+    let params = target_generics.trait_params();
+    let args = target_generics.arguments(&generics::ImplIndirection::None);
+    let where_clause = target_generics.trait_where_clause();
+    let dyn_bindings = dyn_bindings(out_trait);
+    let dyn_args = target_generics.dyn_arguments(&dyn_bindings);
+    let dyn_target = quote! { dyn #target_ident #dyn_args + Send + Sync };
+    let accessed = match ref_delegate {
+        RefDelegate::Box => quote! { ::#entrait::__alloc::Box<#dyn_target> },
+        RefDelegate::Swap => quote! { ::std::sync::RwLock<::std::sync::Arc<#dyn_target>> },
+        _ => quote! { ::#entrait::__alloc::Arc<#dyn_target> },
+    };
+
+    let items = match ref_delegate {
+        RefDelegate::Swap => {
+            let core = &attr.crate_idents.core;
+            let swap_method_ident = swap_method_ident(&out_trait.ident);
+            quote! {
+                fn #method_ident(&self) -> &#accessed;
+
+                fn #swap_method_ident(&self, new: ::std::sync::Arc<#dyn_target>) -> ::std::sync::Arc<#dyn_target> {
+                    let mut current = self
+                        .#method_ident()
+                        .write()
+                        .unwrap_or_else(::std::sync::PoisonError::into_inner);
+                    ::#core::mem::replace(&mut *current, new)
+                }
+            }
+        }
+        _ => quote! {
+            fn #method_ident(&self) -> &#accessed;
+        },
+    };
+
+    // With an impl trait, the application hands out a `dyn TraitImpl<App>`, which is not one for `Scoped<App, Ctx>`
+    let scoped_impl = match &attr.impl_trait {
+        Some(_) => None,
+        None => {
+            let scoped_generics = out_trait
+                .generics
+                .with_type_param(syn::parse_quote! { EntraitCtx })
+                .with_type_param(syn::parse_quote! { EntraitApp: #accessor_ident #args });
+            let scoped_params = scoped_generics.trait_params();
+            let scoped_where_clause = scoped_generics.trait_where_clause();
+
+            Some(quote! {
+                impl #scoped_params #accessor_ident #args for ::#entrait::Scoped<EntraitApp, EntraitCtx> #scoped_where_clause {
+                    fn #method_ident(&self) -> &#accessed {
+                        <EntraitApp as #accessor_ident #args>::#method_ident(::#entrait::Scoped::app(self))
+                    }
+                }
+            })
+        }
+    };
+
+    Some(quote! {
+        #on_unimplemented
+        #vis trait #accessor_ident #params #where_clause {
+            #items
+        }
+
+        #scoped_impl
+    })
+}
+
+/// The current implementation behind the lock of `delegate_by = Swap`.
+///
+/// The `Arc` is cloned out of the lock, so that the lock is not held during the call,
+/// and a swap can't happen in the middle of it. Because the clone is dropped after the call,
+/// the return value can't borrow from the implementation.
+pub fn swapped_target(
+    trait_fn: &TraitFn,
+    out_trait: &OutTrait,
+    impl_t: &syn::Ident,
+    accessor_args: &impl ToTokens,
+) -> syn::Result<TokenStream> {
+    if let syn::ReturnType::Type(_, output) = &trait_fn.sig().output {
+        if borrows_from_inputs(output.to_token_stream()) {
+            return Err(syn::Error::new(
+                output.span(),
+                "Methods returning borrowed data can't be delegated by `Swap`, as the implementation may be swapped out after the call",
+            ));
+        }
+    }
+
+    let (accessor_ident, method_ident) =
+        accessor_idents(&out_trait.ident, &RefDelegate::Swap).unwrap();
+
+    Ok(quote! {
+        {
+            let current = <#impl_t as #accessor_ident #accessor_args>::#method_ident(&*self)
+                .read()
+                .unwrap_or_else(::std::sync::PoisonError::into_inner);
+            ::std::sync::Arc::clone(&*current)
+        }
+    })
+}
+
+/// Whether the tokens contain a reference with an elided lifetime, or a lifetime other than `'static`,
+/// i.e. one that ties the value to the inputs
+fn borrows_from_inputs(stream: TokenStream) -> bool {
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '&' => match tokens.peek() {
+                Some(proc_macro2::TokenTree::Punct(next)) if next.as_char() == '\'' => {}
+                _ => return true,
+            },
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                match tokens.next() {
+                    Some(proc_macro2::TokenTree::Ident(ident)) if ident == "static" => {}
+                    _ => return true,
+                }
+            }
+            proc_macro2::TokenTree::Group(group) if borrows_from_inputs(group.stream()) => {
+                return true;
+            }
+            _ => {}
+        }
+    }
+    false
+}
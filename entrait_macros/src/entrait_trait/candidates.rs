@@ -0,0 +1,271 @@
+//! Statically dispatched choice between listed implementations of the impl trait.
+//!
+//! `delegate_by = enum(..)` generates an enum handed out by the application, i.e. `RepoChoice`,
+//! and `delegate_by = DelegateTrait(..)` generates a selector target asking the application on every call, i.e. `RepoSelector`.
+//! Both dispatch to the listed implementations by matching on a generated enum.
+
+use super::accessor::snake_case;
+use super::input_attr::EntraitTraitAttr;
+use super::out_trait::OutTrait;
+use crate::analyze_generics::TraitFn;
+use crate::generics;
+use crate::opt::Candidate;
+use crate::sub_attributes::SubAttribute;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+
+/// With `delegate_by = enum(..)`, the generated enum has one variant per implementation of the impl trait,
+/// and implements the impl trait by matching on the variant. The calls stay statically dispatched.
+pub fn gen_choice_enum(
+    out_trait: &OutTrait,
+    impl_trait_copy: &OutTrait,
+    candidates: &[Candidate],
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> TokenStream {
+    let vis = &out_trait.vis;
+    let enum_ident = choice_enum_ident(&out_trait.ident);
+    let dispatch = CandidateDispatch::new(impl_trait_copy, candidates, attr);
+    let impl_trait_ident = &impl_trait_copy.ident;
+    let params = impl_trait_copy.generics.trait_params();
+    let args = &dispatch.args;
+    let variant_idents = candidates.iter().map(|candidate| &candidate.variant);
+    let paths: Vec<_> = candidates.iter().map(|candidate| &candidate.path).collect();
+
+    // The futures borrow the implementation in the enum
+    let opt_sync = if dispatch.send_futures() {
+        Some(quote! { + Sync })
+    } else {
+        None
+    };
+    let impl_t_sync = dispatch.impl_t_sync();
+    let where_predicates = impl_trait_copy.generics.where_predicates.iter();
+
+    let methods = impl_trait_copy.fns.iter().map(|trait_fn| {
+        dispatch.method(
+            trait_fn,
+            quote! { self },
+            |variant| quote! { Self::#variant(inner) },
+            Some(quote! { inner }),
+        )
+    });
+
+    quote! {
+        #vis enum #enum_ident {
+            #(#variant_idents(#paths),)*
+        }
+
+        #(#impl_sub_attributes)*
+        impl #params #impl_trait_ident #args for #enum_ident
+        where
+            #(#paths: #impl_trait_ident #args #opt_sync,)*
+            #impl_t_sync
+            #(#where_predicates,)*
+        {
+            #(#methods)*
+        }
+    }
+}
+
+/// The dispatch to a listed implementation shared by `delegate_by = enum(..)` and `delegate_by = DelegateTrait(..)`
+struct CandidateDispatch<'s> {
+    impl_trait_copy: &'s OutTrait,
+    candidates: &'s [Candidate],
+    attr: &'s EntraitTraitAttr,
+    args: generics::ArgumentsGenerator<'s>,
+}
+
+impl<'s> CandidateDispatch<'s> {
+    fn new(
+        impl_trait_copy: &'s OutTrait,
+        candidates: &'s [Candidate],
+        attr: &'s EntraitTraitAttr,
+    ) -> Self {
+        Self {
+            impl_trait_copy,
+            candidates,
+            attr,
+            args: impl_trait_copy
+                .generics
+                .arguments(&generics::ImplIndirection::None),
+        }
+    }
+
+    /// Whether some method returns a future that must be `Send`
+    fn send_futures(&self) -> bool {
+        self.impl_trait_copy
+            .fns
+            .iter()
+            .any(|trait_fn| trait_fn.originally_async && trait_fn.future_send(&self.attr.opts).0)
+    }
+
+    /// `Send` futures hold on to `&Impl<T>`, which requires `T: Sync`
+    fn impl_t_sync(&self) -> Option<TokenStream> {
+        if self.send_futures() {
+            Some(quote! { EntraitT: Sync, })
+        } else {
+            None
+        }
+    }
+
+    /// A method matching `scrutinee` against the `pattern` of each candidate's variant,
+    /// calling the candidate's implementation with the optional `inner` receiver bound by the pattern.
+    fn method(
+        &self,
+        trait_fn: &TraitFn,
+        scrutinee: TokenStream,
+        pattern: impl Fn(&syn::Ident) -> TokenStream,
+        inner: Option<TokenStream>,
+    ) -> TokenStream {
+        let sig = trait_fn.sig();
+        let fn_ident = &sig.ident;
+        let impl_trait_ident = &self.impl_trait_copy.ident;
+        let args = &self.args;
+        let arguments: Vec<_> = inner
+            .into_iter()
+            .chain(sig.inputs.iter().filter_map(|arg| match arg {
+                syn::FnArg::Receiver(_) => None,
+                syn::FnArg::Typed(pat_type) => Some(pat_type.pat.to_token_stream()),
+            }))
+            .collect();
+        let opt_dot_await = trait_fn.opt_dot_await(Span::call_site());
+        let arms = self.candidates.iter().map(|Candidate { variant, path }| {
+            let pattern = pattern(variant);
+            quote! {
+                #pattern => <#path as #impl_trait_ident #args>::#fn_ident(#(#arguments),*) #opt_dot_await
+            }
+        });
+
+        quote! {
+            #sig {
+                match #scrutinee {
+                    #(#arms,)*
+                }
+            }
+        }
+    }
+}
+
+/// The items generated by `delegate_by = DelegateTrait(A, B, ..)`
+pub struct SelectorIdents {
+    /// i.e. `RepoSelection`, with a variant per candidate
+    pub selection_enum: syn::Ident,
+    /// i.e. `SelectRepo`, implemented by `T` to select the candidate
+    pub select_trait: syn::Ident,
+    /// i.e. `select_repo`
+    pub select_method: syn::Ident,
+    /// i.e. `RepoSelector`, the delegation target that calls the selected candidate
+    pub selector: syn::Ident,
+}
+
+impl SelectorIdents {
+    pub fn new(trait_ident: &syn::Ident) -> Self {
+        Self {
+            selection_enum: quote::format_ident!("{}Selection", trait_ident),
+            select_trait: quote::format_ident!("Select{}", trait_ident),
+            select_method: quote::format_ident!("select_{}", snake_case(trait_ident)),
+            selector: quote::format_ident!("{}Selector", trait_ident),
+        }
+    }
+}
+
+/// With `delegate_by = DelegateTrait(A, B, ..)`, the generated selector can be used as the `Target` of `DelegateTrait<T>`.
+/// It implements the impl trait by letting `T` select one of the candidates on every call,
+/// typically based on request context carried by `Impl<T>`. The calls stay statically dispatched.
+pub fn gen_selector(
+    out_trait: &OutTrait,
+    impl_trait_copy: &OutTrait,
+    candidates: &[Candidate],
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> syn::Result<TokenStream> {
+    if let Some(assoc_item) = out_trait.assoc_items.first() {
+        return Err(syn::Error::new(
+            assoc_item.ident().span(),
+            "Associated types and consts are not supported when selecting the implementation per call, as the implementations may disagree on them",
+        ));
+    }
+
+    let entrait = &attr.crate_idents.entrait;
+    let vis = &out_trait.vis;
+    let SelectorIdents {
+        selection_enum,
+        select_trait,
+        select_method,
+        selector,
+    } = SelectorIdents::new(&out_trait.ident);
+    let dispatch = CandidateDispatch::new(impl_trait_copy, candidates, attr);
+    let impl_trait_ident = &impl_trait_copy.ident;
+    let params = impl_trait_copy.generics.trait_params();
+    let args = &dispatch.args;
+    let variant_idents = candidates.iter().map(|candidate| &candidate.variant);
+    let paths = candidates.iter().map(|candidate| &candidate.path);
+    let impl_t_sync = dispatch.impl_t_sync();
+    let where_predicates = impl_trait_copy.generics.where_predicates.iter();
+
+    let methods = impl_trait_copy
+        .fns
+        .iter()
+        .map(|trait_fn| {
+            let sig = trait_fn.sig();
+
+            // The receiver of the entraited trait became `__impl`:
+            let impl_ref = match sig.inputs.first() {
+                Some(syn::FnArg::Typed(pat_type))
+                    if matches!(pat_type.pat.as_ref(), syn::Pat::Ident(pat_ident) if pat_ident.ident == "__impl") =>
+                {
+                    match pat_type.ty.as_ref() {
+                        syn::Type::Reference(_) => quote! { &*__impl },
+                        ty if generics::arc_deps(ty).is_some() => quote! { &*__impl },
+                        _ => quote! { &__impl },
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        sig.span(),
+                        "Methods without `self` can't select their implementation per call",
+                    ))
+                }
+            };
+
+            Ok(dispatch.method(
+                trait_fn,
+                quote! { <EntraitT as #select_trait>::#select_method(#impl_ref) },
+                |variant| quote! { #selection_enum::#variant },
+                None,
+            ))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #vis enum #selection_enum {
+            #(#variant_idents,)*
+        }
+
+        #vis trait #select_trait: Sized {
+            fn #select_method(deps: &::#entrait::Impl<Self>) -> #selection_enum;
+        }
+
+        #vis struct #selector;
+
+        #(#impl_sub_attributes)*
+        impl #params #impl_trait_ident #args for #selector
+        where
+            EntraitT: #select_trait,
+            #(#paths: #impl_trait_ident #args,)*
+            #impl_t_sync
+            #(#where_predicates,)*
+        {
+            #(#methods)*
+        }
+    })
+}
+
+/// The enum generated by `delegate_by = enum(..)`, e.g. `RepoChoice`
+pub fn choice_enum_ident(trait_ident: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("{}Choice", trait_ident)
+}
@@ -0,0 +1,224 @@
+//! A dyn-compatible "shadow" of a trait with `async fn`s, used for dynamic dispatch.
+//!
+//! The shadow has the same methods, but `async fn`s return boxed futures.
+//! It is implemented for every implementor of the shadowed trait, so those keep using native `async fn`s.
+
+use crate::analyze_generics::TraitFn;
+use crate::generics::{ImplIndirection, TraitGenerics};
+use crate::idents::CrateIdents;
+use crate::opt::Opts;
+use crate::trait_codegen::{AssocItem, Supertraits};
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use quote::ToTokens;
+use syn::visit_mut::VisitMut;
+
+/// The ident of the shadow of a trait, i.e. `DynFooImpl`
+pub fn dyn_shadow_ident(trait_ident: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("Dyn{}", trait_ident)
+}
+
+pub struct DynShadowTrait<'s> {
+    pub vis: &'s syn::Visibility,
+    /// The shadowed trait
+    pub trait_ident: &'s syn::Ident,
+    pub generics: &'s TraitGenerics,
+    pub supertraits: &'s Supertraits,
+    pub assoc_items: &'s [AssocItem],
+    pub fns: &'s [TraitFn],
+    pub opts: &'s Opts,
+    pub crate_idents: &'s CrateIdents,
+}
+
+impl DynShadowTrait<'_> {
+    pub fn gen(&self) -> TokenStream {
+        let vis = self.vis;
+        let trait_ident = self.trait_ident;
+        let shadow_ident = dyn_shadow_ident(trait_ident);
+        let params = self.generics.trait_params();
+        let args = self.generics.arguments(&ImplIndirection::None);
+        let where_clause = self.generics.trait_where_clause();
+        let supertraits = self.supertraits;
+
+        let blanket_generics = self.generics.with_type_param(match supertraits {
+            Supertraits::Some { bounds, .. } => syn::parse_quote! {
+                EntraitSelf: ?Sized + #trait_ident #args + #bounds
+            },
+            Supertraits::None => syn::parse_quote! {
+                EntraitSelf: ?Sized + #trait_ident #args
+            },
+        });
+        let blanket_params = blanket_generics.trait_params();
+        let entrait = &self.crate_idents.entrait;
+
+        let assoc_items = self.assoc_items;
+        let delegate = quote! { EntraitSelf as #trait_ident #args };
+        let assoc_item_impls = self
+            .assoc_items
+            .iter()
+            .map(|assoc_item| assoc_item.gen_forwarding_impl_item(&delegate));
+
+        let shadow_sigs: Vec<_> = self
+            .fns
+            .iter()
+            .map(|trait_fn| self.shadow_sig(trait_fn))
+            .collect();
+        let blanket_fns = self.fns.iter().zip(&shadow_sigs).map(|(trait_fn, sig)| {
+            let fn_ident = &sig.ident;
+            let arguments = sig.inputs.iter().map(|arg| match arg {
+                syn::FnArg::Receiver(receiver) => receiver.self_token.to_token_stream(),
+                syn::FnArg::Typed(pat_type) => pat_type.pat.to_token_stream(),
+            });
+            let call = quote! {
+                <EntraitSelf as #trait_ident #args>::#fn_ident(#(#arguments),*)
+            };
+
+            if trait_fn.originally_async {
+                quote! {
+                    #sig {
+                        ::#entrait::__alloc::Box::pin(#call)
+                    }
+                }
+            } else {
+                quote! {
+                    #sig {
+                        #call
+                    }
+                }
+            }
+        });
+
+        quote! {
+            #vis trait #shadow_ident #params #supertraits #where_clause {
+                #(#assoc_items)*
+                #(#shadow_sigs;)*
+            }
+
+            impl #blanket_params #shadow_ident #args for EntraitSelf #where_clause {
+                #(#assoc_item_impls)*
+                #(#blanket_fns)*
+            }
+        }
+    }
+
+    /// `async fn`s return a boxed future, which borrows everything for `'entrait_future`
+    fn shadow_sig(&self, trait_fn: &TraitFn) -> syn::Signature {
+        let mut sig = trait_fn.sig().clone();
+        if !trait_fn.originally_async {
+            return sig;
+        }
+
+        let future_lifetime = syn::Lifetime::new("'entrait_future", Span::call_site());
+        let core = &self.crate_idents.core;
+        let entrait = &self.crate_idents.entrait;
+
+        sig.asyncness = None;
+
+        let mut elided = ElidedLifetimes::default();
+        for input in sig.inputs.iter_mut() {
+            elided.visit_fn_arg_mut(input);
+        }
+
+        let user_lifetimes: Vec<_> = sig
+            .generics
+            .lifetimes()
+            .map(|param| param.lifetime.clone())
+            .collect();
+        let trait_lifetimes = self.generics.params.iter().filter_map(|param| match param {
+            syn::GenericParam::Lifetime(param) => Some(&param.lifetime),
+            _ => None,
+        });
+        let trait_type_params = self.generics.params.iter().filter_map(|param| match param {
+            syn::GenericParam::Type(param) => Some(&param.ident),
+            _ => None,
+        });
+
+        let mut where_predicates: Vec<syn::WherePredicate> = vec![];
+        for lifetime in elided
+            .lifetimes
+            .iter()
+            .chain(&user_lifetimes)
+            .chain(trait_lifetimes)
+        {
+            where_predicates.push(syn::parse_quote! { #lifetime: #future_lifetime });
+        }
+        for ident in trait_type_params {
+            where_predicates.push(syn::parse_quote! { #ident: #future_lifetime });
+        }
+        where_predicates.push(syn::parse_quote! { Self: #future_lifetime });
+
+        for (index, lifetime) in std::iter::once(&future_lifetime)
+            .chain(&elided.lifetimes)
+            .enumerate()
+        {
+            sig.generics.params.insert(
+                index,
+                syn::GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())),
+            );
+        }
+        sig.generics
+            .make_where_clause()
+            .predicates
+            .extend(where_predicates);
+
+        let output = match &sig.output {
+            syn::ReturnType::Default => quote! { () },
+            syn::ReturnType::Type(_, ty) => quote! { #ty },
+        };
+        let opt_send = if trait_fn.future_send(self.opts).0 {
+            Some(quote! { + Send })
+        } else {
+            None
+        };
+        sig.output = syn::parse_quote! {
+            -> ::#core::pin::Pin<::#entrait::__alloc::Box<
+                dyn ::#core::future::Future<Output = #output> #opt_send + #future_lifetime
+            >>
+        };
+
+        sig
+    }
+}
+
+/// Makes the elided lifetimes of the inputs explicit, so that the future can outlive them
+#[derive(Default)]
+struct ElidedLifetimes {
+    lifetimes: Vec<syn::Lifetime>,
+}
+
+impl ElidedLifetimes {
+    fn next_lifetime(&mut self) -> syn::Lifetime {
+        let lifetime = syn::Lifetime::new(
+            &format!("'entrait_life{}", self.lifetimes.len()),
+            Span::call_site(),
+        );
+        self.lifetimes.push(lifetime.clone());
+        lifetime
+    }
+}
+
+impl VisitMut for ElidedLifetimes {
+    fn visit_receiver_mut(&mut self, receiver: &mut syn::Receiver) {
+        if let Some((_, lifetime @ None)) = &mut receiver.reference {
+            let explicit = self.next_lifetime();
+            *lifetime = Some(explicit.clone());
+            if let syn::Type::Reference(reference) = receiver.ty.as_mut() {
+                reference.lifetime = Some(explicit);
+            }
+        }
+    }
+
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(self.next_lifetime());
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.next_lifetime();
+        }
+    }
+}
@@ -30,6 +30,8 @@ impl Parse for EntraitTraitAttr {
             }
         }
 
+        let mut context = None;
+        let mut leaf_accessor = None;
         let mut debug = None;
         let mut mock_api = None;
         let mut future_send = None;
@@ -40,6 +42,8 @@ impl Parse for EntraitTraitAttr {
         if !input.is_empty() {
             loop {
                 match input.parse::<EntraitOpt>()? {
+                    EntraitOpt::Context(opt) => context = Some(opt),
+                    EntraitOpt::LeafAccessor(opt) => leaf_accessor = Some(opt),
                     EntraitOpt::Debug(opt) => debug = Some(opt),
                     EntraitOpt::MockApi(ident) => mock_api = Some(ident),
                     EntraitOpt::MaybeSend(send) => future_send = Some(send),
@@ -64,6 +68,10 @@ impl Parse for EntraitTraitAttr {
             opts: Opts {
                 default_span: proc_macro2::Span::call_site(),
                 no_deps: None,
+                concrete: None,
+                context,
+                leaf_accessor,
+                method_generics: None,
                 debug,
                 export: None,
                 future_send,
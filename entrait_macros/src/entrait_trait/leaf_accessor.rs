@@ -0,0 +1,120 @@
+//! The accessor trait of a leaf trait, i.e. `GetFooLeaf`.
+//!
+//! A type implements the leaf trait by handing out a reference to another implementation of it,
+//! typically one of its fields. This lets `#[derive(Wire)]` wire leaf traits without knowing their methods.
+
+use super::DelegatingMethod;
+use crate::analyze_generics::TraitFn;
+use crate::diagnostics::OnUnimplemented;
+use crate::generics::{ImplIndirection, TraitGenerics};
+use crate::opt::Opts;
+use crate::sub_attributes::SubAttribute;
+use crate::trait_codegen::{AssocItem, Supertraits};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The accessor trait of a leaf trait and its method, i.e. `GetFooLeaf` and `get_foo_leaf`
+pub fn leaf_accessor_idents(trait_ident: &syn::Ident) -> (syn::Ident, syn::Ident) {
+    (
+        quote::format_ident!("{}Leaf", trait_ident),
+        quote::format_ident!("{}_leaf", super::accessor::snake_case(trait_ident)),
+    )
+}
+
+/// Leaf traits with only `&self` methods can be implemented through an accessor.
+///
+/// Not for traits with type parameters, as other crates could implement `GetFooLeaf<TheirType>` for `Impl<T>`,
+/// which would then have two implementations of the leaf trait.
+pub fn has_leaf_accessor(generics: &TraitGenerics, fns: &[TraitFn]) -> bool {
+    !super::has_type_params(generics)
+        && fns.iter().all(|trait_fn| {
+        matches!(
+            trait_fn.sig().receiver(),
+            Some(receiver) if receiver.colon_token.is_none() && receiver.reference.is_some() && receiver.mutability.is_none()
+        )
+    })
+}
+
+pub struct LeafAccessorTrait<'s> {
+    pub vis: &'s syn::Visibility,
+    /// The leaf trait
+    pub trait_ident: &'s syn::Ident,
+    pub generics: &'s TraitGenerics,
+    pub supertraits: &'s Supertraits,
+    pub assoc_items: &'s [AssocItem],
+    pub fns: &'s [TraitFn],
+    pub impl_sub_attributes: &'s [SubAttribute<'s>],
+    pub opts: &'s Opts,
+}
+
+impl LeafAccessorTrait<'_> {
+    pub fn gen(&self) -> TokenStream {
+        let vis = self.vis;
+        let trait_ident = self.trait_ident;
+        let (accessor_ident, method_ident) = leaf_accessor_idents(trait_ident);
+        let params = self.generics.trait_params();
+        let args = self.generics.arguments(&ImplIndirection::None);
+        let where_clause = self.generics.trait_where_clause();
+
+        let mut blanket_generics = self.generics.with_type_param(match self.supertraits {
+            Supertraits::Some { bounds, .. } => syn::parse_quote! {
+                EntraitSelf: ?Sized + #accessor_ident #args + #bounds
+            },
+            Supertraits::None => syn::parse_quote! {
+                EntraitSelf: ?Sized + #accessor_ident #args
+            },
+        });
+        let target = quote! { <EntraitSelf as #accessor_ident #args>::Target };
+
+        // The futures borrow both `self` and the target:
+        if self
+            .fns
+            .iter()
+            .any(|trait_fn| trait_fn.originally_async && trait_fn.future_send(self.opts).0)
+        {
+            let predicates = &mut blanket_generics.where_predicates;
+            predicates.push(syn::parse_quote! { EntraitSelf: Sync });
+            predicates.push(syn::parse_quote! { #target: Sync });
+        }
+        let blanket_params = blanket_generics.trait_params();
+        let blanket_where_clause = blanket_generics.trait_where_clause();
+
+        let delegate = quote! { #target as #trait_ident #args };
+        let assoc_item_impls = self
+            .assoc_items
+            .iter()
+            .map(|assoc_item| assoc_item.gen_forwarding_impl_item(&delegate));
+
+        let method_items = self.fns.iter().map(|trait_fn| {
+            let fn_ident = &trait_fn.sig().ident;
+            let arguments = trait_fn.sig().inputs.iter().filter_map(|arg| match arg {
+                syn::FnArg::Receiver(_) => None,
+                syn::FnArg::Typed(pat_type) => Some(&pat_type.pat),
+            });
+            DelegatingMethod {
+                trait_fn,
+                call: quote! {
+                    <EntraitSelf as #accessor_ident #args>::#method_ident(self).#fn_ident(#(#arguments),*)
+                },
+            }
+        });
+        let impl_sub_attributes = self.impl_sub_attributes;
+        let on_unimplemented = OnUnimplemented::leaf_accessor_trait(&accessor_ident, trait_ident);
+
+        quote! {
+            #on_unimplemented
+            #vis trait #accessor_ident #params #where_clause {
+                type Target: ?Sized + #trait_ident #args;
+
+                fn #method_ident(&self) -> &Self::Target;
+            }
+
+            #(#impl_sub_attributes)*
+            impl #blanket_params #trait_ident #args for EntraitSelf #blanket_where_clause {
+                #(#assoc_item_impls)*
+                #(#method_items)*
+            }
+        }
+    }
+}
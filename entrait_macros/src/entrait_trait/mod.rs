@@ -1,39 +1,61 @@
 //! Implementation for invoking entrait on a trait!
 
+mod accessor;
+mod candidates;
+mod dyn_shadow;
 pub mod input_attr;
+mod leaf_accessor;
 mod out_trait;
+mod scoped;
+
+pub use accessor::accessor_idents;
+pub use leaf_accessor::leaf_accessor_idents;
 
 use input_attr::EntraitTraitAttr;
 use proc_macro2::Span;
 
 use crate::analyze_generics::TraitFn;
+use crate::diagnostics;
+use crate::diagnostics::OnUnimplemented;
 use crate::entrait_trait::input_attr::ImplTrait;
 use crate::generics;
+use crate::generics::FnDeps;
 use crate::generics::TraitDependencyMode;
+use crate::idents::CrateIdents;
 use crate::idents::GenericIdents;
 use crate::input::FnInputMode;
 use crate::input::LiteralAttrs;
 use crate::opt::*;
 use crate::sub_attributes::analyze_sub_attributes;
+use crate::sub_attributes::contains_async_trait;
 use crate::sub_attributes::SubAttribute;
 use crate::token_util::*;
+use crate::trait_codegen::AssocItem;
 use crate::trait_codegen::Supertraits;
 use crate::trait_codegen::TraitCodegen;
 
 use proc_macro2::TokenStream;
 use quote::quote;
 use quote::ToTokens;
+use syn::spanned::Spanned;
 
+use self::accessor::{gen_accessor_trait_def, swapped_target};
+use self::candidates::{choice_enum_ident, gen_choice_enum, gen_selector, SelectorIdents};
 use self::out_trait::OutTrait;
+use self::scoped::gen_scoped_delegation_impl;
 
 #[derive(Clone, Copy)]
 struct ContainsAsync(bool);
 
+/// Whether dynamic dispatch goes through a generated dyn-compatible shadow of the delegation target trait
+#[derive(Clone, Copy)]
+struct DynShadow(bool);
+
 pub fn output_tokens(
     attr: EntraitTraitAttr,
     item_trait: syn::ItemTrait,
 ) -> syn::Result<TokenStream> {
-    if let (None, Some(SpanOpt(Delegate::ByTrait(_), span))) =
+    if let (None, Some(SpanOpt(Delegate::ByTrait(..), span))) =
         (&attr.impl_trait, &attr.delegation_kind)
     {
         return Err(syn::Error::new(
@@ -41,14 +63,38 @@ pub fn output_tokens(
             "Cannot use a custom delegating trait without a custom trait to delegate to. Use either `#[entrait(TraitImpl, delegate_by = DelegateTrait)]` or `#[entrait(delegate_by = ref)]`",
         ));
     }
-
-    let trait_ident_span = item_trait.ident.span();
-    let contains_async = ContainsAsync(item_trait.items.iter().any(|item| match item {
-        syn::TraitItem::Fn(method) => method.sig.asyncness.is_some(),
-        _ => false,
-    }));
+    if let (None, Some(SpanOpt(Delegate::ByRef(RefDelegate::Enum(_)), span))) =
+        (&attr.impl_trait, &attr.delegation_kind)
+    {
+        return Err(syn::Error::new(
+            *span,
+            "`delegate_by = enum(..)` needs a trait for the implementations to implement. Use `#[entrait(TraitImpl, delegate_by = enum(..))]`",
+        ));
+    }
 
     let out_trait = out_trait::analyze_trait(item_trait)?;
+    if let Some(SpanOpt(true, span)) = attr.opts.context {
+        if !is_leaf_trait(&attr) || has_type_params(&out_trait.generics) {
+            return Err(syn::Error::new(
+                span,
+                "`context` requires a leaf trait without `delegate_by` or type parameters",
+            ));
+        }
+    }
+    if let Some(SpanOpt(true, span)) = attr.opts.leaf_accessor {
+        if !has_leaf_accessor_trait(&out_trait, &attr) {
+            return Err(syn::Error::new(
+                span,
+                "`leaf_accessor` requires a leaf trait without `delegate_by` or type parameters, with only `&self` methods",
+            ));
+        }
+    }
+    let contains_async = ContainsAsync(
+        out_trait
+            .fns
+            .iter()
+            .any(|trait_fn| trait_fn.originally_async),
+    );
     let sub_attributes = analyze_sub_attributes(&out_trait.attrs);
     let impl_sub_attributes: Vec<_> = sub_attributes
         .iter()
@@ -65,13 +111,21 @@ pub fn output_tokens(
         _ => panic!(),
     };
 
+    let dyn_shadow = dyn_shadow(&out_trait.fns, &impl_sub_attributes, &attr);
+
     let delegation_trait_def = gen_impl_delegation_trait_defs(
         &out_trait,
         &trait_dependency_mode,
         generic_idents,
         &impl_sub_attributes,
         &attr,
+        dyn_shadow,
     )?;
+    let leaf_dyn_shadow_trait_def = gen_leaf_dyn_shadow_trait_def(&out_trait, &attr, dyn_shadow);
+
+    let accessor_trait_def = gen_accessor_trait_def(&out_trait, generic_idents, &attr, dyn_shadow);
+    let leaf_accessor_trait_def =
+        gen_leaf_accessor_trait_def(&out_trait, &impl_sub_attributes, &attr);
 
     let trait_def = TraitCodegen {
         crate_idents: &attr.crate_idents,
@@ -79,51 +133,472 @@ pub fn output_tokens(
         trait_indirection: generics::TraitIndirection::Trait,
         trait_dependency_mode: &trait_dependency_mode,
         sub_attributes: &sub_attributes,
+        on_unimplemented: Some(trait_on_unimplemented(&out_trait, &attr, dyn_shadow)),
     }
     .gen_trait_def(
         &out_trait.vis,
         &out_trait.ident,
         &out_trait.generics,
         &out_trait.supertraits,
+        &out_trait.assoc_items,
         &out_trait.fns,
         &FnInputMode::RawTrait(LiteralAttrs(&out_trait.attrs)),
     )?;
 
+    let impl_t_impl = gen_impl_t_impl(
+        &out_trait,
+        generic_idents,
+        &impl_sub_attributes,
+        &attr,
+        contains_async,
+        dyn_shadow,
+    )?;
+    let scoped_impl = gen_scoped_impl(&out_trait, &impl_sub_attributes, &attr);
+
+    let out = quote! {
+        #trait_def
+
+        #delegation_trait_def
+
+        #leaf_dyn_shadow_trait_def
+
+        #accessor_trait_def
+
+        #leaf_accessor_trait_def
+
+        #impl_t_impl
+
+        #scoped_impl
+    };
+
+    Ok(out)
+}
+
+/// `delegate_by` for entraited functions and modules.
+///
+/// The trait itself is generated by the caller. This generates the `{Trait}Impl` trait,
+/// which has the entraited functions as provided methods, and the delegating `Impl<T>` implementation.
+#[allow(clippy::too_many_arguments)]
+pub fn output_tokens_for_fns(
+    trait_vis: &syn::Visibility,
+    trait_ident: &syn::Ident,
+    trait_generics: &generics::TraitGenerics,
+    supertraits: &Supertraits,
+    trait_fns: &[TraitFn],
+    delegation_kind: &SpanOpt<Delegate>,
+    opts: &Opts,
+    sub_attributes: &[SubAttribute],
+) -> syn::Result<TokenStream> {
+    let span = trait_ident.span();
+    let attr = EntraitTraitAttr {
+        impl_trait: Some(ImplTrait(
+            trait_vis.clone(),
+            quote::format_ident!("{}Impl", trait_ident),
+        )),
+        opts: Opts {
+            mock_api: None,
+            unimock: None,
+            mockall: None,
+            ..*opts
+        },
+        delegation_kind: Some(delegation_kind.clone()),
+        crate_idents: CrateIdents::new(span),
+    };
+    let contains_async = ContainsAsync(trait_fns.iter().any(|trait_fn| trait_fn.originally_async));
+
+    let out_trait = OutTrait {
+        attrs: vec![],
+        vis: trait_vis.clone(),
+        trait_token: syn::token::Trait(span),
+        generics: trait_generics.clone(),
+        ident: trait_ident.clone(),
+        supertraits: supertraits.clone(),
+        assoc_items: vec![],
+        fns: trait_fns
+            .iter()
+            .cloned()
+            .map(|mut trait_fn| {
+                trait_fn.default = Some(fn_default_body(&trait_fn));
+                trait_fn
+            })
+            .collect(),
+    };
+    let impl_sub_attributes: Vec<_> = sub_attributes
+        .iter()
+        .copied()
+        .filter(|sub_attr| matches!(sub_attr, SubAttribute::AsyncTrait(_)))
+        .collect();
+
+    let trait_dependency_mode =
+        TraitDependencyMode::Generic(GenericIdents::new(&attr.crate_idents, span));
+    let generic_idents = match &trait_dependency_mode {
+        TraitDependencyMode::Generic(idents) => idents,
+        _ => panic!(),
+    };
+
+    let dyn_shadow = dyn_shadow(&out_trait.fns, &impl_sub_attributes, &attr);
+
+    let delegation_trait_def = gen_impl_delegation_trait_defs(
+        &out_trait,
+        &trait_dependency_mode,
+        generic_idents,
+        &impl_sub_attributes,
+        &attr,
+        dyn_shadow,
+    )?;
+    let accessor_trait_def = gen_accessor_trait_def(&out_trait, generic_idents, &attr, dyn_shadow);
+    let impl_t_impl = gen_impl_t_impl(
+        &out_trait,
+        generic_idents,
+        &impl_sub_attributes,
+        &attr,
+        contains_async,
+        dyn_shadow,
+    )?;
+    let scoped_impl = gen_scoped_impl(&out_trait, &impl_sub_attributes, &attr);
+
+    Ok(quote! {
+        #delegation_trait_def
+
+        #accessor_trait_def
+
+        #impl_t_impl
+
+        #scoped_impl
+    })
+}
+
+/// Traits with `async fn`s are not dyn-compatible, unless `#[async_trait]` is applied to them.
+/// Without it, dynamic dispatch goes through a dyn-compatible shadow trait returning boxed futures.
+fn dyn_shadow(
+    fns: &[TraitFn],
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> DynShadow {
+    DynShadow(match &attr.delegation_kind {
+        Some(SpanOpt(Delegate::ByRef(ref_delegate), _)) => {
+            needs_dyn_shadow(ref_delegate, fns, impl_sub_attributes)
+        }
+        _ => false,
+    })
+}
+
+fn needs_dyn_shadow(
+    ref_delegate: &RefDelegate,
+    fns: &[TraitFn],
+    sub_attributes: &[SubAttribute],
+) -> bool {
+    ref_delegate.is_dyn()
+        && fns.iter().any(|trait_fn| trait_fn.originally_async)
+        && !contains_async_trait(sub_attributes)
+}
+
+/// The ident of the trait used as `dyn` trait object with dynamic dispatch
+fn dyn_target_ident(
+    out_trait: &OutTrait,
+    attr: &EntraitTraitAttr,
+    dyn_shadow: DynShadow,
+) -> syn::Ident {
+    let target_ident = match &attr.impl_trait {
+        Some(ImplTrait(_, impl_trait_ident)) => impl_trait_ident,
+        None => &out_trait.ident,
+    };
+    if dyn_shadow.0 {
+        dyn_shadow::dyn_shadow_ident(target_ident)
+    } else {
+        target_ident.clone()
+    }
+}
+
+/// The associated types pinned in the `dyn` trait object with `#[entrait(dyn = Type)]`
+fn dyn_bindings(out_trait: &OutTrait) -> Vec<syn::AssocType> {
+    out_trait
+        .assoc_items
+        .iter()
+        .filter_map(AssocItem::dyn_binding)
+        .collect()
+}
+
+/// Leaf dependencies without an impl trait are shadowed directly
+fn gen_leaf_dyn_shadow_trait_def(
+    out_trait: &OutTrait,
+    attr: &EntraitTraitAttr,
+    dyn_shadow: DynShadow,
+) -> Option<TokenStream> {
+    if !dyn_shadow.0 || attr.impl_trait.is_some() {
+        return None;
+    }
+
+    Some(
+        dyn_shadow::DynShadowTrait {
+            vis: &out_trait.vis,
+            trait_ident: &out_trait.ident,
+            generics: &out_trait.generics,
+            supertraits: &impl_trait_supertraits(out_trait),
+            assoc_items: &out_trait.assoc_items,
+            fns: &out_trait.fns,
+            opts: &attr.opts,
+            crate_idents: &attr.crate_idents,
+        }
+        .gen(),
+    )
+}
+
+/// Without `delegate_by`, `T` implements the trait itself
+fn is_leaf_trait(attr: &EntraitTraitAttr) -> bool {
+    matches!(
+        &attr.delegation_kind,
+        None | Some(SpanOpt(Delegate::BySelf, _))
+    ) && attr.impl_trait.is_none()
+}
+
+fn has_type_params(generics: &generics::TraitGenerics) -> bool {
+    generics
+        .params
+        .iter()
+        .any(|param| matches!(param, syn::GenericParam::Type(_)))
+}
+
+/// A leaf trait can be implemented through a generated leaf accessor trait
+fn has_leaf_accessor_trait(out_trait: &OutTrait, attr: &EntraitTraitAttr) -> bool {
+    is_leaf_trait(attr) && leaf_accessor::has_leaf_accessor(&out_trait.generics, &out_trait.fns)
+}
+
+/// `Impl<Scoped<App, Ctx>>` implements the trait by forwarding to `Impl<App>`,
+/// unless `Scoped` already satisfies the bounds on the `T` of `Impl<T>`:
+/// it forwards `Deref`, and the traits of `delegate_by = DelegateTrait` and of the accessors that don't hand out a `dyn TraitImpl<T>`.
+///
+/// Not for traits with type parameters, as other crates could implement `Trait<TheirType>` for `Scoped<App, Ctx>`,
+/// which would then overlap with the implementation for `Impl<T>`.
+fn gen_scoped_impl(
+    out_trait: &OutTrait,
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> Option<TokenStream> {
+    let forwards = match &attr.delegation_kind {
+        None | Some(SpanOpt(Delegate::BySelf, _)) => attr.impl_trait.is_none(),
+        Some(SpanOpt(Delegate::ByRef(RefDelegate::AsRef | RefDelegate::Borrow), _)) => true,
+        Some(SpanOpt(
+            Delegate::ByRef(RefDelegate::Arc | RefDelegate::Box | RefDelegate::Swap),
+            _,
+        )) => attr.impl_trait.is_some(),
+        Some(SpanOpt(Delegate::ByRef(RefDelegate::Deref | RefDelegate::Enum(_)), _))
+        | Some(SpanOpt(Delegate::ByTrait(..), _)) => false,
+    };
+    if !forwards || has_type_params(&out_trait.generics) {
+        return None;
+    }
+
+    Some(
+        scoped::ScopedImpl {
+            trait_ident: &out_trait.ident,
+            generics: &out_trait.generics,
+            supertraits: &out_trait.supertraits,
+            assoc_items: &out_trait.assoc_items,
+            fns: &out_trait.fns,
+            impl_sub_attributes,
+            opts: &attr.opts,
+            crate_idents: &attr.crate_idents,
+        }
+        .gen(),
+    )
+}
+
+fn gen_leaf_accessor_trait_def(
+    out_trait: &OutTrait,
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> Option<TokenStream> {
+    if !attr.opts.leaf_accessor_value() {
+        return None;
+    }
+
+    Some(
+        leaf_accessor::LeafAccessorTrait {
+            vis: &out_trait.vis,
+            trait_ident: &out_trait.ident,
+            generics: &out_trait.generics,
+            supertraits: &out_trait.supertraits,
+            assoc_items: &out_trait.assoc_items,
+            fns: &out_trait.fns,
+            impl_sub_attributes,
+            opts: &attr.opts,
+        }
+        .gen(),
+    )
+}
+
+/// Names what `Impl<T>` is missing when it does not implement the trait, unless the trait has its own message
+fn trait_on_unimplemented(
+    out_trait: &OutTrait,
+    attr: &EntraitTraitAttr,
+    dyn_shadow: DynShadow,
+) -> OnUnimplemented {
+    if let Some(hand_written) = diagnostics::find_on_unimplemented(&out_trait.attrs) {
+        return OnUnimplemented::HandWritten(hand_written.clone());
+    }
+
+    OnUnimplemented::entraited_trait(
+        &out_trait.ident,
+        &diagnostics::delegated_to(
+            &out_trait.ident,
+            attr.delegation_kind
+                .as_ref()
+                .map(|SpanOpt(delegate, _)| delegate),
+            attr.impl_trait
+                .as_ref()
+                .map(|ImplTrait(_, impl_trait_ident)| impl_trait_ident),
+            &dyn_target_ident(out_trait, attr, dyn_shadow),
+        ),
+    )
+}
+
+/// The `on_unimplemented` message of the trait of entraited functions
+pub fn fn_trait_on_unimplemented(
+    trait_ident: &syn::Ident,
+    delegation_kind: Option<&SpanOpt<Delegate>>,
+    trait_fns: &[TraitFn],
+    sub_attributes: &[SubAttribute],
+    trait_dependency_mode: &TraitDependencyMode,
+) -> OnUnimplemented {
+    let implemented_for = match delegation_kind {
+        Some(SpanOpt(delegate, _)) => {
+            let impl_trait_ident = quote::format_ident!("{}Impl", trait_ident);
+            let dyn_target_ident = match delegate {
+                Delegate::ByRef(ref_delegate)
+                    if needs_dyn_shadow(ref_delegate, trait_fns, sub_attributes) =>
+                {
+                    dyn_shadow::dyn_shadow_ident(&impl_trait_ident)
+                }
+                _ => impl_trait_ident.clone(),
+            };
+            diagnostics::delegated_to(
+                trait_ident,
+                Some(delegate),
+                Some(&impl_trait_ident),
+                &dyn_target_ident,
+            )
+        }
+        None => diagnostics::fn_deps_implementors(trait_dependency_mode, trait_fns),
+    };
+
+    OnUnimplemented::entraited_trait(trait_ident, &implemented_for)
+}
+
+/// The traits (and enum) generated by `delegate_by` for entraited functions
+pub fn fn_delegation_trait_idents(
+    trait_ident: &syn::Ident,
+    delegation_kind: &SpanOpt<Delegate>,
+    trait_fns: &[TraitFn],
+    sub_attributes: &[SubAttribute],
+) -> Vec<syn::Ident> {
+    let impl_trait_ident = quote::format_ident!("{}Impl", trait_ident);
+    let mut idents = vec![];
+
+    match &delegation_kind.0 {
+        Delegate::ByTrait(delegation_ident, candidates) => {
+            idents.push(delegation_ident.clone());
+            if !candidates.is_empty() {
+                let selector_idents = SelectorIdents::new(trait_ident);
+                idents.push(selector_idents.selection_enum);
+                idents.push(selector_idents.select_trait);
+                idents.push(selector_idents.selector);
+            }
+        }
+        Delegate::ByRef(ref_delegate) => {
+            if let Some((accessor_ident, _)) = accessor_idents(trait_ident, ref_delegate) {
+                idents.push(accessor_ident);
+            }
+            if let RefDelegate::Enum(_) = ref_delegate {
+                idents.push(choice_enum_ident(trait_ident));
+            }
+            if needs_dyn_shadow(ref_delegate, trait_fns, sub_attributes) {
+                idents.push(dyn_shadow::dyn_shadow_ident(&impl_trait_ident));
+            }
+        }
+        Delegate::BySelf => {}
+    }
+
+    idents.insert(0, impl_trait_ident);
+    idents
+}
+
+/// The body of a provided method of the impl trait, calling the entraited function
+fn fn_default_body(trait_fn: &TraitFn) -> syn::Block {
+    let fn_ident = trait_fn.source_ident();
+    let opt_self = match trait_fn.deps {
+        FnDeps::NoDeps => None,
+        _ => Some(quote! { self, }),
+    };
+    let arguments = trait_fn.sig().inputs.iter().filter_map(|arg| match arg {
+        syn::FnArg::Receiver(_) => None,
+        syn::FnArg::Typed(pat_type) => Some(&pat_type.pat),
+    });
+    let opt_dot_await = trait_fn.opt_dot_await(Span::call_site());
+
+    syn::parse_quote! {
+        {
+            #fn_ident(#opt_self #(#arguments),*) #opt_dot_await
+        }
+    }
+}
+
+/// The implementation of the entraited trait for `Impl<T>`
+fn gen_impl_t_impl(
+    out_trait: &OutTrait,
+    generic_idents: &GenericIdents,
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+    contains_async: ContainsAsync,
+    dyn_shadow: DynShadow,
+) -> syn::Result<TokenStream> {
+    let trait_ident_span = out_trait.ident.span();
     let trait_ident = &out_trait.ident;
+    let impl_trait_generics = impl_trait_generics(out_trait, generic_idents);
     let params = out_trait.generics.impl_params_from_idents(
         generic_idents,
-        generics::TakesSelfByValue(false), // BUG?
+        generics::has_any_self_by_value(out_trait.fns.iter().map(TraitFn::sig)),
     );
     let args = out_trait
         .generics
         .arguments(&generics::ImplIndirection::None);
     let self_ty = generic_idents.impl_path(trait_ident_span);
     let where_clause = ImplWhereClause {
-        out_trait: &out_trait,
+        out_trait,
         contains_async,
+        dyn_shadow,
         trait_generics: &out_trait.generics,
+        impl_trait_generics: &impl_trait_generics,
         generic_idents,
-        attr: &attr,
+        attr,
         span: trait_ident_span,
     };
 
     let method_items = out_trait
         .fns
         .iter()
-        .map(|trait_fn| gen_delegation_method(trait_fn, generic_idents, &attr, contains_async));
-
-    let out = quote! {
-        #trait_def
-
-        #delegation_trait_def
+        .map(|trait_fn| {
+            gen_delegation_method(
+                out_trait,
+                trait_fn,
+                generic_idents,
+                &impl_trait_generics,
+                attr,
+                contains_async,
+                dyn_shadow,
+            )
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let assoc_item_impls =
+        gen_assoc_item_impls(out_trait, generic_idents, &impl_trait_generics, attr)?;
 
+    Ok(quote! {
         #(#impl_sub_attributes)*
         impl #params #trait_ident #args for #self_ty #where_clause {
+            #(#assoc_item_impls)*
             #(#method_items)*
         }
-    };
-
-    Ok(out)
+    })
 }
 
 fn gen_impl_delegation_trait_defs(
@@ -132,6 +607,7 @@ fn gen_impl_delegation_trait_defs(
     generic_idents: &GenericIdents,
     impl_sub_attributes: &[SubAttribute],
     attr: &EntraitTraitAttr,
+    dyn_shadow: DynShadow,
 ) -> syn::Result<Option<TokenStream>> {
     let entrait = &generic_idents.crate_idents.entrait;
 
@@ -151,13 +627,8 @@ fn gen_impl_delegation_trait_defs(
     };
 
     match &attr.delegation_kind {
-        Some(SpanOpt(Delegate::ByTrait(delegation_ident), _)) => {
-            trait_copy.generics.params.insert(
-                0,
-                syn::parse_quote! {
-                    EntraitT
-                },
-            );
+        Some(SpanOpt(delegate @ Delegate::ByTrait(delegation_ident, candidates), _)) => {
+            trait_copy.generics = impl_trait_generics(out_trait, generic_idents);
             for trait_fn in trait_copy.fns.iter_mut() {
                 if !matches!(trait_fn.sig().inputs.first(), Some(syn::FnArg::Receiver(_))) {
                     continue;
@@ -166,8 +637,20 @@ fn gen_impl_delegation_trait_defs(
                 if let Some(first_arg) = trait_fn.entrait_sig.sig.inputs.first_mut() {
                     if let syn::FnArg::Receiver(receiver) = first_arg {
                         *first_arg = if let Some((and, lifetime)) = receiver.reference.clone() {
+                            let mutability = receiver.mutability;
+                            syn::parse_quote! {
+                                __impl: #and #lifetime #mutability ::#entrait::Impl<EntraitT>
+                            }
+                        } else if let Some((arc_path, _)) = receiver
+                            .colon_token
+                            .and_then(|_| generics::arc_deps(&receiver.ty))
+                        {
+                            let ty = generics::arc_with_pointee(
+                                arc_path,
+                                syn::parse_quote!(::#entrait::Impl<EntraitT>),
+                            );
                             syn::parse_quote! {
-                                __impl: #and #lifetime ::#entrait::Impl<EntraitT>
+                                __impl: #ty
                             }
                         } else {
                             syn::parse_quote! {
@@ -178,44 +661,98 @@ fn gen_impl_delegation_trait_defs(
                 }
             }
 
+            let defaults_trait_def = gen_defaults_trait(
+                out_trait,
+                &mut trait_copy,
+                trait_dependency_mode,
+                impl_sub_attributes,
+                attr,
+            )?;
+
             let trait_def = TraitCodegen {
                 crate_idents: &attr.crate_idents,
                 opts: &no_mock_opts,
                 trait_indirection: generics::TraitIndirection::StaticImpl,
                 trait_dependency_mode,
                 sub_attributes: impl_sub_attributes,
+                on_unimplemented: Some(OnUnimplemented::impl_trait(
+                    impl_trait_ident,
+                    &out_trait.ident,
+                    delegate,
+                )),
             }
             .gen_trait_def(
                 &trait_copy.vis,
                 &trait_copy.ident,
                 &trait_copy.generics,
-                &Supertraits::Some {
-                    colon_token: syn::token::Colon::default(),
-                    bounds: syn::parse_quote! { 'static },
-                },
+                &impl_trait_supertraits(out_trait),
+                &trait_copy.assoc_items,
                 &trait_copy.fns,
                 &FnInputMode::RawTrait(LiteralAttrs(&[])),
             )?;
 
+            let impl_trait_args = trait_copy
+                .generics
+                .arguments(&generics::ImplIndirection::None);
+            let delegation_params = trait_copy.generics.trait_params();
+            let delegation_where_clause = trait_copy.generics.trait_where_clause();
+            let on_unimplemented = OnUnimplemented::delegation_trait(
+                delegation_ident,
+                &out_trait.ident,
+                impl_trait_ident,
+            );
+            let scoped_delegation_impl = gen_scoped_delegation_impl(
+                out_trait,
+                delegation_ident,
+                impl_trait_ident,
+                &attr.crate_idents,
+            );
+            let selector = if candidates.is_empty() {
+                None
+            } else {
+                Some(gen_selector(
+                    out_trait,
+                    &trait_copy,
+                    candidates,
+                    impl_sub_attributes,
+                    attr,
+                )?)
+            };
+
             Ok(Some(quote! {
+                #defaults_trait_def
+
                 #(#impl_sub_attributes)*
                 #trait_def
 
-                pub trait #delegation_ident<T> {
-                    type Target: #impl_trait_ident<T>;
+                #on_unimplemented
+                pub trait #delegation_ident #delegation_params #delegation_where_clause {
+                    type Target: #impl_trait_ident #impl_trait_args;
                 }
+
+                #scoped_delegation_impl
+
+                #selector
             }))
         }
-        Some(SpanOpt(Delegate::ByRef(_), _)) => {
-            trait_copy.generics.params.insert(
-                0,
-                syn::parse_quote! {
-                    EntraitT
-                },
-            );
+        Some(SpanOpt(delegate @ Delegate::ByRef(_), _)) => {
+            trait_copy.generics = impl_trait_generics(out_trait, generic_idents);
             for trait_fn in trait_copy.fns.iter_mut() {
-                if !matches!(trait_fn.sig().inputs.first(), Some(syn::FnArg::Receiver(_))) {
-                    continue;
+                let receiver = match trait_fn.sig().inputs.first() {
+                    Some(syn::FnArg::Receiver(receiver)) => receiver,
+                    _ => continue,
+                };
+                if receiver.reference.is_some() && receiver.mutability.is_some() {
+                    return Err(syn::Error::new(
+                        receiver.span(),
+                        "`&mut self` methods can't be delegated by `ref`, as the delegation target is only available by shared reference",
+                    ));
+                }
+                if is_arc_self(receiver) {
+                    return Err(syn::Error::new(
+                        receiver.span(),
+                        "`self: Arc<Self>` methods can't be delegated by `ref`, as the delegation target is only available by shared reference",
+                    ));
                 }
 
                 trait_fn.entrait_sig.sig.inputs.insert(
@@ -233,28 +770,70 @@ fn gen_impl_delegation_trait_defs(
                 ..attr.opts
             };
 
+            let defaults_trait_def = gen_defaults_trait(
+                out_trait,
+                &mut trait_copy,
+                trait_dependency_mode,
+                impl_sub_attributes,
+                attr,
+            )?;
+
             let trait_def = TraitCodegen {
                 crate_idents: &attr.crate_idents,
                 opts: &no_mock_opts,
                 trait_indirection: generics::TraitIndirection::DynamicImpl,
                 trait_dependency_mode,
                 sub_attributes: impl_sub_attributes,
+                on_unimplemented: Some(OnUnimplemented::impl_trait(
+                    impl_trait_ident,
+                    &out_trait.ident,
+                    delegate,
+                )),
             }
             .gen_trait_def(
                 &trait_copy.vis,
                 &trait_copy.ident,
                 &trait_copy.generics,
-                &Supertraits::Some {
-                    colon_token: syn::token::Colon::default(),
-                    bounds: syn::parse_quote! { 'static },
-                },
+                &impl_trait_supertraits(out_trait),
+                &trait_copy.assoc_items,
                 &trait_copy.fns,
                 &FnInputMode::RawTrait(LiteralAttrs(&[])),
             )?;
 
+            let dyn_shadow_trait_def = if dyn_shadow.0 {
+                Some(
+                    dyn_shadow::DynShadowTrait {
+                        vis: &trait_copy.vis,
+                        trait_ident: &trait_copy.ident,
+                        generics: &trait_copy.generics,
+                        supertraits: &impl_trait_supertraits(out_trait),
+                        assoc_items: &trait_copy.assoc_items,
+                        fns: &trait_copy.fns,
+                        opts: &attr.opts,
+                        crate_idents: &attr.crate_idents,
+                    }
+                    .gen(),
+                )
+            } else {
+                None
+            };
+
+            let choice_enum = match &attr.delegation_kind {
+                Some(SpanOpt(Delegate::ByRef(RefDelegate::Enum(variants)), _)) => Some(
+                    gen_choice_enum(out_trait, &trait_copy, variants, impl_sub_attributes, attr),
+                ),
+                _ => None,
+            };
+
             Ok(Some(quote! {
+                #defaults_trait_def
+
                 #(#impl_sub_attributes)*
                 #trait_def
+
+                #dyn_shadow_trait_def
+
+                #choice_enum
             }))
         }
         _ => Err(syn::Error::new(
@@ -264,13 +843,337 @@ fn gen_impl_delegation_trait_defs(
     }
 }
 
+/// Provided methods of the impl trait fall back to the default bodies of the entraited trait.
+///
+/// The default bodies are copied into a private extension trait of the entraited trait,
+/// which is implemented for all its implementors, including `Impl<T>`.
+/// The methods are renamed, so that method calls in the default bodies stay unambiguous.
+fn gen_defaults_trait(
+    out_trait: &OutTrait,
+    impl_trait_copy: &mut OutTrait,
+    trait_dependency_mode: &TraitDependencyMode,
+    impl_sub_attributes: &[SubAttribute],
+    attr: &EntraitTraitAttr,
+) -> syn::Result<Option<TokenStream>> {
+    if !out_trait
+        .fns
+        .iter()
+        .any(|trait_fn| trait_fn.default.is_some())
+    {
+        return Ok(None);
+    }
+
+    let entrait = &attr.crate_idents.entrait;
+    let trait_ident = &out_trait.ident;
+    let args = out_trait
+        .generics
+        .arguments(&generics::ImplIndirection::None);
+    let defaults_ident = quote::format_ident!("__{}Defaults", trait_ident);
+
+    let default_fns: Vec<_> = out_trait
+        .fns
+        .iter()
+        .filter(|trait_fn| trait_fn.default.is_some())
+        .cloned()
+        .map(|mut trait_fn| {
+            trait_fn.entrait_sig.sig.ident = default_fn_ident(trait_fn.sig());
+            // The default body of an entraited fn takes `self` as its dependency
+            if let FnDeps::Generic { trait_bounds, .. } = &trait_fn.deps {
+                let opt_sync = opt_plus_sync_deps(&trait_fn, attr);
+                trait_fn
+                    .entrait_sig
+                    .sig
+                    .generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! {
+                        Self: Sized #(+ #trait_bounds)* #opt_sync
+                    });
+            }
+            trait_fn
+        })
+        .collect();
+
+    for trait_fn in impl_trait_copy.fns.iter_mut() {
+        if trait_fn.default.is_none() {
+            continue;
+        }
+
+        let default_ident = default_fn_ident(trait_fn.sig());
+        let arguments = trait_fn.sig().inputs.iter().filter_map(|arg| match arg {
+            syn::FnArg::Receiver(_) => None,
+            syn::FnArg::Typed(pat_type) => Some(&pat_type.pat),
+        });
+        let opt_dot_await = trait_fn.opt_dot_await(Span::call_site());
+        let deps_bounds = deps_trait_bounds(trait_fn).to_vec();
+        let opt_sync = opt_plus_sync_deps(trait_fn, attr);
+
+        trait_fn.default = Some(syn::parse_quote! {
+            {
+                <::#entrait::Impl<EntraitT> as #defaults_ident #args>::#default_ident(#(#arguments),*) #opt_dot_await
+            }
+        });
+        trait_fn
+            .entrait_sig
+            .sig
+            .generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote! {
+                ::#entrait::Impl<EntraitT>: #trait_ident #args #(+ #deps_bounds)* #opt_sync
+            });
+    }
+
+    let no_mock_opts = Opts {
+        mock_api: None,
+        unimock: None,
+        mockall: None,
+        ..attr.opts
+    };
+
+    let trait_def = TraitCodegen {
+        crate_idents: &attr.crate_idents,
+        opts: &no_mock_opts,
+        trait_indirection: generics::TraitIndirection::Plain,
+        trait_dependency_mode,
+        sub_attributes: impl_sub_attributes,
+        on_unimplemented: None,
+    }
+    .gen_trait_def(
+        &syn::Visibility::Inherited,
+        &defaults_ident,
+        &out_trait.generics,
+        &Supertraits::Some {
+            colon_token: syn::token::Colon::default(),
+            bounds: syn::parse_quote! { #trait_ident #args },
+        },
+        &[],
+        &default_fns,
+        &FnInputMode::RawTrait(LiteralAttrs(&[])),
+    )?;
+
+    let blanket_generics = out_trait.generics.with_type_param(syn::parse_quote! {
+        EntraitSelf: ?Sized + #trait_ident #args
+    });
+    let params = blanket_generics.trait_params();
+    let where_clause = out_trait.generics.trait_where_clause();
+
+    Ok(Some(quote! {
+        #(#impl_sub_attributes)*
+        #trait_def
+
+        impl #params #defaults_ident #args for EntraitSelf #where_clause {}
+    }))
+}
+
+/// The generics of the impl trait, which is generic over the `T` in `Impl<T>`
+fn impl_trait_generics(
+    out_trait: &OutTrait,
+    generic_idents: &GenericIdents,
+) -> generics::TraitGenerics {
+    let impl_t = &generic_idents.impl_t;
+    out_trait
+        .generics
+        .with_type_param(syn::parse_quote! { #impl_t })
+}
+
+/// The impl trait is `'static`, unless it has lifetime params.
+/// Those would have to outlive `'static` for calls through `dyn` to type check.
+fn is_static_impl_trait(out_trait: &OutTrait) -> bool {
+    !out_trait
+        .generics
+        .params
+        .iter()
+        .any(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+}
+
+/// The impl trait inherits the lifetime and auto trait supertraits of the entraited trait,
+/// so that e.g. a `Send + Sync` trait object of it can be delegated to.
+/// The other supertraits are bounds on `Impl<T>`, which is the one implementing the entraited trait.
+fn impl_trait_supertraits(out_trait: &OutTrait) -> Supertraits {
+    let mut bounds: syn::punctuated::Punctuated<syn::TypeParamBound, syn::token::Plus> =
+        Default::default();
+
+    if is_static_impl_trait(out_trait) {
+        bounds.push(syn::parse_quote! { 'static });
+    }
+
+    for bound in out_trait.supertraits.bounds() {
+        let inherit = match bound {
+            syn::TypeParamBound::Lifetime(lifetime) => {
+                !(lifetime.ident == "static" && is_static_impl_trait(out_trait))
+            }
+            syn::TypeParamBound::Trait(trait_bound) => is_auto_trait_bound(trait_bound),
+            _ => false,
+        };
+        if inherit {
+            bounds.push(bound.clone());
+        }
+    }
+
+    if bounds.is_empty() {
+        Supertraits::None
+    } else {
+        Supertraits::Some {
+            colon_token: syn::token::Colon::default(),
+            bounds,
+        }
+    }
+}
+
+fn is_auto_trait_bound(trait_bound: &syn::TraitBound) -> bool {
+    trait_bound.lifetimes.is_none()
+        && matches!(trait_bound.modifier, syn::TraitBoundModifier::None)
+        && trait_bound
+            .path
+            .segments
+            .last()
+            .map(|segment| {
+                segment.arguments.is_empty()
+                    && (segment.ident == "Send"
+                        || segment.ident == "Sync"
+                        || segment.ident == "Unpin")
+            })
+            .unwrap_or(false)
+}
+
+/// The trait bounds of the generic dependency of an entraited fn
+fn deps_trait_bounds(trait_fn: &TraitFn) -> &[syn::TypeParamBound] {
+    match &trait_fn.deps {
+        FnDeps::Generic { trait_bounds, .. } => trait_bounds,
+        _ => &[],
+    }
+}
+
+/// The dependency of an async entraited fn is borrowed across `.await` in its default body,
+/// so it has to be `Sync` for the future to be `Send`.
+fn opt_plus_sync_deps(trait_fn: &TraitFn, attr: &EntraitTraitAttr) -> Option<TokenStream> {
+    match &trait_fn.deps {
+        FnDeps::Generic { .. }
+            if trait_fn.originally_async && trait_fn.future_send(&attr.opts).0 =>
+        {
+            Some(quote! { + Sync })
+        }
+        _ => None,
+    }
+}
+
+/// The ident of the method carrying the default body of a provided method, i.e. `__foo_default`
+fn default_fn_ident(sig: &syn::Signature) -> syn::Ident {
+    quote::format_ident!("__{}_default", sig.ident)
+}
+
+/// Associated types and consts of `Impl<T>` are taken from the type it delegates to
+fn gen_assoc_item_impls(
+    out_trait: &OutTrait,
+    generic_idents: &GenericIdents,
+    impl_trait_generics: &generics::TraitGenerics,
+    attr: &EntraitTraitAttr,
+) -> syn::Result<Vec<TokenStream>> {
+    let first_assoc_item = match out_trait.assoc_items.first() {
+        Some(assoc_item) => assoc_item,
+        None => return Ok(vec![]),
+    };
+    let impl_t = &generic_idents.impl_t;
+
+    let delegate = match (&attr.impl_trait, &attr.delegation_kind) {
+        (
+            Some(ImplTrait(_, impl_trait_ident)),
+            Some(SpanOpt(Delegate::ByTrait(delegation_ident, _), _)),
+        ) => {
+            let impl_trait_args = impl_trait_generics.arguments(&generics::ImplIndirection::None);
+            quote! {
+                <#impl_t as #delegation_ident #impl_trait_args>::Target as #impl_trait_ident #impl_trait_args
+            }
+        }
+        (impl_trait, Some(SpanOpt(Delegate::ByRef(RefDelegate::Deref), _))) => {
+            let core = &generic_idents.crate_idents.core;
+            let (target_ident, target_args) = match impl_trait {
+                Some(ImplTrait(_, impl_trait_ident)) => (
+                    impl_trait_ident,
+                    impl_trait_generics.arguments(&generics::ImplIndirection::None),
+                ),
+                None => (
+                    &out_trait.ident,
+                    out_trait
+                        .generics
+                        .arguments(&generics::ImplIndirection::None),
+                ),
+            };
+            quote! {
+                <#impl_t as ::#core::ops::Deref>::Target as #target_ident #target_args
+            }
+        }
+        (_, Some(SpanOpt(Delegate::ByRef(RefDelegate::Enum(_)), _))) => {
+            return Err(syn::Error::new(
+                first_assoc_item.ident().span(),
+                "Associated types and consts are not supported with `delegate_by = enum(..)`, as the implementations may disagree on them",
+            ));
+        }
+        (_, Some(SpanOpt(Delegate::ByRef(_), _))) => {
+            // The `dyn` trait object has its associated types pinned
+            return out_trait
+                .assoc_items
+                .iter()
+                .map(|assoc_item| match assoc_item {
+                    AssocItem::Type {
+                        item,
+                        dyn_type: Some(dyn_type),
+                        ..
+                    } => {
+                        let ident = &item.ident;
+                        Ok(quote! { type #ident = #dyn_type; })
+                    }
+                    AssocItem::Type { item, .. } => Err(syn::Error::new(
+                        item.ident.span(),
+                        "Associated types of a trait delegated through a `dyn` trait object must be pinned with `#[entrait(dyn = Type)]`",
+                    )),
+                    AssocItem::Const { item, .. } => Err(syn::Error::new(
+                        item.ident.span(),
+                        "Associated consts are not supported when delegating through a `dyn` trait object, as they can't be part of it",
+                    )),
+                })
+                .collect();
+        }
+        _ => {
+            let trait_ident = &out_trait.ident;
+            let args = out_trait
+                .generics
+                .arguments(&generics::ImplIndirection::None);
+            quote! { #impl_t as #trait_ident #args }
+        }
+    };
+
+    Ok(out_trait
+        .assoc_items
+        .iter()
+        .map(|assoc_item| assoc_item.gen_forwarding_impl_item(&delegate))
+        .collect())
+}
+
 fn gen_delegation_method<'s>(
+    out_trait: &OutTrait,
     trait_fn: &'s TraitFn,
     generic_idents: &'s GenericIdents,
+    impl_trait_generics: &'s generics::TraitGenerics,
     attr: &'s EntraitTraitAttr,
     contains_async: ContainsAsync,
-) -> DelegatingMethod<'s> {
+    dyn_shadow: DynShadow,
+) -> syn::Result<DelegatingMethod<'s>> {
     let fn_sig = &trait_fn.sig();
+
+    if fn_sig.receiver().map(is_arc_self).unwrap_or(false)
+        && !matches!(
+            attr.delegation_kind,
+            Some(SpanOpt(Delegate::ByTrait(..), _))
+        )
+    {
+        return Err(syn::Error::new(
+            fn_sig.span(),
+            "`self: Arc<Self>` methods can only be implemented for `Impl<T>` with `delegate_by = DelegateTrait`",
+        ));
+    }
+
     let fn_ident = &fn_sig.ident;
     let impl_t = &generic_idents.impl_t;
 
@@ -282,18 +1185,19 @@ fn gen_delegation_method<'s>(
         },
     });
     let core = &generic_idents.crate_idents.core;
+    let impl_trait_args = impl_trait_generics.arguments(&generics::ImplIndirection::None);
 
-    match (&attr.impl_trait, &attr.delegation_kind) {
-        (Some(ImplTrait(_, impl_trait_ident)), Some(SpanOpt(Delegate::ByTrait(_), _))) => {
-            DelegatingMethod {
-                trait_fn,
-                call: quote! {
-                    // TODO: pass additional generic arguments(?)
-                    <#impl_t::Target as #impl_trait_ident<#impl_t>>::#fn_ident(self, #(#arguments),*)
-                },
-            }
-        }
-        (Some(ImplTrait(_, impl_trait_ident)), Some(SpanOpt(Delegate::ByRef(ref_delegate), _))) => {
+    Ok(match (&attr.impl_trait, &attr.delegation_kind) {
+        (
+            Some(ImplTrait(_, impl_trait_ident)),
+            Some(SpanOpt(Delegate::ByTrait(delegation_ident, _), _)),
+        ) => DelegatingMethod {
+            trait_fn,
+            call: quote! {
+                <<#impl_t as #delegation_ident #impl_trait_args>::Target as #impl_trait_ident #impl_trait_args>::#fn_ident(self, #(#arguments),*)
+            },
+        },
+        (Some(ImplTrait(..)), Some(SpanOpt(Delegate::ByRef(ref_delegate), _))) => {
             let plus_sync = if contains_async.0 {
                 Some(TokenPair(
                     syn::token::Plus::default(),
@@ -302,16 +1206,47 @@ fn gen_delegation_method<'s>(
             } else {
                 None
             };
+            let dyn_target_ident = dyn_target_ident(out_trait, attr, dyn_shadow);
+            let dyn_bindings = dyn_bindings(out_trait);
+            let dyn_target_args = impl_trait_generics.dyn_arguments(&dyn_bindings);
             let call = match ref_delegate {
                 RefDelegate::AsRef => {
                     quote! {
-                        <#impl_t as ::#core::convert::AsRef<dyn #impl_trait_ident<#impl_t> #plus_sync>>::as_ref(&*self)
+                        <#impl_t as ::#core::convert::AsRef<dyn #dyn_target_ident #dyn_target_args #plus_sync>>::as_ref(&*self)
                             .#fn_ident(self, #(#arguments),*)
                     }
                 }
                 RefDelegate::Borrow => {
                     quote! {
-                        <#impl_t as ::#core::borrow::Borrow<dyn #impl_trait_ident<#impl_t> #plus_sync>>::borrow(&*self)
+                        <#impl_t as ::#core::borrow::Borrow<dyn #dyn_target_ident #dyn_target_args #plus_sync>>::borrow(&*self)
+                            .#fn_ident(self, #(#arguments),*)
+                    }
+                }
+                RefDelegate::Deref => {
+                    quote! {
+                        <#impl_t as ::#core::ops::Deref>::deref(&*self)
+                            .#fn_ident(self, #(#arguments),*)
+                    }
+                }
+                RefDelegate::Arc | RefDelegate::Box => {
+                    let (accessor_ident, method_ident) =
+                        accessor_idents(&out_trait.ident, ref_delegate).unwrap();
+                    quote! {
+                        <#impl_t as #accessor_ident #impl_trait_args>::#method_ident(&*self)
+                            .#fn_ident(self, #(#arguments),*)
+                    }
+                }
+                RefDelegate::Swap => {
+                    let current = swapped_target(trait_fn, out_trait, impl_t, &impl_trait_args)?;
+                    quote! {
+                        #current.#fn_ident(self, #(#arguments),*)
+                    }
+                }
+                RefDelegate::Enum(_) => {
+                    let (accessor_ident, method_ident) =
+                        accessor_idents(&out_trait.ident, ref_delegate).unwrap();
+                    quote! {
+                        <#impl_t as #accessor_ident>::#method_ident(&*self)
                             .#fn_ident(self, #(#arguments),*)
                     }
                 }
@@ -331,13 +1266,61 @@ fn gen_delegation_method<'s>(
                 self.as_ref().borrow().#fn_ident(#(#arguments),*)
             },
         },
+        (None, Some(SpanOpt(Delegate::ByRef(RefDelegate::Deref), _))) => DelegatingMethod {
+            trait_fn,
+            call: quote! {
+                <#impl_t as ::#core::ops::Deref>::deref(&*self).#fn_ident(#(#arguments),*)
+            },
+        },
+        (None, Some(SpanOpt(Delegate::ByRef(RefDelegate::Swap), _))) => {
+            let trait_args = out_trait
+                .generics
+                .arguments(&generics::ImplIndirection::None);
+            let current = swapped_target(trait_fn, out_trait, impl_t, &trait_args)?;
+            DelegatingMethod {
+                trait_fn,
+                call: quote! {
+                    #current.#fn_ident(#(#arguments),*)
+                },
+            }
+        }
+        (None, Some(SpanOpt(Delegate::ByRef(ref_delegate), _))) => {
+            let (accessor_ident, method_ident) =
+                accessor_idents(&out_trait.ident, ref_delegate).unwrap();
+            let trait_args = out_trait
+                .generics
+                .arguments(&generics::ImplIndirection::None);
+            DelegatingMethod {
+                trait_fn,
+                call: quote! {
+                    <#impl_t as #accessor_ident #trait_args>::#method_ident(&*self).#fn_ident(#(#arguments),*)
+                },
+            }
+        }
+        _ if is_mut_self_ref(fn_sig) => DelegatingMethod {
+            trait_fn,
+            call: quote! {
+                ::#core::ops::DerefMut::deref_mut(self).#fn_ident(#(#arguments),*)
+            },
+        },
         _ => DelegatingMethod {
             trait_fn,
             call: quote! {
                 self.as_ref().#fn_ident(#(#arguments),*)
             },
         },
-    }
+    })
+}
+
+fn is_arc_self(receiver: &syn::Receiver) -> bool {
+    receiver.colon_token.is_some() && generics::arc_deps(&receiver.ty).is_some()
+}
+
+fn is_mut_self_ref(sig: &syn::Signature) -> bool {
+    matches!(
+        sig.inputs.first(),
+        Some(syn::FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_some()
+    )
 }
 
 struct DelegatingMethod<'s> {
@@ -389,7 +1372,9 @@ impl ToTokens for DelegatingMethod<'_> {
 struct ImplWhereClause<'g, 'c> {
     out_trait: &'g OutTrait,
     contains_async: ContainsAsync,
+    dyn_shadow: DynShadow,
     trait_generics: &'g generics::TraitGenerics,
+    impl_trait_generics: &'g generics::TraitGenerics,
     generic_idents: &'g GenericIdents<'c>,
     attr: &'g EntraitTraitAttr,
     span: proc_macro2::Span,
@@ -398,41 +1383,36 @@ struct ImplWhereClause<'g, 'c> {
 impl ImplWhereClause<'_, '_> {
     fn push_impl_t_bounds(&self, stream: &mut TokenStream) {
         use syn::token::*;
+        let dyn_bindings = dyn_bindings(self.out_trait);
 
         push_tokens!(stream, self.generic_idents.impl_t, Colon(self.span));
 
         match (&self.attr.impl_trait, &self.attr.delegation_kind) {
-            (Some(_), Some(SpanOpt(Delegate::ByTrait(delegate_ident), _))) => {
+            (Some(_), Some(SpanOpt(Delegate::ByTrait(delegate_ident, _), _))) => {
                 push_tokens!(
                     stream,
                     delegate_ident,
-                    Lt(self.span),
-                    self.generic_idents.impl_t,
-                    Gt(self.span),
+                    self.impl_trait_arguments(),
                     self.plus_sync(),
                     self.plus_static()
                 );
             }
-            (
-                Some(ImplTrait(_, impl_trait_ident)),
-                Some(SpanOpt(Delegate::ByRef(ref_delegate), _)),
-            ) => {
-                self.push_core_delegation_trait(stream, ref_delegate);
-                push_tokens!(
+            (Some(ImplTrait(..)), Some(SpanOpt(Delegate::ByRef(ref_delegate), _))) => {
+                self.push_ref_delegation_trait(
                     stream,
-                    // Generic arguments:
-                    Lt(self.span),
-                    Dyn(self.span),
-                    impl_trait_ident,
-                    Lt(self.span),
-                    self.generic_idents.impl_t,
-                    Gt(self.span),
-                    if self.contains_async.0 {
-                        Some(self.plus_sync())
-                    } else {
-                        None
-                    },
-                    Gt(self.span)
+                    ref_delegate,
+                    TokenPair(
+                        TokenPair(
+                            dyn_target_ident(self.out_trait, self.attr, self.dyn_shadow),
+                            self.impl_trait_generics.dyn_arguments(&dyn_bindings),
+                        ),
+                        if self.contains_async.0 {
+                            Some(self.plus_sync())
+                        } else {
+                            None
+                        },
+                    ),
+                    self.impl_trait_arguments(),
                 );
 
                 if self.contains_async.0 {
@@ -441,13 +1421,15 @@ impl ImplWhereClause<'_, '_> {
                 push_tokens!(stream, self.plus_static());
             }
             (None, Some(SpanOpt(Delegate::ByRef(ref_delegate), _))) => {
-                self.push_core_delegation_trait(stream, ref_delegate);
-                push_tokens!(
+                self.push_ref_delegation_trait(
                     stream,
-                    Lt(self.span),
-                    Dyn(self.span),
-                    self.trait_with_arguments(),
-                    Gt(self.span)
+                    ref_delegate,
+                    TokenPair(
+                        dyn_target_ident(self.out_trait, self.attr, self.dyn_shadow),
+                        self.trait_generics.dyn_arguments(&dyn_bindings),
+                    ),
+                    self.trait_generics
+                        .arguments(&generics::ImplIndirection::None),
                 );
 
                 if self.contains_async.0 {
@@ -466,6 +1448,42 @@ impl ImplWhereClause<'_, '_> {
         }
     }
 
+    /// The trait that `T` delegates through, given the `dyn` target trait with its arguments
+    fn push_ref_delegation_trait(
+        &self,
+        stream: &mut TokenStream,
+        ref_delegate: &RefDelegate,
+        target_trait: impl ToTokens,
+        accessor_args: impl ToTokens,
+    ) {
+        use syn::token::*;
+        match ref_delegate {
+            RefDelegate::AsRef | RefDelegate::Borrow => {
+                self.push_core_delegation_trait(stream, ref_delegate);
+                push_tokens!(
+                    stream,
+                    Lt(self.span),
+                    Dyn(self.span),
+                    target_trait,
+                    Gt(self.span)
+                );
+            }
+            RefDelegate::Deref => {
+                push_tokens!(stream, self.deref_path());
+            }
+            RefDelegate::Arc | RefDelegate::Box | RefDelegate::Swap => {
+                let (accessor_ident, _) =
+                    accessor_idents(&self.out_trait.ident, ref_delegate).unwrap();
+                push_tokens!(stream, accessor_ident, accessor_args);
+            }
+            RefDelegate::Enum(_) => {
+                let (accessor_ident, _) =
+                    accessor_idents(&self.out_trait.ident, ref_delegate).unwrap();
+                push_tokens!(stream, accessor_ident);
+            }
+        }
+    }
+
     fn push_core_delegation_trait(&self, stream: &mut TokenStream, ref_delegate: &RefDelegate) {
         use syn::token::*;
         match ref_delegate {
@@ -491,9 +1509,22 @@ impl ImplWhereClause<'_, '_> {
                     syn::Ident::new("Borrow", self.span)
                 );
             }
+            _ => {}
+        }
+    }
+
+    fn is_dyn_delegation(&self) -> bool {
+        match &self.attr.delegation_kind {
+            Some(SpanOpt(Delegate::ByRef(ref_delegate), _)) => ref_delegate.is_dyn(),
+            _ => false,
         }
     }
 
+    fn deref_path(&self) -> TokenStream {
+        let core = &self.generic_idents.crate_idents.core;
+        quote! { ::#core::ops::Deref }
+    }
+
     fn trait_with_arguments(&self) -> TokenPair<impl ToTokens + '_, impl ToTokens + '_> {
         TokenPair(
             &self.out_trait.ident,
@@ -502,6 +1533,11 @@ impl ImplWhereClause<'_, '_> {
         )
     }
 
+    fn impl_trait_arguments(&self) -> impl ToTokens + '_ {
+        self.impl_trait_generics
+            .arguments(&generics::ImplIndirection::None)
+    }
+
     fn plus_static(&self) -> TokenPair<impl ToTokens, impl ToTokens> {
         TokenPair(
             syn::token::Plus(self.span),
@@ -538,6 +1574,83 @@ impl quote::ToTokens for ImplWhereClause<'_, '_> {
             self.push_impl_t_bounds(stream);
         });
 
+        // The provided methods of the impl trait of entraited fns depend on `Impl<T>`:
+        for trait_fn in &self.out_trait.fns {
+            let deps_bounds = deps_trait_bounds(trait_fn);
+            if !deps_bounds.is_empty() {
+                let impl_path = self.generic_idents.impl_path(self.span);
+                punctuator.push(quote! { #impl_path: #(#deps_bounds)+* });
+            }
+        }
+
+        // `Impl<T>` must also implement the supertraits:
+        if let Supertraits::Some { bounds, .. } = &self.out_trait.supertraits {
+            punctuator.push_fn(|stream| {
+                push_tokens!(
+                    stream,
+                    self.generic_idents.impl_path(self.span),
+                    syn::token::Colon(self.span),
+                    bounds
+                );
+            });
+        }
+
+        // The statically dispatched delegation target implements the trait:
+        let static_target = match &self.attr.delegation_kind {
+            Some(SpanOpt(Delegate::ByRef(RefDelegate::Deref), _)) => {
+                let impl_t = &self.generic_idents.impl_t;
+                let deref_path = self.deref_path();
+                Some(quote! { <#impl_t as #deref_path>::Target })
+            }
+            Some(SpanOpt(Delegate::ByRef(RefDelegate::Enum(_)), _)) => {
+                Some(choice_enum_ident(&self.out_trait.ident).into_token_stream())
+            }
+            _ => None,
+        };
+        if let Some(static_target) = static_target {
+            let target_trait = match &self.attr.impl_trait {
+                Some(ImplTrait(_, impl_trait_ident)) => {
+                    let args = self.impl_trait_arguments();
+                    quote! { #impl_trait_ident #args }
+                }
+                None => self.trait_with_arguments().into_token_stream(),
+            };
+            let opt_sync = if self.contains_async.0 {
+                Some(self.plus_sync())
+            } else {
+                None
+            };
+            punctuator.push(quote! {
+                #static_target: #target_trait #opt_sync
+            });
+        }
+
+        // The `'static` impl trait is used as `dyn` object, so its type params must be `'static` too:
+        if let (Some(_), true, true) = (
+            &self.attr.impl_trait,
+            self.is_dyn_delegation(),
+            is_static_impl_trait(self.out_trait),
+        ) {
+            for type_param in self
+                .trait_generics
+                .params
+                .iter()
+                .filter_map(|param| match param {
+                    syn::GenericParam::Type(type_param) => Some(type_param),
+                    _ => None,
+                })
+            {
+                punctuator.push_fn(|stream| {
+                    push_tokens!(
+                        stream,
+                        type_param.ident,
+                        syn::token::Colon(self.span),
+                        syn::Lifetime::new("'static", self.span)
+                    );
+                });
+            }
+        }
+
         for predicate in &self.trait_generics.where_predicates {
             punctuator.push(predicate);
         }
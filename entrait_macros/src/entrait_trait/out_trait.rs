@@ -1,10 +1,13 @@
 use crate::{
     analyze_generics::TraitFn,
+    entrait_fn::input_attr::is_entrait_attr,
     generics::{FnDeps, TraitGenerics},
-    signature::EntraitSignature,
-    trait_codegen::{self, Supertraits},
+    signature::{self, EntraitSignature},
+    trait_codegen::{self, AssocItem, Supertraits},
 };
 
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
 
 #[derive(Clone)]
@@ -16,29 +19,67 @@ pub struct OutTrait {
     pub generics: TraitGenerics,
     pub ident: syn::Ident,
     pub supertraits: trait_codegen::Supertraits,
+    pub assoc_items: Vec<AssocItem>,
     pub fns: Vec<TraitFn>,
 }
 
 pub fn analyze_trait(item_trait: syn::ItemTrait) -> syn::Result<OutTrait> {
-    let mut associated_types = vec![];
+    let mut assoc_items = vec![];
     let mut fns = vec![];
 
     for item in item_trait.items.into_iter() {
         match item {
             syn::TraitItem::Fn(method) => {
-                let originally_async = method.sig.asyncness.is_some();
+                let mut sig = method.sig;
+                let desugared = signature::desugar_future_output(&mut sig);
+                let originally_async = sig.asyncness.is_some();
+                let default = match method.default {
+                    // The body evaluates to the future, which the generated async body awaits
+                    Some(default) if desugared => Some(syn::parse_quote! {
+                        { (#default).await }
+                    }),
+                    default => default,
+                };
 
-                let entrait_sig = EntraitSignature::new(method.sig);
+                let entrait_sig = EntraitSignature::new(sig);
 
                 fns.push(TraitFn {
                     deps: FnDeps::NoDeps,
+                    deps_span: entrait_sig.sig.ident.span(),
                     attrs: method.attrs,
                     entrait_sig,
                     originally_async,
+                    renamed_from: None,
+                    future_send: None,
+                    default,
                 });
             }
-            syn::TraitItem::Type(ty) => {
-                associated_types.push(ty);
+            syn::TraitItem::Type(mut item) => {
+                if !item.generics.params.is_empty() {
+                    return Err(syn::Error::new(
+                        item.generics.span(),
+                        "Entrait does not support generic associated types",
+                    ));
+                }
+                let AssocItemAttrs { mock, dyn_type } = AssocItemAttrs::extract(&mut item.attrs)?;
+                assoc_items.push(AssocItem::Type {
+                    item,
+                    mock: mock.map(syn::parse2).transpose()?,
+                    dyn_type,
+                });
+            }
+            syn::TraitItem::Const(mut item) => {
+                let AssocItemAttrs { mock, dyn_type } = AssocItemAttrs::extract(&mut item.attrs)?;
+                if let Some(dyn_type) = dyn_type {
+                    return Err(syn::Error::new(
+                        dyn_type.span(),
+                        "`dyn` only pins associated types, as associated consts can't be part of a `dyn` trait object",
+                    ));
+                }
+                assoc_items.push(AssocItem::Const {
+                    item,
+                    mock: mock.map(syn::parse2).transpose()?,
+                });
             }
             item => {
                 return Err(syn::Error::new(
@@ -72,6 +113,63 @@ pub fn analyze_trait(item_trait: syn::ItemTrait) -> syn::Result<OutTrait> {
                 .unwrap_or_default(),
         },
         supertraits,
+        assoc_items,
         fns,
     })
 }
+
+/// The entrait attributes of an associated type or const
+#[derive(Default)]
+struct AssocItemAttrs {
+    /// The tokens of the mock type or value
+    mock: Option<proc_macro2::TokenStream>,
+    dyn_type: Option<syn::Type>,
+}
+
+impl AssocItemAttrs {
+    /// Remove the entrait attributes from the item
+    fn extract(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Self> {
+        let mut item_attrs = Self::default();
+        let mut result = Ok(());
+
+        attrs.retain(|attr| {
+            if !is_entrait_attr(attr) {
+                return true;
+            }
+            match attr.parse_args::<AssocItemAttr>() {
+                Ok(AssocItemAttr::Mock(mock)) => item_attrs.mock = Some(mock),
+                Ok(AssocItemAttr::Dyn(dyn_type)) => item_attrs.dyn_type = Some(dyn_type),
+                Err(err) => result = Err(err),
+            }
+            false
+        });
+
+        result.map(|_| item_attrs)
+    }
+}
+
+/// `#[entrait(mock = ...)]` or `#[entrait(dyn = Type)]` on an associated type or const
+enum AssocItemAttr {
+    Mock(proc_macro2::TokenStream),
+    Dyn(syn::Type),
+}
+
+impl Parse for AssocItemAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.call(syn::Ident::parse_any)?;
+        match ident.to_string().as_str() {
+            "mock" => {
+                input.parse::<syn::token::Eq>()?;
+                Ok(Self::Mock(input.parse()?))
+            }
+            "dyn" => {
+                input.parse::<syn::token::Eq>()?;
+                Ok(Self::Dyn(input.parse()?))
+            }
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("Unknown entrait option \"{ident}\""),
+            )),
+        }
+    }
+}
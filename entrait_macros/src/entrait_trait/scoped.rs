@@ -0,0 +1,165 @@
+//! The implementation of a trait for `Impl<Scoped<App, Ctx>>`.
+//!
+//! It forwards to `Impl<App>`, or to `Ctx` with the `context` option. The forwarding happens on the level of `Impl`,
+//! because `Impl<App>` is what a `dyn TraitImpl<App>` handed out by the application expects as its dependencies.
+//!
+//! With `delegate_by = DelegateTrait`, `Scoped` instead implements the delegation trait itself, selecting the same target as its application.
+
+use super::out_trait::OutTrait;
+use super::DelegatingMethod;
+use crate::analyze_generics::TraitFn;
+use crate::generics::{ImplIndirection, TraitGenerics};
+use crate::idents::CrateIdents;
+use crate::opt::Opts;
+use crate::sub_attributes::SubAttribute;
+use crate::trait_codegen::{AssocItem, Supertraits};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::ToTokens;
+
+pub struct ScopedImpl<'s> {
+    pub trait_ident: &'s syn::Ident,
+    pub generics: &'s TraitGenerics,
+    pub supertraits: &'s Supertraits,
+    pub assoc_items: &'s [AssocItem],
+    pub fns: &'s [TraitFn],
+    pub impl_sub_attributes: &'s [SubAttribute<'s>],
+    pub opts: &'s Opts,
+    pub crate_idents: &'s CrateIdents,
+}
+
+impl ScopedImpl<'_> {
+    pub fn gen(&self) -> TokenStream {
+        let entrait = &self.crate_idents.entrait;
+        let trait_ident = self.trait_ident;
+        let args = self.generics.arguments(&ImplIndirection::None);
+        let scoped = quote! { ::#entrait::Impl<::#entrait::Scoped<EntraitApp, EntraitCtx>> };
+
+        let (target, part) = if self.opts.context_value() {
+            (quote! { EntraitCtx }, Part::Ctx)
+        } else {
+            (quote! { ::#entrait::Impl<EntraitApp> }, Part::App)
+        };
+
+        let mut scoped_generics = self
+            .generics
+            .with_type_param(syn::parse_quote! { EntraitCtx })
+            .with_type_param(syn::parse_quote! { EntraitApp });
+        let predicates = &mut scoped_generics.where_predicates;
+        predicates.push(syn::parse_quote! { #target: #trait_ident #args });
+        if let Supertraits::Some { bounds, .. } = self.supertraits {
+            predicates.push(syn::parse_quote! { #scoped: #bounds });
+        }
+        // The futures borrow the whole scoped value:
+        if self
+            .fns
+            .iter()
+            .any(|trait_fn| trait_fn.originally_async && trait_fn.future_send(self.opts).0)
+        {
+            predicates.push(syn::parse_quote! { EntraitApp: Sync });
+            predicates.push(syn::parse_quote! { EntraitCtx: Sync });
+        }
+        let params = scoped_generics.trait_params();
+        let where_clause = scoped_generics.trait_where_clause();
+
+        let delegate = quote! { #target as #trait_ident #args };
+        let assoc_item_impls = self
+            .assoc_items
+            .iter()
+            .map(|assoc_item| assoc_item.gen_forwarding_impl_item(&delegate));
+
+        let method_items = self.fns.iter().map(|trait_fn| {
+            let fn_ident = &trait_fn.sig().ident;
+            let arguments = trait_fn.sig().inputs.iter().map(|arg| match arg {
+                syn::FnArg::Receiver(receiver) => part.access(receiver, entrait),
+                syn::FnArg::Typed(pat_type) => pat_type.pat.to_token_stream(),
+            });
+            DelegatingMethod {
+                trait_fn,
+                call: quote! {
+                    <#delegate>::#fn_ident(#(#arguments),*)
+                },
+            }
+        });
+        let impl_sub_attributes = self.impl_sub_attributes;
+
+        quote! {
+            #(#impl_sub_attributes)*
+            impl #params #trait_ident #args for #scoped #where_clause {
+                #(#assoc_item_impls)*
+                #(#method_items)*
+            }
+        }
+    }
+}
+
+/// The part of `Scoped` that is forwarded to
+enum Part {
+    App,
+    Ctx,
+}
+
+impl Part {
+    /// The receiver of the forwarded call, given the receiver of the method of `Impl<Scoped<App, Ctx>>`
+    fn access(&self, receiver: &syn::Receiver, entrait: &syn::Ident) -> TokenStream {
+        match (self, &receiver.reference, receiver.mutability) {
+            (Self::App, Some(_), None) => quote! { ::#entrait::Scoped::app_impl(self) },
+            (Self::App, Some(_), Some(_)) => quote! { ::#entrait::Scoped::app_impl_mut(self) },
+            (Self::App, None, _) => quote! {
+                ::#entrait::Impl::new(::#entrait::Scoped::into_parts(::#entrait::Impl::into_inner(self)).0)
+            },
+            (Self::Ctx, Some(_), None) => quote! { ::#entrait::Scoped::ctx(self) },
+            (Self::Ctx, Some(_), Some(_)) => quote! { ::#entrait::Scoped::ctx_mut(self) },
+            (Self::Ctx, None, _) => quote! {
+                ::#entrait::Scoped::into_parts(::#entrait::Impl::into_inner(self)).1
+            },
+        }
+    }
+}
+
+/// `Scoped` delegates to the same target as its application
+pub fn gen_scoped_delegation_impl(
+    out_trait: &OutTrait,
+    delegation_ident: &syn::Ident,
+    impl_trait_ident: &syn::Ident,
+    crate_idents: &CrateIdents,
+) -> TokenStream {
+    let entrait = &crate_idents.entrait;
+    let scoped = quote! { ::#entrait::Scoped<EntraitApp, EntraitCtx> };
+    let app_args = impl_trait_args_for(out_trait, quote! { EntraitApp });
+    let scoped_args = impl_trait_args_for(out_trait, scoped.clone());
+    let app_target = quote! { <EntraitApp as #delegation_ident #app_args>::Target };
+
+    let mut scoped_generics = out_trait
+        .generics
+        .with_type_param(syn::parse_quote! { EntraitCtx })
+        .with_type_param(syn::parse_quote! { EntraitApp: #delegation_ident #app_args });
+    scoped_generics
+        .where_predicates
+        .push(syn::parse_quote! { #app_target: #impl_trait_ident #scoped_args });
+    let params = scoped_generics.trait_params();
+    let where_clause = scoped_generics.trait_where_clause();
+
+    quote! {
+        impl #params #delegation_ident #scoped_args for #scoped #where_clause {
+            type Target = #app_target;
+        }
+    }
+}
+
+/// The generic arguments of the impl trait with `impl_t` as the `T` of `Impl<T>`, i.e. `<'a, App, X>`
+fn impl_trait_args_for(out_trait: &OutTrait, impl_t: TokenStream) -> TokenStream {
+    let params = &out_trait.generics.params;
+    let lifetimes = params.iter().filter_map(|param| match param {
+        syn::GenericParam::Lifetime(lifetime_param) => Some(&lifetime_param.lifetime),
+        _ => None,
+    });
+    let others = params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(type_param) => Some(&type_param.ident),
+        syn::GenericParam::Const(const_param) => Some(&const_param.ident),
+        syn::GenericParam::Lifetime(_) => None,
+    });
+
+    quote! { <#(#lifetimes,)* #impl_t #(, #others)*> }
+}
@@ -10,6 +10,7 @@ use crate::generics::ImplIndirection;
 use crate::generics::TraitDependencyMode;
 use crate::idents::CrateIdents;
 use crate::input::FnInputMode;
+use crate::input::ImplItem;
 use crate::opt::Mockable;
 use crate::opt::Opts;
 use crate::sub_attributes::SubAttribute;
@@ -28,6 +29,8 @@ pub struct FnDelegationCodegen<'s, TR> {
     pub fn_input_mode: &'s FnInputMode<'s>,
     pub trait_dependency_mode: &'s TraitDependencyMode<'s, 's>,
     pub sub_attributes: &'s [SubAttribute<'s>],
+    /// Associated types and consts to include in the trait impl
+    pub assoc_items: &'s [&'s ImplItem],
 }
 
 impl<TR: ToTokens> FnDelegationCodegen<'_, TR> {
@@ -43,10 +46,13 @@ impl<TR: ToTokens> FnDelegationCodegen<'_, TR> {
     /// ```
     ///
     pub fn gen_impl_block(&self, trait_fns: &[TraitFn]) -> TokenStream {
-        let params = self.trait_generics.impl_params(
-            self.trait_dependency_mode,
-            generics::has_any_self_by_value(trait_fns.iter().map(|trait_fn| trait_fn.sig())),
-        );
+        let params = self
+            .trait_generics
+            .impl_params(
+                self.trait_dependency_mode,
+                generics::has_any_self_by_value(trait_fns.iter().map(|trait_fn| trait_fn.sig())),
+            )
+            .with_impl_block_generics(self.impl_indirection.impl_block_generics());
         let args = self.trait_generics.arguments(&self.impl_indirection);
         let self_ty = SelfTy {
             trait_dependency_mode: self.trait_dependency_mode,
@@ -86,10 +92,12 @@ impl<TR: ToTokens> FnDelegationCodegen<'_, TR> {
 
         let trait_span = self.trait_span;
         let trait_ref = &self.trait_ref;
+        let assoc_items = self.assoc_items;
 
         quote_spanned! { trait_span=>
             #(#trait_impl_sub_attributes)*
             impl #params #trait_ref #args for #self_ty #where_clause {
+                #(#assoc_items)*
                 #(#items)*
             }
         }
@@ -106,15 +114,33 @@ impl<TR: ToTokens> FnDelegationCodegen<'_, TR> {
         let trait_fn_sig = &trait_fn.sig();
         let deps = &trait_fn.deps;
 
-        let mut fn_ident = trait_fn.sig().ident.clone();
+        let mut fn_ident = trait_fn.source_ident().clone();
         fn_ident.set_span(span);
 
         let opt_self_comma = match (deps, entrait_sig.sig.inputs.first(), &self.impl_indirection) {
-            (generics::FnDeps::NoDeps { .. }, _, _) | (_, None, _) => None,
+            (generics::FnDeps::NoDeps, _, _) | (_, None, _) => None,
             (_, _, ImplIndirection::Static { .. } | ImplIndirection::Dynamic { .. }) => None,
             (_, Some(_), _) => Some(SelfArgComma(&self.impl_indirection, span)),
         };
 
+        let opt_concrete_projection =
+            match (deps, &self.impl_indirection, self.trait_dependency_mode) {
+                (
+                    generics::FnDeps::Concrete(ty),
+                    ImplIndirection::Static { .. } | ImplIndirection::Dynamic { .. },
+                    TraitDependencyMode::Generic(generic_idents),
+                ) => Some(ConcreteProjection {
+                    impl_t: &generic_idents.impl_t,
+                    as_ref_path: generics::AsRefPath {
+                        core: &generic_idents.crate_idents.core,
+                        target: ty,
+                        span,
+                    },
+                    span,
+                }),
+                _ => None,
+            };
+
         let arguments = entrait_sig
             .sig
             .inputs
@@ -127,6 +153,12 @@ impl<TR: ToTokens> FnDelegationCodegen<'_, TR> {
                         panic!("Found a non-ident pattern, this should be handled in signature.rs")
                     }
                 },
+            })
+            .enumerate()
+            .map(|(index, ident)| match (index, &opt_concrete_projection) {
+                // The first parameter is `__impl`, which must be projected onto the concrete type
+                (0, Some(projection)) => projection.to_token_stream(),
+                _ => ident.to_token_stream(),
             });
 
         let opt_dot_await = trait_fn.opt_dot_await(span);
@@ -157,10 +189,10 @@ impl quote::ToTokens for SelfTy<'_, '_> {
                         push_tokens!(stream, idents.impl_t)
                     }
                 }
-                ImplIndirection::Static { ty } => {
+                ImplIndirection::Static { ty, .. } => {
                     push_tokens!(stream, ty);
                 }
-                ImplIndirection::Dynamic { ty } => {
+                ImplIndirection::Dynamic { ty, .. } => {
                     push_tokens!(stream, ty);
                 }
             },
@@ -198,3 +230,35 @@ impl quote::ToTokens for SelfArgComma<'_> {
         }
     }
 }
+
+// i.e. `<EntraitT as ::core::convert::AsRef<Concrete>>::as_ref(&**__impl)`
+struct ConcreteProjection<'g> {
+    impl_t: &'g syn::Ident,
+    as_ref_path: generics::AsRefPath<'g>,
+    span: Span,
+}
+
+impl quote::ToTokens for ConcreteProjection<'_> {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let span = self.span;
+        push_tokens!(
+            stream,
+            syn::token::Lt(span),
+            self.impl_t,
+            syn::token::As(span),
+            self.as_ref_path,
+            syn::token::Gt(span),
+            syn::token::PathSep(span),
+            syn::Ident::new("as_ref", span)
+        );
+        syn::token::Paren(span).surround(stream, |stream| {
+            push_tokens!(
+                stream,
+                syn::token::And(span),
+                syn::token::Star(span),
+                syn::token::Star(span),
+                syn::Ident::new("__impl", span)
+            );
+        });
+    }
+}
@@ -9,8 +9,36 @@ use crate::{
 #[derive(Clone)]
 pub enum ImplIndirection<'s> {
     None,
-    Static { ty: &'s syn::Type },
-    Dynamic { ty: &'s syn::Type },
+    Static {
+        ty: &'s syn::Type,
+        generics: &'s syn::Generics,
+        /// Generic arguments of the implemented trait, e.g. `<K, V>` in `impl StoreImpl<K, V> for ..`
+        trait_args: Option<&'s syn::AngleBracketedGenericArguments>,
+    },
+    Dynamic {
+        ty: &'s syn::Type,
+        generics: &'s syn::Generics,
+        trait_args: Option<&'s syn::AngleBracketedGenericArguments>,
+    },
+}
+
+impl ImplIndirection<'_> {
+    /// The generics of the `impl` block being delegated from, if any
+    pub fn impl_block_generics(&self) -> Option<&syn::Generics> {
+        match self {
+            Self::None => None,
+            Self::Static { generics, .. } | Self::Dynamic { generics, .. } => Some(generics),
+        }
+    }
+
+    fn trait_args(&self) -> impl Iterator<Item = &syn::GenericArgument> + Clone {
+        match self {
+            Self::None => None,
+            Self::Static { trait_args, .. } | Self::Dynamic { trait_args, .. } => *trait_args,
+        }
+        .into_iter()
+        .flat_map(|trait_args| trait_args.args.iter())
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -37,6 +65,46 @@ pub fn has_any_self_by_value<'s>(
     }))
 }
 
+/// The `Arc` path and inner type of an `Arc<T>` dependency, which is taken as `self: Arc<Self>`.
+pub fn arc_deps(ty: &syn::Type) -> Option<(&syn::TypePath, &syn::Type)> {
+    let type_path = match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        _ => return None,
+    };
+
+    let idents: Vec<String> = type_path
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let idents: Vec<&str> = idents.iter().map(String::as_str).collect();
+
+    match idents.as_slice() {
+        ["Arc"] | ["sync", "Arc"] | ["std" | "alloc", "sync", "Arc"] => {}
+        _ => return None,
+    }
+
+    match &type_path.path.segments.last()?.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                syn::GenericArgument::Type(inner) => Some((type_path, inner)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Replace the pointee of an `Arc` dependency type, i.e. `Arc<impl Trait>` becomes `Arc<#pointee>`.
+pub fn arc_with_pointee(arc_path: &syn::TypePath, pointee: syn::Type) -> syn::Type {
+    let mut arc_path = arc_path.clone();
+    if let Some(segment) = arc_path.path.segments.last_mut() {
+        segment.arguments = syn::PathArguments::AngleBracketed(syn::parse_quote!(<#pointee>));
+    }
+    syn::Type::Path(arc_path)
+}
+
 #[derive(Clone)]
 pub enum FnDeps {
     Generic {
@@ -59,11 +127,26 @@ pub struct TraitGenerics {
 }
 
 impl TraitGenerics {
+    /// A copy of these generics with an additional type param, declared after the lifetimes
+    pub fn with_type_param(&self, type_param: syn::TypeParam) -> Self {
+        let mut generics = self.clone();
+        let index = generics
+            .params
+            .iter()
+            .take_while(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+            .count();
+        generics
+            .params
+            .insert(index, syn::GenericParam::Type(type_param));
+        generics
+    }
+
     pub fn trait_params(&self) -> ParamsGenerator<'_> {
         ParamsGenerator {
             params: &self.params,
             impl_t: None,
             takes_self_by_value: TakesSelfByValue(false),
+            impl_block_generics: None,
         }
     }
 
@@ -85,6 +168,7 @@ impl TraitGenerics {
                 TraitDependencyMode::Concrete(_) => None,
             },
             takes_self_by_value,
+            impl_block_generics: None,
         }
     }
 
@@ -97,6 +181,7 @@ impl TraitGenerics {
             params: &self.params,
             impl_t: Some(&idents.impl_t),
             takes_self_by_value,
+            impl_block_generics: None,
         }
     }
 
@@ -123,6 +208,16 @@ impl TraitGenerics {
         ArgumentsGenerator {
             params: &self.params,
             impl_indirection,
+            bindings: &[],
+        }
+    }
+
+    /// The arguments of a `dyn` trait object, with its associated types pinned, i.e. `<T, Error = E>`
+    pub fn dyn_arguments<'s>(&'s self, bindings: &'s [syn::AssocType]) -> ArgumentsGenerator<'s> {
+        ArgumentsGenerator {
+            params: &self.params,
+            impl_indirection: &ImplIndirection::None,
+            bindings,
         }
     }
 }
@@ -159,6 +254,17 @@ pub struct ParamsGenerator<'g> {
     params: &'g syn::punctuated::Punctuated<syn::GenericParam, syn::token::Comma>,
     impl_t: Option<&'g syn::Ident>,
     takes_self_by_value: TakesSelfByValue,
+    impl_block_generics: Option<&'g syn::Generics>,
+}
+
+impl<'g> ParamsGenerator<'g> {
+    /// Also include the params of the `impl` block that is delegated from
+    pub fn with_impl_block_generics(self, impl_block_generics: Option<&'g syn::Generics>) -> Self {
+        Self {
+            impl_block_generics,
+            ..self
+        }
+    }
 }
 
 impl quote::ToTokens for ParamsGenerator<'_> {
@@ -170,6 +276,18 @@ impl quote::ToTokens for ParamsGenerator<'_> {
             syn::token::Gt::default(),
         );
 
+        let impl_block_params = self
+            .impl_block_generics
+            .into_iter()
+            .flat_map(|generics| generics.params.iter());
+
+        // Lifetimes must be declared first
+        for param in impl_block_params.clone().chain(self.params) {
+            if let syn::GenericParam::Lifetime(_) = param {
+                punctuator.push(param);
+            }
+        }
+
         if let Some(impl_t) = &self.impl_t {
             punctuator.push_fn(|stream| {
                 push_tokens!(
@@ -200,8 +318,16 @@ impl quote::ToTokens for ParamsGenerator<'_> {
             });
         }
 
+        for param in impl_block_params {
+            if !matches!(param, syn::GenericParam::Lifetime(_)) {
+                punctuator.push(param);
+            }
+        }
+
         for param in self.params {
-            punctuator.push(param);
+            if !matches!(param, syn::GenericParam::Lifetime(_)) {
+                punctuator.push(param);
+            }
         }
     }
 }
@@ -210,6 +336,7 @@ impl quote::ToTokens for ParamsGenerator<'_> {
 pub struct ArgumentsGenerator<'g> {
     params: &'g syn::punctuated::Punctuated<syn::GenericParam, syn::token::Comma>,
     impl_indirection: &'g ImplIndirection<'g>,
+    bindings: &'g [syn::AssocType],
 }
 
 impl quote::ToTokens for ArgumentsGenerator<'_> {
@@ -221,6 +348,20 @@ impl quote::ToTokens for ArgumentsGenerator<'_> {
             syn::token::Gt::default(),
         );
 
+        let trait_args = self.impl_indirection.trait_args();
+
+        // Lifetimes must come first, then the `EntraitT` of the impl trait
+        for arg in trait_args.clone() {
+            if let syn::GenericArgument::Lifetime(_) = arg {
+                punctuator.push(arg);
+            }
+        }
+        for param in self.params {
+            if let syn::GenericParam::Lifetime(lifetime_def) = param {
+                punctuator.push(&lifetime_def.lifetime);
+            }
+        }
+
         if matches!(
             &self.impl_indirection,
             ImplIndirection::Static { .. } | ImplIndirection::Dynamic { .. }
@@ -228,19 +369,27 @@ impl quote::ToTokens for ArgumentsGenerator<'_> {
             punctuator.push(syn::Ident::new("EntraitT", proc_macro2::Span::call_site()));
         }
 
-        for pair in self.params.pairs() {
-            match pair.value() {
+        for arg in trait_args {
+            if !matches!(arg, syn::GenericArgument::Lifetime(_)) {
+                punctuator.push(arg);
+            }
+        }
+
+        for param in self.params {
+            match param {
                 syn::GenericParam::Type(type_param) => {
                     punctuator.push(&type_param.ident);
                 }
-                syn::GenericParam::Lifetime(lifetime_def) => {
-                    punctuator.push(&lifetime_def.lifetime);
-                }
+                syn::GenericParam::Lifetime(_) => {}
                 syn::GenericParam::Const(const_param) => {
                     punctuator.push(&const_param.ident);
                 }
             }
         }
+
+        for binding in self.bindings {
+            punctuator.push(binding);
+        }
     }
 }
 
@@ -309,6 +458,26 @@ impl quote::ToTokens for ImplWhereClauseGenerator<'_, '_, '_> {
                         }
                     });
                 }
+
+                // Concrete dependencies in impl blocks are projected out of the `T` in `Impl<T>`
+                if let ImplIndirection::Static { .. } | ImplIndirection::Dynamic { .. } =
+                    self.impl_indirection
+                {
+                    for concrete_ty in concrete_dep_types(self.trait_fns) {
+                        punctuator.push_fn(|stream| {
+                            push_tokens!(
+                                stream,
+                                generic_idents.impl_t,
+                                syn::token::Colon(self.span),
+                                AsRefPath {
+                                    core: &generic_idents.crate_idents.core,
+                                    target: concrete_ty,
+                                    span: self.span,
+                                }
+                            );
+                        });
+                    }
+                }
             }
             TraitDependencyMode::Concrete(_) => {
                 // NOTE: the impl for Impl<T> is generated by invoking #[entrait] on the trait(!),
@@ -319,6 +488,16 @@ impl quote::ToTokens for ImplWhereClauseGenerator<'_, '_, '_> {
         for predicate in self.trait_where_predicates {
             punctuator.push(predicate);
         }
+
+        if let Some(where_clause) = self
+            .impl_indirection
+            .impl_block_generics()
+            .and_then(|generics| generics.where_clause.as_ref())
+        {
+            for predicate in &where_clause.predicates {
+                punctuator.push(predicate);
+            }
+        }
     }
 }
 
@@ -343,3 +522,44 @@ fn push_impl_t_bounds(
         }
     }
 }
+
+/// The distinct concrete dependency types used by the given functions.
+pub fn concrete_dep_types(trait_fns: &[TraitFn]) -> Vec<&syn::Type> {
+    use quote::ToTokens;
+
+    let mut types: Vec<(String, &syn::Type)> = vec![];
+    for trait_fn in trait_fns {
+        if let FnDeps::Concrete(ty) = &trait_fn.deps {
+            let key = ty.to_token_stream().to_string();
+            if !types.iter().any(|(existing, _)| *existing == key) {
+                types.push((key, ty.as_ref()));
+            }
+        }
+    }
+    types.into_iter().map(|(_, ty)| ty).collect()
+}
+
+/// `::core::convert::AsRef<Target>`
+pub struct AsRefPath<'g> {
+    pub core: &'g syn::Ident,
+    pub target: &'g syn::Type,
+    pub span: proc_macro2::Span,
+}
+
+impl quote::ToTokens for AsRefPath<'_> {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let span = self.span;
+        push_tokens!(
+            stream,
+            syn::token::PathSep(span),
+            self.core,
+            syn::token::PathSep(span),
+            syn::Ident::new("convert", span),
+            syn::token::PathSep(span),
+            syn::Ident::new("AsRef", span),
+            syn::token::Lt(span),
+            self.target,
+            syn::token::Gt(span)
+        );
+    }
+}
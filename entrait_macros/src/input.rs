@@ -36,6 +36,8 @@ pub enum Input {
     Trait(syn::ItemTrait),
     Mod(InputMod),
     Impl(InputImpl),
+    Struct(syn::ItemStruct),
+    Const(InputConst),
 }
 
 impl Parse for Input {
@@ -60,6 +62,22 @@ impl Parse for Input {
         } else if input.peek(syn::token::Impl) {
             disallow_token(auto_token)?;
             Ok(Input::Impl(parse_impl(attrs, unsafety, input)?))
+        } else if input.peek(syn::token::Static)
+            || (input.peek(syn::token::Const) && !input.peek2(syn::token::Fn))
+        {
+            disallow_token(unsafety)?;
+            disallow_token(auto_token)?;
+            Ok(Input::Const(parse_const(attrs, vis, input)?))
+        } else if input.peek(syn::token::Struct) {
+            disallow_token(unsafety)?;
+            disallow_token(auto_token)?;
+            let item_struct: syn::ItemStruct = input.parse()?;
+
+            Ok(Input::Struct(syn::ItemStruct {
+                attrs,
+                vis,
+                ..item_struct
+            }))
         } else if input.peek(syn::token::Mod) {
             disallow_token(unsafety)?;
             disallow_token(auto_token)?;
@@ -125,6 +143,8 @@ impl ToTokens for InputMod {
 
 pub enum ModItem {
     PubFn(Box<InputFn>),
+    /// A private function, which is not part of the trait
+    PrivateFn(ItemUnknown),
     Unknown(ItemUnknown),
 }
 
@@ -136,6 +156,42 @@ impl ModItem {
             _ => None,
         }
     }
+
+    pub fn filter_pub_fn_mut(&mut self) -> Option<&mut InputFn> {
+        match self {
+            Self::PubFn(input_fn) => Some(input_fn),
+            _ => None,
+        }
+    }
+
+    pub fn private_fn_attrs(&self) -> Option<&[syn::Attribute]> {
+        match self {
+            Self::PrivateFn(unknown) => Some(&unknown.attrs),
+            _ => None,
+        }
+    }
+
+    /// Parse an inline submodule with a visibility keyword, which is otherwise kept verbatim.
+    /// Only done on demand, so that modules are not reparsed unless the `recursive` option asks for it.
+    pub fn parse_pub_inline_mod(&self) -> Option<syn::Result<InputMod>> {
+        let unknown = match self {
+            Self::Unknown(unknown) => unknown,
+            _ => return None,
+        };
+
+        syn::parse::Parser::parse2(
+            |input: ParseStream| {
+                if peek_pub_inline_mod(input, &unknown.vis) {
+                    parse_mod(unknown.attrs.clone(), unknown.vis.clone(), input).map(Some)
+                } else {
+                    input.parse::<TokenStream>()?;
+                    Ok(None)
+                }
+            },
+            unknown.tokens.clone(),
+        )
+        .transpose()
+    }
 }
 
 impl ToTokens for ModItem {
@@ -153,22 +209,54 @@ impl ToTokens for ModItem {
                 }
                 push_tokens!(stream, fn_vis, fn_sig, fn_body);
             }
-            ModItem::Unknown(unknown) => {
+            ModItem::PrivateFn(unknown) | ModItem::Unknown(unknown) => {
                 unknown.to_tokens(stream);
             }
         }
     }
 }
 
+/// A `const` or `static` item
+pub enum InputConst {
+    Const(syn::ItemConst),
+    Static(syn::ItemStatic),
+}
+
+impl InputConst {
+    pub fn ident(&self) -> &syn::Ident {
+        match self {
+            Self::Const(item_const) => &item_const.ident,
+            Self::Static(item_static) => &item_static.ident,
+        }
+    }
+
+    pub fn ty(&self) -> &syn::Type {
+        match self {
+            Self::Const(item_const) => &item_const.ty,
+            Self::Static(item_static) => &item_static.ty,
+        }
+    }
+}
+
+impl ToTokens for InputConst {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        match self {
+            Self::Const(item_const) => item_const.to_tokens(stream),
+            Self::Static(item_static) => item_static.to_tokens(stream),
+        }
+    }
+}
+
 #[expect(unused)]
 pub struct DeriveImplTraitPath(pub syn::Path);
 
 /// An impl block
-/// Note: No support for generics
 pub struct InputImpl {
     pub attrs: Vec<syn::Attribute>,
     pub unsafety: Option<syn::token::Unsafe>,
     pub impl_token: syn::token::Impl,
+    /// The generics of the impl block, including its where clause
+    pub generics: syn::Generics,
     pub trait_path: syn::Path,
     #[expect(unused)]
     pub for_token: syn::token::For,
@@ -180,6 +268,8 @@ pub struct InputImpl {
 
 pub enum ImplItem {
     Fn(Box<InputFn>),
+    /// An associated type or const, which belongs to the trait impl
+    Assoc(ItemUnknown),
     Unknown(ItemUnknown),
 }
 
@@ -191,6 +281,10 @@ impl ImplItem {
             _ => None,
         }
     }
+
+    pub fn is_assoc(&self) -> bool {
+        matches!(self, Self::Assoc(_))
+    }
 }
 
 impl ToTokens for ImplItem {
@@ -208,7 +302,7 @@ impl ToTokens for ImplItem {
                 }
                 push_tokens!(stream, fn_vis, fn_sig, fn_body);
             }
-            ImplItem::Unknown(unknown) => {
+            ImplItem::Assoc(unknown) | ImplItem::Unknown(unknown) => {
                 unknown.to_tokens(stream);
             }
         }
@@ -287,6 +381,9 @@ impl Parse for ModItem {
                     fn_body,
                 })))
             }
+        } else if peek_fn(input) {
+            let tokens = parse_matched_braces_or_ending_semi(input)?;
+            Ok(ModItem::PrivateFn(ItemUnknown { attrs, vis, tokens }))
         } else {
             let tokens = parse_matched_braces_or_ending_semi(input)?;
             Ok(ModItem::Unknown(ItemUnknown { attrs, vis, tokens }))
@@ -300,9 +397,11 @@ fn parse_impl(
     input: ParseStream,
 ) -> syn::Result<InputImpl> {
     let impl_token = input.parse()?;
+    let mut generics: syn::Generics = input.parse()?;
     let trait_path = input.parse()?;
     let for_token = input.parse()?;
     let self_ty = input.parse()?;
+    generics.where_clause = input.parse()?;
 
     let lookahead = input.lookahead1();
     if lookahead.peek(syn::token::Brace) {
@@ -319,6 +418,7 @@ fn parse_impl(
             attrs,
             unsafety,
             impl_token,
+            generics,
             trait_path,
             for_token,
             self_ty,
@@ -355,6 +455,9 @@ impl Parse for ImplItem {
                     fn_body,
                 })))
             }
+        } else if input.peek(syn::token::Type) || input.peek(syn::token::Const) {
+            let tokens = parse_matched_braces_or_ending_semi(input)?;
+            Ok(ImplItem::Assoc(ItemUnknown { attrs, vis, tokens }))
         } else {
             let tokens = parse_matched_braces_or_ending_semi(input)?;
             Ok(ImplItem::Unknown(ItemUnknown { attrs, vis, tokens }))
@@ -381,6 +484,13 @@ fn peek_pub_fn(input: ParseStream, vis: &syn::Visibility) -> bool {
     peek_fn(input)
 }
 
+fn peek_pub_inline_mod(input: ParseStream, vis: &syn::Visibility) -> bool {
+    if let syn::Visibility::Inherited = vis {
+        return false;
+    }
+    input.peek(syn::token::Mod) && input.peek2(syn::Ident) && input.peek3(syn::token::Brace)
+}
+
 fn peek_fn(input: ParseStream) -> bool {
     if input.peek(syn::token::Fn) {
         return true;
@@ -451,6 +561,36 @@ fn parse_matched_braces_or_ending_semi(input: ParseStream) -> syn::Result<TokenS
     Ok(tokens)
 }
 
+fn parse_const(
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    input: ParseStream,
+) -> syn::Result<InputConst> {
+    if input.peek(syn::token::Static) {
+        let item_static: syn::ItemStatic = input.parse()?;
+        if let syn::StaticMutability::Mut(mut_token) = &item_static.mutability {
+            return Err(syn::Error::new(
+                mut_token.span(),
+                "Mutable statics are not supported",
+            ));
+        }
+
+        Ok(InputConst::Static(syn::ItemStatic {
+            attrs,
+            vis,
+            ..item_static
+        }))
+    } else {
+        let item_const: syn::ItemConst = input.parse()?;
+
+        Ok(InputConst::Const(syn::ItemConst {
+            attrs,
+            vis,
+            ..item_const
+        }))
+    }
+}
+
 fn disallow_token<T: Spanned>(token: Option<T>) -> syn::Result<()> {
     if let Some(token) = token {
         Err(syn::Error::new(token.span(), "Not allowed here"))
@@ -10,8 +10,11 @@ use proc_macro::TokenStream;
 
 mod analyze_generics;
 mod attributes;
+mod diagnostics;
+mod entrait_const;
 mod entrait_fn;
 mod entrait_impl;
+mod entrait_struct;
 mod entrait_trait;
 mod fn_delegation_codegen;
 mod generics;
@@ -22,6 +25,7 @@ mod signature;
 mod sub_attributes;
 mod token_util;
 mod trait_codegen;
+mod wire;
 
 use input::Input;
 use opt::Opts;
@@ -52,6 +56,18 @@ pub fn entrait_export_unimock(attr: TokenStream, input: TokenStream) -> TokenStr
     })
 }
 
+#[proc_macro_derive(Wire, attributes(wire))]
+pub fn derive_wire(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    let output = match wire::output_tokens(input) {
+        Ok(token_stream) => token_stream,
+        Err(err) => err.into_compile_error(),
+    };
+
+    proc_macro::TokenStream::from(output)
+}
+
 fn set_fallbacks<const N: usize>(opts: [&mut Option<opt::SpanOpt<bool>>; N]) {
     for opt in opts.into_iter() {
         opt.get_or_insert(opt::SpanOpt::of(true));
@@ -103,6 +119,21 @@ fn invoke(
                 debug,
             )
         }
+        Input::Struct(item_struct) => {
+            let mut attr =
+                syn::parse_macro_input!(attr as entrait_struct::input_attr::EntraitStructAttr);
+            opts_modifier(&mut attr.opts);
+            let debug = attr.opts.debug_value();
+
+            (entrait_struct::output_tokens(attr, item_struct), debug)
+        }
+        Input::Const(input_const) => {
+            let mut attr = syn::parse_macro_input!(attr as entrait_fn::input_attr::EntraitFnAttr);
+            opts_modifier(&mut attr.opts);
+            let debug = attr.opts.debug_value();
+
+            (entrait_const::output_tokens(attr, input_const), debug)
+        }
     };
 
     let output = match result {
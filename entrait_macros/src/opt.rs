@@ -5,6 +5,14 @@ pub struct Opts {
     pub default_span: Span,
 
     pub no_deps: Option<SpanOpt<bool>>,
+    /// Whether all dependencies are required to be the same concrete type
+    pub concrete: Option<SpanOpt<bool>>,
+    /// Whether `Scoped` takes the leaf trait from its context instead of from the application
+    pub context: Option<SpanOpt<bool>>,
+    /// Whether a leaf trait gets a `{Trait}Leaf` accessor trait, through which it is implemented
+    pub leaf_accessor: Option<SpanOpt<bool>>,
+    /// Whether generic params of functions stay on the trait methods, instead of becoming trait params
+    pub method_generics: Option<SpanOpt<bool>>,
     pub debug: Option<SpanOpt<bool>>,
 
     /// Whether to export mocks (i.e. not gated with cfg(test))
@@ -26,6 +34,22 @@ impl Opts {
         self.default_option(self.no_deps, false).0
     }
 
+    pub fn concrete_value(&self) -> bool {
+        self.default_option(self.concrete, false).0
+    }
+
+    pub fn context_value(&self) -> bool {
+        self.default_option(self.context, false).0
+    }
+
+    pub fn leaf_accessor_value(&self) -> bool {
+        self.default_option(self.leaf_accessor, false).0
+    }
+
+    pub fn method_generics_value(&self) -> bool {
+        self.default_option(self.method_generics, false).0
+    }
+
     pub fn debug_value(&self) -> bool {
         self.default_option(self.debug, false).0
     }
@@ -71,18 +95,85 @@ impl Mockable {
 pub enum Delegate {
     BySelf,
     ByRef(RefDelegate),
-    ByTrait(syn::Ident),
+    /// A generated trait selecting the implementation through its `Target`,
+    /// with optional candidates for selecting the implementation per call
+    ByTrait(syn::Ident, Vec<Candidate>),
+}
+
+/// An implementation listed in `delegate_by = enum(..)` or `delegate_by = DelegateTrait(..)`
+#[derive(Clone)]
+pub struct Candidate {
+    /// Given as `Variant = path::Impl`, or else the last segment of the path
+    pub variant: syn::Ident,
+    pub path: syn::Path,
+}
+
+impl Parse for Candidate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Ident) && input.peek2(syn::token::Eq) {
+            let variant = input.parse()?;
+            input.parse::<syn::token::Eq>()?;
+            Ok(Self {
+                variant,
+                path: input.parse()?,
+            })
+        } else {
+            let path: syn::Path = input.parse()?;
+            Ok(Self {
+                variant: path.segments.last().unwrap().ident.clone(),
+                path,
+            })
+        }
+    }
 }
 
 #[derive(Clone)]
 pub enum RefDelegate {
     AsRef,
     Borrow,
+    /// `T: Deref`, with the target implementing the trait
+    Deref,
+    /// A generated accessor trait handing out `&Arc<dyn Trait + Send + Sync>`
+    Arc,
+    /// A generated accessor trait handing out `&Box<dyn Trait + Send + Sync>`
+    Box,
+    /// A generated accessor trait handing out `&RwLock<Arc<dyn Trait + Send + Sync>>`,
+    /// so that the implementation can be swapped while the application is running
+    Swap,
+    /// A generated accessor trait handing out a generated enum of the listed implementations
+    Enum(Vec<Candidate>),
+}
+
+impl RefDelegate {
+    /// Whether the trait is called through a `dyn` trait object
+    pub fn is_dyn(&self) -> bool {
+        !matches!(self, Self::Deref | Self::Enum(_))
+    }
+
+    /// The (swappable) smart pointer handed out by the generated accessor trait
+    pub fn accessor_pointer(&self) -> Option<&'static str> {
+        match self {
+            Self::Arc => Some("Arc"),
+            Self::Box => Some("Box"),
+            Self::Swap => Some("Swap"),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct FutureSend(pub bool);
 
+/// How inline submodules become nested traits
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Recursive {
+    No,
+    /// The nested traits are re-exported, and become supertraits of the module's trait
+    Supertraits,
+    /// `recursive = flat`: The nested traits are only re-exported
+    Flat,
+}
+
 #[derive(Copy, Clone)]
 pub struct SpanOpt<T>(pub T, pub Span);
 
@@ -101,6 +192,14 @@ impl<T> SpanOpt<T> {
 ///
 pub enum EntraitOpt {
     NoDeps(SpanOpt<bool>),
+    /// Whether dependencies are concrete
+    Concrete(SpanOpt<bool>),
+    /// Whether a leaf trait is taken from the context of `Scoped`
+    Context(SpanOpt<bool>),
+    /// Whether a leaf trait gets an accessor trait
+    LeafAccessor(SpanOpt<bool>),
+    /// Whether generic params of functions stay on the trait methods
+    MethodGenerics(SpanOpt<bool>),
     Debug(SpanOpt<bool>),
     DelegateBy(SpanOpt<Delegate>),
     /// Whether to export mocks
@@ -112,12 +211,18 @@ pub enum EntraitOpt {
     Unimock(SpanOpt<bool>),
     /// Whether to generate mockall impl
     Mockall(SpanOpt<bool>),
+    /// Whether inline submodules become nested traits
+    Recursive(SpanOpt<Recursive>),
 }
 
 impl EntraitOpt {
     pub fn span(&self) -> proc_macro2::Span {
         match self {
             Self::NoDeps(opt) => opt.1,
+            Self::Concrete(opt) => opt.1,
+            Self::Context(opt) => opt.1,
+            Self::LeafAccessor(opt) => opt.1,
+            Self::MethodGenerics(opt) => opt.1,
             Self::Debug(opt) => opt.1,
             Self::DelegateBy(opt) => opt.1,
             Self::MaybeSend(opt) => opt.1,
@@ -125,6 +230,7 @@ impl EntraitOpt {
             Self::MockApi(ident) => ident.0.span(),
             Self::Unimock(opt) => opt.1,
             Self::Mockall(opt) => opt.1,
+            Self::Recursive(opt) => opt.1,
         }
     }
 }
@@ -154,6 +260,10 @@ impl Parse for EntraitOpt {
 
             match ident_string.as_str() {
                 "no_deps" => Ok(NoDeps(parse_eq_bool(input, true, span)?)),
+                "concrete" => Ok(Concrete(parse_eq_bool(input, true, span)?)),
+                "context" => Ok(Context(parse_eq_bool(input, true, span)?)),
+                "leaf_accessor" => Ok(LeafAccessor(parse_eq_bool(input, true, span)?)),
+                "method_generics" => Ok(MethodGenerics(parse_eq_bool(input, true, span)?)),
                 "debug" => Ok(Debug(parse_eq_bool(input, true, span)?)),
                 "delegate_by" => Ok(DelegateBy(parse_eq_delegate_by(
                     input,
@@ -167,6 +277,7 @@ impl Parse for EntraitOpt {
                 }
                 "unimock" => Ok(Unimock(parse_eq_bool(input, true, span)?)),
                 "mockall" => Ok(Mockall(parse_eq_bool(input, true, span)?)),
+                "recursive" => Ok(Recursive(parse_eq_recursive(input, span)?)),
                 _ => Err(syn::Error::new(
                     span,
                     format!("Unkonwn entrait option \"{ident_string}\""),
@@ -176,12 +287,42 @@ impl Parse for EntraitOpt {
     }
 }
 
+#[derive(Clone)]
 pub struct MockApiIdent(pub syn::Ident);
 
 fn parse_eq_bool(input: ParseStream, default: bool, span: Span) -> syn::Result<SpanOpt<bool>> {
     parse_eq_value_or_default(input, default, |b: syn::LitBool| Ok(b.value()), span)
 }
 
+fn parse_eq_recursive(input: ParseStream, span: Span) -> syn::Result<SpanOpt<Recursive>> {
+    if !input.peek(syn::token::Eq) {
+        return Ok(SpanOpt(Recursive::Supertraits, span));
+    }
+
+    input.parse::<syn::token::Eq>()?;
+
+    if input.peek(syn::LitBool) {
+        let lit_bool: syn::LitBool = input.parse()?;
+        return Ok(SpanOpt(
+            if lit_bool.value() {
+                Recursive::Supertraits
+            } else {
+                Recursive::No
+            },
+            span,
+        ));
+    }
+
+    let ident: syn::Ident = input.parse()?;
+    match ident.to_string().as_str() {
+        "flat" => Ok(SpanOpt(Recursive::Flat, span)),
+        _ => Err(syn::Error::new(
+            ident.span(),
+            "Expected `true`, `false` or `flat`",
+        )),
+    }
+}
+
 fn parse_eq_delegate_by(
     input: ParseStream,
     default: Delegate,
@@ -199,18 +340,89 @@ fn parse_eq_delegate_by(
         return Ok(SpanOpt(Delegate::ByRef(RefDelegate::AsRef), span));
     }
 
+    if input.peek(syn::token::Enum) {
+        let enum_token: syn::token::Enum = input.parse()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let candidates = parse_candidates(&content)?;
+
+        if candidates.is_empty() {
+            return Err(syn::Error::new(
+                enum_token.span,
+                "`delegate_by = enum(..)` needs at least one implementation",
+            ));
+        }
+
+        return Ok(SpanOpt(
+            Delegate::ByRef(RefDelegate::Enum(candidates)),
+            span,
+        ));
+    }
+
     let ident = input.parse::<syn::Ident>()?;
 
     Ok(SpanOpt(
         match ident.to_string().as_str() {
             "Self" => Delegate::BySelf,
             "Borrow" => Delegate::ByRef(RefDelegate::Borrow),
-            _ => Delegate::ByTrait(ident),
+            "Deref" => Delegate::ByRef(RefDelegate::Deref),
+            "Arc" => Delegate::ByRef(RefDelegate::Arc),
+            "Box" => Delegate::ByRef(RefDelegate::Box),
+            "Swap" => Delegate::ByRef(RefDelegate::Swap),
+            _ => {
+                let candidates = if input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let candidates = parse_candidates(&content)?;
+                    if candidates.is_empty() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            format!(
+                                "`delegate_by = {ident}(..)` needs at least one implementation"
+                            ),
+                        ));
+                    }
+                    candidates
+                } else {
+                    vec![]
+                };
+
+                Delegate::ByTrait(ident, candidates)
+            }
         },
         span,
     ))
 }
 
+/// Each candidate becomes an enum variant, so their names must be unique
+fn parse_candidates(input: ParseStream) -> syn::Result<Vec<Candidate>> {
+    let candidates =
+        syn::punctuated::Punctuated::<Candidate, syn::token::Comma>::parse_terminated(input)?;
+
+    let mut variants: Vec<&syn::Ident> = vec![];
+    for candidate in &candidates {
+        if variants.contains(&&candidate.variant) {
+            let path = candidate
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+            return Err(syn::Error::new_spanned(
+                &candidate.path,
+                format!(
+                    "Another implementation already has the variant name `{}`. Name this one with `Variant = {path}`",
+                    candidate.variant,
+                ),
+            ));
+        }
+        variants.push(&candidate.variant);
+    }
+
+    Ok(candidates.into_iter().collect())
+}
+
 fn parse_eq_value_or_default<V, F, O>(
     input: ParseStream,
     default_value: O,
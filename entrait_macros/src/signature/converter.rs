@@ -1,6 +1,10 @@
-use super::{fn_params, ReceiverGeneration};
+use super::{desugar_future_output, fn_params, ReceiverGeneration};
 use super::{EntraitSignature, ImplReceiverKind, InputSig};
-use crate::{generics::FnDeps, idents::CrateIdents, opt::Opts};
+use crate::{
+    generics::{self, FnDeps},
+    idents::CrateIdents,
+    opt::Opts,
+};
 
 use proc_macro2::Span;
 use syn::spanned::Spanned;
@@ -9,7 +13,6 @@ pub struct SignatureConverter<'a> {
     pub crate_idents: &'a CrateIdents,
     #[expect(unused)]
     pub trait_span: Span,
-    #[expect(unused)]
     pub opts: &'a Opts,
     pub input_sig: InputSig<'a>,
     pub deps: &'a FnDeps,
@@ -33,10 +36,12 @@ impl SignatureConverter<'_> {
             }
         }
 
+        desugar_future_output(&mut entrait_sig.sig);
+
         let receiver_generation = self.detect_receiver_generation(&entrait_sig.sig);
         self.generate_params(&mut entrait_sig.sig, receiver_generation);
 
-        self.remove_generic_type_params(&mut entrait_sig.sig);
+        self.remove_generic_params(&mut entrait_sig.sig);
         tidy_generics(&mut entrait_sig.sig.generics);
 
         fn_params::fix_fn_param_idents(&mut entrait_sig.sig);
@@ -46,7 +51,7 @@ impl SignatureConverter<'_> {
 
     fn detect_receiver_generation(&self, sig: &syn::Signature) -> ReceiverGeneration {
         match self.deps {
-            FnDeps::NoDeps { .. } => ReceiverGeneration::Insert,
+            FnDeps::NoDeps => ReceiverGeneration::Insert,
             _ => {
                 if sig.inputs.is_empty() {
                     ReceiverGeneration::None // bug?
@@ -65,6 +70,7 @@ impl SignatureConverter<'_> {
                     self.gen_first_receiver(
                         Span::call_site(),
                         Some((syn::token::And::default(), None)),
+                        None,
                     ),
                 );
             }
@@ -76,14 +82,24 @@ impl SignatureConverter<'_> {
                         syn::Type::Reference(type_reference) => {
                             let and_token = type_reference.and_token;
                             let lifetime = type_reference.lifetime.clone();
+                            let mutability = type_reference.mutability;
 
-                            *input = self
-                                .gen_first_receiver(pat_type.span(), Some((and_token, lifetime)));
-                        }
-                        _ => {
-                            let first_mut = sig.inputs.first_mut().unwrap();
-                            *first_mut = self.gen_first_receiver(input_span, None);
+                            *input = self.gen_first_receiver(
+                                pat_type.span(),
+                                Some((and_token, lifetime)),
+                                mutability,
+                            );
                         }
+                        ty => match (self.deps, generics::arc_deps(ty)) {
+                            (FnDeps::Generic { .. }, Some((arc_path, _))) => {
+                                let arc_path = arc_path.clone();
+                                *input = self.gen_arc_receiver(input_span, &arc_path);
+                            }
+                            _ => {
+                                let first_mut = sig.inputs.first_mut().unwrap();
+                                *first_mut = self.gen_first_receiver(input_span, None, None);
+                            }
+                        },
                     },
                     syn::FnArg::Receiver(_) => panic!(),
                 }
@@ -93,7 +109,7 @@ impl SignatureConverter<'_> {
 
         if matches!(self.impl_receiver_kind, ImplReceiverKind::DynamicImpl) {
             sig.inputs
-                .insert(1, self.gen_impl_receiver(Span::call_site()));
+                .insert(1, self.gen_impl_receiver(Span::call_site(), None));
         }
     }
 
@@ -101,12 +117,13 @@ impl SignatureConverter<'_> {
         &self,
         span: Span,
         reference: Option<(syn::token::And, Option<syn::Lifetime>)>,
+        mutability: Option<syn::token::Mut>,
     ) -> syn::FnArg {
         match &self.impl_receiver_kind {
             ImplReceiverKind::SelfRef | ImplReceiverKind::DynamicImpl => {
-                self.gen_self_receiver(span, reference)
+                self.gen_self_receiver(span, reference, mutability)
             }
-            ImplReceiverKind::StaticImpl => self.gen_impl_receiver(span),
+            ImplReceiverKind::StaticImpl => self.gen_impl_receiver(span, mutability),
         }
     }
 
@@ -114,34 +131,68 @@ impl SignatureConverter<'_> {
         &self,
         span: Span,
         reference: Option<(syn::token::And, Option<syn::Lifetime>)>,
+        mutability: Option<syn::token::Mut>,
     ) -> syn::FnArg {
-        let ty = match reference {
-            Some(_) => syn::parse_quote!(&Self),
-            None => syn::parse_quote!(Self),
+        let ty = match (&reference, mutability) {
+            (Some(_), Some(_)) => syn::parse_quote!(&mut Self),
+            (Some(_), None) => syn::parse_quote!(&Self),
+            (None, _) => syn::parse_quote!(Self),
         };
 
         syn::FnArg::Receiver(syn::Receiver {
             attrs: vec![],
+            mutability: reference.as_ref().and(mutability),
             reference,
-            mutability: None,
             self_token: syn::token::SelfValue(span),
             colon_token: None,
             ty,
         })
     }
 
-    fn gen_impl_receiver(&self, _: Span) -> syn::FnArg {
+    /// `self: Arc<Self>`, or `__impl: Arc<Impl<T>>` for static impl traits.
+    fn gen_arc_receiver(&self, span: Span, arc_path: &syn::TypePath) -> syn::FnArg {
+        match &self.impl_receiver_kind {
+            ImplReceiverKind::SelfRef | ImplReceiverKind::DynamicImpl => {
+                syn::FnArg::Receiver(syn::Receiver {
+                    attrs: vec![],
+                    reference: None,
+                    mutability: None,
+                    self_token: syn::token::SelfValue(span),
+                    colon_token: Some(syn::token::Colon(span)),
+                    ty: Box::new(generics::arc_with_pointee(
+                        arc_path,
+                        syn::parse_quote!(Self),
+                    )),
+                })
+            }
+            ImplReceiverKind::StaticImpl => {
+                let entrait = &self.crate_idents.entrait;
+                let ty = generics::arc_with_pointee(
+                    arc_path,
+                    syn::parse_quote!(::#entrait::Impl<EntraitT>),
+                );
+                syn::parse_quote! {
+                    __impl: #ty
+                }
+            }
+        }
+    }
+
+    fn gen_impl_receiver(&self, _: Span, mutability: Option<syn::token::Mut>) -> syn::FnArg {
         let entrait = &self.crate_idents.entrait;
         syn::parse_quote! {
-            __impl: &::#entrait::Impl<EntraitT>
+            __impl: &#mutability ::#entrait::Impl<EntraitT>
         }
     }
 
-    fn remove_generic_type_params(&self, sig: &mut syn::Signature) {
+    /// Remove the generic type and const params, which become params of the trait.
+    /// With `method_generics`, only the generic param of the dependency is removed, as it is replaced by `Self`.
+    fn remove_generic_params(&self, sig: &mut syn::Signature) {
         let deps_ident = match &self.deps {
             FnDeps::Generic { generic_param, .. } => generic_param.as_ref(),
             _ => None,
         };
+        let method_generics = self.opts.method_generics_value();
 
         let generics = &mut sig.generics;
         let mut params = syn::punctuated::Punctuated::new();
@@ -149,28 +200,23 @@ impl SignatureConverter<'_> {
 
         for param in params.into_iter() {
             match &param {
-                syn::GenericParam::Type(_) => {}
+                syn::GenericParam::Type(type_param)
+                    if !method_generics || Some(&type_param.ident) == deps_ident => {}
+                syn::GenericParam::Const(_) if !method_generics => {}
                 _ => {
                     generics.params.push(param);
                 }
             }
         }
 
-        if let Some(where_clause) = &mut generics.where_clause {
+        if let (Some(where_clause), Some(deps_ident)) = (&mut generics.where_clause, deps_ident) {
             let mut predicates = syn::punctuated::Punctuated::new();
             std::mem::swap(&mut predicates, &mut where_clause.predicates);
 
             for predicate in predicates.into_iter() {
                 match &predicate {
-                    syn::WherePredicate::Type(pred) => {
-                        if let Some(deps_ident) = &deps_ident {
-                            if !is_type_eq_ident(&pred.bounded_ty, deps_ident) {
-                                where_clause.predicates.push(predicate);
-                            }
-                        } else {
-                            where_clause.predicates.push(predicate);
-                        }
-                    }
+                    syn::WherePredicate::Type(pred)
+                        if is_type_eq_ident(&pred.bounded_ty, deps_ident) => {}
                     _ => {
                         where_clause.predicates.push(predicate);
                     }
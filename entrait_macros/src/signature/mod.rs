@@ -62,3 +62,73 @@ pub enum ReceiverGeneration {
     Rewrite,
     None,
 }
+
+/// Rewrite `fn f(..) -> impl Future<Output = T> + Send + 'a` into `async fn f(..) -> T`,
+/// so that an explicitly future-returning fn is treated like an `async fn`.
+///
+/// The `Send`-ness is then decided by the `?Send` option, and lifetime bounds are implied by the desugaring.
+/// Returns whether the signature was rewritten.
+pub fn desugar_future_output(sig: &mut syn::Signature) -> bool {
+    if sig.asyncness.is_some() {
+        return false;
+    }
+
+    let (async_span, output) = match &sig.output {
+        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+            syn::Type::ImplTrait(impl_trait) => match future_output(impl_trait) {
+                Some(output) => (impl_trait.impl_token.span, output.clone()),
+                None => return false,
+            },
+            _ => return false,
+        },
+        syn::ReturnType::Default => return false,
+    };
+
+    sig.asyncness = Some(syn::token::Async(async_span));
+    sig.output = if is_unit(&output) {
+        syn::ReturnType::Default
+    } else {
+        syn::parse_quote! { -> #output }
+    };
+    true
+}
+
+/// The `Output` of `impl Future<Output = T>`, when all other bounds are `Send` or lifetimes.
+/// A `'static` future does not borrow its inputs, which an `async fn` can't express.
+fn future_output(impl_trait: &syn::TypeImplTrait) -> Option<&syn::Type> {
+    let mut output = None;
+
+    for bound in &impl_trait.bounds {
+        match bound {
+            syn::TypeParamBound::Lifetime(lifetime) if lifetime.ident == "static" => return None,
+            syn::TypeParamBound::Lifetime(_) => {}
+            syn::TypeParamBound::Trait(trait_bound) => {
+                let segment = trait_bound.path.segments.last()?;
+                if segment.ident == "Send" && segment.arguments.is_empty() {
+                    continue;
+                }
+                if segment.ident != "Future" || output.is_some() {
+                    return None;
+                }
+                match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                        match args.args.first()? {
+                            syn::GenericArgument::AssocType(assoc) if assoc.ident == "Output" => {
+                                output = Some(&assoc.ty);
+                            }
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    output
+}
+
+fn is_unit(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())
+}
@@ -38,7 +38,7 @@ pub struct Punctuator<'s, S, P, E: ToTokens> {
 pub fn comma_sep(
     stream: &mut TokenStream,
     span: proc_macro2::Span,
-) -> Punctuator<EmptyToken, syn::token::Comma, EmptyToken> {
+) -> Punctuator<'_, EmptyToken, syn::token::Comma, EmptyToken> {
     Punctuator::new(stream, EmptyToken, syn::token::Comma(span), EmptyToken)
 }
 
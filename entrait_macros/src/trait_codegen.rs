@@ -4,11 +4,12 @@ use syn::spanned::Spanned;
 
 use crate::{
     analyze_generics::TraitFn,
-    attributes,
+    attributes::{self, IsEmpty},
+    diagnostics::OnUnimplemented,
     generics::{self, TraitDependencyMode, TraitIndirection},
     idents::CrateIdents,
     input::FnInputMode,
-    opt::{Opts, SpanOpt},
+    opt::{FutureSend, Opts, SpanOpt},
     signature::EntraitSignature,
     sub_attributes::{contains_async_trait, SubAttribute},
     token_util::push_tokens,
@@ -20,15 +21,18 @@ pub struct TraitCodegen<'s> {
     pub trait_indirection: TraitIndirection,
     pub trait_dependency_mode: &'s TraitDependencyMode<'s, 's>,
     pub sub_attributes: &'s [SubAttribute<'s>],
+    pub on_unimplemented: Option<OnUnimplemented>,
 }
 
 impl TraitCodegen<'_> {
+    #[allow(clippy::too_many_arguments)]
     pub fn gen_trait_def(
         &self,
         visibility: &syn::Visibility,
         trait_ident: &syn::Ident,
         trait_generics: &generics::TraitGenerics,
         supertraits: &Supertraits,
+        assoc_items: &[AssocItem],
         trait_fns: &[TraitFn],
         fn_input_mode: &FnInputMode<'_>,
     ) -> syn::Result<TokenStream> {
@@ -42,6 +46,7 @@ impl TraitCodegen<'_> {
                     trait_indirection: self.trait_indirection,
                     crate_idents: self.crate_idents,
                     trait_fns,
+                    assoc_items,
                     fn_input_mode,
                     span,
                 },
@@ -54,6 +59,8 @@ impl TraitCodegen<'_> {
             TraitDependencyMode::Concrete(_) => {
                 Some(attributes::Attr(attributes::EntraitForTraitParams {
                     crate_idents: self.crate_idents,
+                    context: self.opts.context_value(),
+                    leaf_accessor: self.opts.leaf_accessor_value(),
                 }))
             }
             _ => None,
@@ -61,11 +68,30 @@ impl TraitCodegen<'_> {
 
         let opt_mockall_automock_attr = match self.opts.default_option(self.opts.mockall, false) {
             SpanOpt(true, span) => Some(attributes::ExportGatedAttr {
-                params: attributes::MockallAutomockParams { span },
+                params: attributes::MockallAutomockParams { assoc_items, span },
                 opts: self.opts,
             }),
             _ => None,
         };
+        if let Some(unimock_attr) = &opt_unimock_attr {
+            if !unimock_attr.params.is_empty() {
+                check_mockable_assoc_items(assoc_items, MockBackend::Unimock)?;
+            }
+        }
+        if opt_mockall_automock_attr.is_some() {
+            check_mockable_assoc_items(assoc_items, MockBackend::Mockall)?;
+        }
+        if self.opts.method_generics_value() {
+            if let Some(unimock_attr) = &opt_unimock_attr {
+                if !unimock_attr.params.is_empty() {
+                    check_mockable_generics(trait_fns, MockBackend::Unimock)?;
+                }
+            }
+            if opt_mockall_automock_attr.is_some() {
+                check_mockable_generics(trait_fns, MockBackend::Mockall)?;
+            }
+        }
+
         let trait_visibility = TraitVisibility {
             visibility,
             fn_input_mode,
@@ -73,17 +99,38 @@ impl TraitCodegen<'_> {
 
         let fn_defs = trait_fns.iter().map(|trait_fn| {
             let attrs = &trait_fn.attrs;
-            let trait_fn_sig =
-                make_trait_fn_sig(&trait_fn.entrait_sig, self.sub_attributes, self.opts);
+            let trait_fn_sig = make_trait_fn_sig(
+                &trait_fn.entrait_sig,
+                self.sub_attributes,
+                trait_fn.future_send(self.opts),
+            );
 
-            quote! {
-                #(#attrs)*
-                #trait_fn_sig;
+            match &trait_fn.default {
+                // An `async fn` desugared to `fn -> impl Future` needs an async body
+                Some(default)
+                    if trait_fn.sig().asyncness.is_some() && trait_fn_sig.asyncness.is_none() =>
+                {
+                    quote! {
+                        #(#attrs)*
+                        #trait_fn_sig {
+                            async move #default
+                        }
+                    }
+                }
+                Some(default) => quote! {
+                    #(#attrs)*
+                    #trait_fn_sig #default
+                },
+                None => quote! {
+                    #(#attrs)*
+                    #trait_fn_sig;
+                },
             }
         });
 
         let params = trait_generics.trait_params();
         let where_clause = trait_generics.trait_where_clause();
+        let on_unimplemented = &self.on_unimplemented;
 
         let trait_sub_attributes = self.sub_attributes.iter().filter(|attr| {
             matches!(
@@ -97,7 +144,9 @@ impl TraitCodegen<'_> {
             #opt_entrait_for_trait_attr
             #opt_mockall_automock_attr
             #(#trait_sub_attributes)*
+            #on_unimplemented
             #trait_visibility trait #trait_ident #params #supertraits #where_clause {
+                #(#assoc_items)*
                 #(#fn_defs)*
             }
         })
@@ -113,6 +162,17 @@ pub enum Supertraits {
     },
 }
 
+impl Supertraits {
+    pub fn bounds(&self) -> impl Iterator<Item = &syn::TypeParamBound> {
+        match self {
+            Self::None => None,
+            Self::Some { bounds, .. } => Some(bounds),
+        }
+        .into_iter()
+        .flat_map(|bounds| bounds.iter())
+    }
+}
+
 impl ToTokens for Supertraits {
     fn to_tokens(&self, stream: &mut TokenStream) {
         if let Self::Some {
@@ -125,9 +185,100 @@ impl ToTokens for Supertraits {
     }
 }
 
-struct TraitVisibility<'a> {
-    visibility: &'a syn::Visibility,
-    fn_input_mode: &'a FnInputMode<'a>,
+/// An associated type or const of an entraited trait
+#[derive(Clone)]
+pub enum AssocItem {
+    Type {
+        item: syn::TraitItemType,
+        /// The type used by mock implementations
+        mock: Option<syn::Type>,
+        /// The type pinned in the `dyn` trait object that `delegate_by` delegates through
+        dyn_type: Option<syn::Type>,
+    },
+    Const {
+        item: syn::TraitItemConst,
+        /// The value used by mock implementations
+        mock: Option<syn::Expr>,
+    },
+}
+
+impl AssocItem {
+    pub fn ident(&self) -> &syn::Ident {
+        match self {
+            Self::Type { item, .. } => &item.ident,
+            Self::Const { item, .. } => &item.ident,
+        }
+    }
+
+    /// An impl item forwarding to the same item of `delegate`, e.g. `type Error = <T as Trait>::Error;`
+    pub fn gen_forwarding_impl_item(&self, delegate: &impl ToTokens) -> TokenStream {
+        let ident = self.ident();
+        match self {
+            Self::Type { .. } => quote! {
+                type #ident = <#delegate>::#ident;
+            },
+            Self::Const { item, .. } => {
+                let ty = &item.ty;
+                quote! {
+                    const #ident: #ty = <#delegate>::#ident;
+                }
+            }
+        }
+    }
+
+    /// The associated type pinned in a `dyn` trait object, e.g. `Error = String`
+    pub fn dyn_binding(&self) -> Option<syn::AssocType> {
+        match self {
+            Self::Type {
+                item,
+                dyn_type: Some(dyn_type),
+                ..
+            } => Some(syn::AssocType {
+                ident: item.ident.clone(),
+                generics: None,
+                eq_token: syn::token::Eq::default(),
+                ty: dyn_type.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The item pinned for mock implementations, e.g. `type Error = String;`
+    pub fn gen_mock_item(&self) -> Option<TokenStream> {
+        match self {
+            Self::Type {
+                item,
+                mock: Some(mock),
+                ..
+            } => {
+                let ident = &item.ident;
+                Some(quote! { type #ident = #mock; })
+            }
+            Self::Const {
+                item,
+                mock: Some(mock),
+            } => {
+                let ident = &item.ident;
+                let ty = &item.ty;
+                Some(quote! { const #ident: #ty = #mock; })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ToTokens for AssocItem {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        match self {
+            Self::Type { item, .. } => item.to_tokens(stream),
+            Self::Const { item, .. } => item.to_tokens(stream),
+        }
+    }
+}
+
+pub struct TraitVisibility<'a> {
+    pub visibility: &'a syn::Visibility,
+    pub fn_input_mode: &'a FnInputMode<'a>,
 }
 
 impl ToTokens for TraitVisibility<'_> {
@@ -158,10 +309,159 @@ impl ToTokens for TraitVisibility<'_> {
     }
 }
 
+#[derive(Clone, Copy)]
+enum MockBackend {
+    Unimock,
+    Mockall,
+}
+
+impl MockBackend {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Unimock => "unimock",
+            Self::Mockall => "mockall",
+        }
+    }
+}
+
+/// With `method_generics`, generic methods are preserved as-is on the trait, but the mock backends have some restrictions on them
+fn check_mockable_generics(trait_fns: &[TraitFn], backend: MockBackend) -> syn::Result<()> {
+    for trait_fn in trait_fns {
+        let sig = trait_fn.sig();
+        let fn_ident = &sig.ident;
+
+        for param in &sig.generics.params {
+            match param {
+                syn::GenericParam::Const(const_param) => {
+                    return Err(syn::Error::new(
+                        const_param.ident.span(),
+                        format!(
+                            "`{fn_ident}` has a const generic parameter, which can't be mocked by {}",
+                            backend.name()
+                        ),
+                    ));
+                }
+                syn::GenericParam::Type(type_param) => {
+                    let where_bounds = sig
+                        .generics
+                        .where_clause
+                        .iter()
+                        .flat_map(|where_clause| where_clause.predicates.iter())
+                        .filter_map(|predicate| match predicate {
+                            syn::WherePredicate::Type(predicate_type) => {
+                                match &predicate_type.bounded_ty {
+                                    syn::Type::Path(type_path)
+                                        if type_path.path.is_ident(&type_param.ident) =>
+                                    {
+                                        Some(predicate_type.bounds.iter())
+                                    }
+                                    _ => None,
+                                }
+                            }
+                            _ => None,
+                        })
+                        .flatten();
+
+                    if !has_static_bound(type_param.bounds.iter().chain(where_bounds)) {
+                        return Err(syn::Error::new(
+                            type_param.ident.span(),
+                            format!(
+                                "`{}` needs a `'static` bound for `{fn_ident}` to be mocked by {}",
+                                type_param.ident,
+                                backend.name()
+                            ),
+                        ));
+                    }
+                }
+                syn::GenericParam::Lifetime(_) => {}
+            }
+        }
+
+        let impl_trait_args = sig.inputs.iter().filter_map(|fn_arg| match fn_arg {
+            syn::FnArg::Typed(pat_type) => match pat_type.ty.as_ref() {
+                syn::Type::ImplTrait(type_impl_trait) => Some(type_impl_trait),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        });
+
+        for type_impl_trait in impl_trait_args {
+            match backend {
+                MockBackend::Mockall => {
+                    return Err(syn::Error::new(
+                        type_impl_trait.span(),
+                        format!("mockall does not support `impl Trait` in argument position of `{fn_ident}`. Use a generic parameter instead"),
+                    ));
+                }
+                MockBackend::Unimock => {
+                    if !has_static_bound(type_impl_trait.bounds.iter()) {
+                        return Err(syn::Error::new(
+                            type_impl_trait.span(),
+                            format!("`impl Trait` parameters need a `'static` bound for `{fn_ident}` to be mocked by unimock"),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Mock implementations need concrete associated types and consts
+fn check_mockable_assoc_items(assoc_items: &[AssocItem], backend: MockBackend) -> syn::Result<()> {
+    for assoc_item in assoc_items {
+        match (assoc_item, backend) {
+            (
+                AssocItem::Type {
+                    item, mock: None, ..
+                },
+                _,
+            ) => {
+                return Err(syn::Error::new(
+                    item.ident.span(),
+                    format!(
+                        "Associated type `{}` needs a type for {} to use. Annotate it with `#[entrait(mock = Type)]`",
+                        item.ident,
+                        backend.name()
+                    ),
+                ));
+            }
+            (AssocItem::Const { item, .. }, MockBackend::Mockall) if item.default.is_none() => {
+                return Err(syn::Error::new(
+                    item.ident.span(),
+                    format!(
+                        "Associated const `{}` needs a default value to be mocked by mockall",
+                        item.ident
+                    ),
+                ));
+            }
+            (AssocItem::Const { item, mock: None }, MockBackend::Unimock)
+                if item.default.is_none() =>
+            {
+                return Err(syn::Error::new(
+                    item.ident.span(),
+                    format!(
+                        "Associated const `{}` needs a value for unimock to use. Annotate it with `#[entrait(mock = value)]`",
+                        item.ident
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn has_static_bound<'b>(mut bounds: impl Iterator<Item = &'b syn::TypeParamBound>) -> bool {
+    bounds.any(|bound| matches!(bound, syn::TypeParamBound::Lifetime(lifetime) if lifetime.ident == "static"))
+}
+
 fn make_trait_fn_sig(
     entrait_sig: &EntraitSignature,
     sub_attributes: &[SubAttribute],
-    opts: &Opts,
+    future_send: FutureSend,
 ) -> syn::Signature {
     let mut sig = entrait_sig.sig.clone();
 
@@ -182,7 +482,7 @@ fn make_trait_fn_sig(
             ::core::future::Future<Output = #output_type>
         }];
 
-        if opts.future_send().0 {
+        if future_send.0 {
             bounds.push(quote! {
                 ::core::marker::Send
             });
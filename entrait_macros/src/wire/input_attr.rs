@@ -0,0 +1,99 @@
+use crate::opt::RefDelegate;
+
+use proc_macro2::Span;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+
+/// A `#[wire(..)]` attribute on a field of a struct deriving `Wire`
+pub enum WireAttr {
+    /// `leaf = Trait`: The field implements a leaf trait for the struct
+    Leaf(syn::Path),
+    /// `ref = dyn Trait`: `AsRef<dyn Trait>`
+    Ref(syn::Type, DerefField),
+    /// `Borrow = dyn Trait`: `Borrow<dyn Trait>`
+    Borrow(syn::Type, DerefField),
+    /// `Deref`: The struct derefs to the field
+    Deref(Span),
+    /// `Arc = Trait`, `Box = Trait`, `Swap = Trait` or `enum = Trait`: The generated accessor trait of that delegation
+    Accessor(RefDelegate, syn::Path),
+    /// `delegate = DelegateTrait, target = Type`: The delegation target, which defaults to the field type
+    Delegate {
+        delegate: syn::Path,
+        target: Option<syn::Type>,
+    },
+}
+
+impl Parse for WireAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.call(syn::Ident::parse_any)?;
+
+        let wire_attr = match ident.to_string().as_str() {
+            "Deref" => WireAttr::Deref(ident.span()),
+            "leaf" => WireAttr::Leaf(parse_eq(input)?),
+            "ref" => WireAttr::Ref(parse_eq(input)?, input.parse()?),
+            "Borrow" => WireAttr::Borrow(parse_eq(input)?, input.parse()?),
+            "Arc" => WireAttr::Accessor(RefDelegate::Arc, parse_eq(input)?),
+            "Box" => WireAttr::Accessor(RefDelegate::Box, parse_eq(input)?),
+            "Swap" => WireAttr::Accessor(RefDelegate::Swap, parse_eq(input)?),
+            "enum" => WireAttr::Accessor(RefDelegate::Enum(vec![]), parse_eq(input)?),
+            "delegate" => {
+                let delegate = parse_eq(input)?;
+                let target = if input.peek(syn::token::Comma) {
+                    input.parse::<syn::token::Comma>()?;
+                    let target_ident: syn::Ident = input.parse()?;
+                    if target_ident != "target" {
+                        return Err(syn::Error::new(
+                            target_ident.span(),
+                            "Expected `target = Type`",
+                        ));
+                    }
+                    Some(parse_eq(input)?)
+                } else {
+                    None
+                };
+
+                WireAttr::Delegate { delegate, target }
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("Unknown wire option \"{ident}\""),
+                ))
+            }
+        };
+
+        if !input.is_empty() {
+            return Err(input.error("Only one wiring per `#[wire(..)]` attribute is supported"));
+        }
+
+        Ok(wire_attr)
+    }
+}
+
+/// `, deref`: The field is a smart pointer, which is dereferenced before coercing the pointee into the `dyn` trait
+pub struct DerefField(pub bool);
+
+impl Parse for DerefField {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if !input.peek(syn::token::Comma) {
+            return Ok(Self(false));
+        }
+
+        input.parse::<syn::token::Comma>()?;
+        let ident: syn::Ident = input.parse()?;
+        if ident != "deref" {
+            return Err(syn::Error::new(ident.span(), "Expected `deref`"));
+        }
+
+        Ok(Self(true))
+    }
+}
+
+fn parse_eq<T: Parse>(input: ParseStream) -> syn::Result<T> {
+    input.parse::<syn::token::Eq>()?;
+    input.parse()
+}
+
+pub fn is_wire_attr(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("wire")
+}
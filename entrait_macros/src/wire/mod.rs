@@ -0,0 +1,186 @@
+//! # derive(Wire)
+//!
+//! Wires the fields of an application struct to the traits it delegates to,
+//! by generating the impls that `Impl<T>` needs from `T`.
+
+pub mod input_attr;
+
+use crate::entrait_trait::{accessor_idents, leaf_accessor_idents};
+use crate::opt::RefDelegate;
+use input_attr::*;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+pub fn output_tokens(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        syn::Data::Struct(data_struct) => &data_struct.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`Wire` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut impls = vec![];
+
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index::from(index)),
+        };
+
+        for attr in field.attrs.iter().filter(|attr| is_wire_attr(attr)) {
+            let wire_attr: WireAttr = attr.parse_args()?;
+            impls.push(gen_wire_impl(&input, field, &member, wire_attr)?);
+        }
+    }
+
+    Ok(quote! {
+        #(#impls)*
+    })
+}
+
+fn gen_wire_impl(
+    input: &syn::DeriveInput,
+    field: &syn::Field,
+    member: &syn::Member,
+    wire_attr: WireAttr,
+) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let field_ty = &field.ty;
+
+    let (trait_path, items) = match wire_attr {
+        WireAttr::Leaf(trait_path) => {
+            let (accessor_ident, method_ident) =
+                leaf_accessor_idents(&last_segment(&trait_path)?.ident);
+            (
+                with_last_ident(trait_path, accessor_ident)?,
+                quote! {
+                    type Target = #field_ty;
+
+                    fn #method_ident(&self) -> &#field_ty {
+                        &self.#member
+                    }
+                },
+            )
+        }
+        WireAttr::Ref(target, deref) => {
+            let field_ref = field_ref(member, deref);
+            let return_target = with_static_object_lifetime(&target);
+            (
+                syn::parse_quote! { ::core::convert::AsRef<#target> },
+                quote! {
+                    fn as_ref(&self) -> &(#return_target) {
+                        #field_ref
+                    }
+                },
+            )
+        }
+        WireAttr::Borrow(target, deref) => {
+            let field_ref = field_ref(member, deref);
+            let return_target = with_static_object_lifetime(&target);
+            (
+                syn::parse_quote! { ::core::borrow::Borrow<#target> },
+                quote! {
+                    fn borrow(&self) -> &(#return_target) {
+                        #field_ref
+                    }
+                },
+            )
+        }
+        WireAttr::Deref(span) => (
+            syn::parse_quote_spanned! { span=> ::core::ops::Deref },
+            quote! {
+                type Target = #field_ty;
+
+                fn deref(&self) -> &#field_ty {
+                    &self.#member
+                }
+            },
+        ),
+        WireAttr::Accessor(ref_delegate, trait_path) => {
+            let segment = last_segment(&trait_path)?;
+            if let (RefDelegate::Enum(_), false) = (&ref_delegate, segment.arguments.is_empty()) {
+                return Err(syn::Error::new(
+                    segment.arguments.span(),
+                    "The accessor trait of `delegate_by = enum(..)` has no generic arguments",
+                ));
+            }
+            let (accessor_ident, method_ident) =
+                accessor_idents(&segment.ident, &ref_delegate).unwrap();
+            (
+                with_last_ident(trait_path, accessor_ident)?,
+                quote! {
+                    fn #method_ident(&self) -> &#field_ty {
+                        &self.#member
+                    }
+                },
+            )
+        }
+        WireAttr::Delegate { delegate, target } => {
+            let delegate = if last_segment(&delegate)?.arguments.is_empty() {
+                syn::parse_quote! { #delegate<Self> }
+            } else {
+                delegate
+            };
+            let target = target.as_ref().unwrap_or(field_ty);
+            (
+                delegate,
+                quote! {
+                    type Target = #target;
+                },
+            )
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #trait_path for #ident #ty_generics #where_clause {
+            #items
+        }
+    })
+}
+
+/// A reference to the field, or with `deref` to the pointee of the field, to be coerced into a `dyn` trait
+fn field_ref(member: &syn::Member, deref: DerefField) -> TokenStream {
+    match deref {
+        DerefField(true) => quote! { &*self.#member },
+        DerefField(false) => quote! { &self.#member },
+    }
+}
+
+/// `dyn Trait` is `dyn Trait + 'static` as a generic argument, but not behind the returned reference
+fn with_static_object_lifetime(target: &syn::Type) -> syn::Type {
+    let mut target = target.clone();
+    if let syn::Type::TraitObject(trait_object) = &mut target {
+        let has_lifetime = trait_object
+            .bounds
+            .iter()
+            .any(|bound| matches!(bound, syn::TypeParamBound::Lifetime(_)));
+        if !has_lifetime {
+            trait_object.bounds.push(syn::parse_quote! { 'static });
+        }
+    }
+    target
+}
+
+fn last_segment(path: &syn::Path) -> syn::Result<&syn::PathSegment> {
+    path.segments
+        .last()
+        .ok_or_else(|| syn::Error::new(path.span(), "Expected a trait"))
+}
+
+/// i.e. `upstream::GetFoo` => `upstream::GetFooLeaf`
+fn with_last_ident(mut path: syn::Path, ident: syn::Ident) -> syn::Result<syn::Path> {
+    let span = path.span();
+    match path.segments.last_mut() {
+        Some(segment) => {
+            segment.ident = ident;
+            Ok(path)
+        }
+        None => Err(syn::Error::new(span, "Expected a trait")),
+    }
+}
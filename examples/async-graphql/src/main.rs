@@ -11,7 +11,7 @@ mod graphql {
     use super::db;
     use std::marker::PhantomData;
 
-    pub struct Query<A>(PhantomData<A>);
+    pub struct Query<A>(pub PhantomData<A>);
 
     #[async_graphql::Object]
     impl<A> Query<A>
@@ -80,4 +80,18 @@ mod graphql {
     }
 }
 
-fn main() {}
+#[tokio::main]
+async fn main() {
+    let app = entrait::Impl::new(());
+    let response = async_graphql::Schema::build(
+        graphql::Query::<entrait::Impl<()>>(std::marker::PhantomData),
+        async_graphql::EmptyMutation,
+        async_graphql::EmptySubscription,
+    )
+    .data(app)
+    .finish()
+    .execute("{ someValue }")
+    .await;
+
+    println!("{}", response.data);
+}
@@ -158,6 +158,87 @@
 //! ```
 //! This example generates a `MyModule` trait containing the methods `foo` and `bar`.
 //!
+//! Modules use generic dependencies by default.
+//! A group of accessors over the same [concrete dependency](#concrete-dependencies) can be turned into one leaf trait with the `concrete` option:
+//!
+//! ```rust
+//! # use entrait::*;
+//! pub struct Config {
+//!     db_url: String,
+//!     page_size: usize,
+//! }
+//!
+//! #[entrait(pub ConfigAccess, concrete)]
+//! mod config_access {
+//!     use super::Config;
+//!
+//!     pub fn db_url(config: &Config) -> &str {
+//!         &config.db_url
+//!     }
+//!
+//!     pub fn page_size(config: &Config) -> usize {
+//!         config.page_size
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let app = Impl::new(Config { db_url: "postgres://".into(), page_size: 20 });
+//! assert_eq!(20, app.page_size());
+//! # }
+//! ```
+//!
+//! Every non-private function with a dependency in such a module must depend on the same concrete type.
+//! Functions with generic dependencies belong in a separate module, and are reported with an error pointing at their `deps` parameter:
+//!
+//! ```compile_fail
+//! # use entrait::*;
+//! # pub struct Config {
+//! #     page_size: usize,
+//! # }
+//! #[entrait(pub ConfigAccess, concrete)]
+//! mod config_access {
+//!     use super::Config;
+//!
+//!     pub fn page_size(config: &Config) -> usize {
+//!         config.page_size
+//!     }
+//!
+//!     pub fn double_page_size(deps: &impl super::ConfigAccess) -> usize {
+//!         deps.page_size() * 2
+//!     }
+//! }
+//! ```
+//!
+//! With the `recursive` option, every non-private inline submodule gets its own trait, named after the module.
+//! The submodule traits are re-exported from their parent module, and become supertraits of the parent trait:
+//!
+//! ```rust
+//! # use entrait::*;
+//! # #[entrait(Repository)]
+//! # fn repository<D>(_: &D) {}
+//! #[entrait(pub Domain, recursive)]
+//! mod domain {
+//!     pub mod users {
+//!         pub fn create_user(deps: &impl super::super::Repository) {}
+//!     }
+//!
+//!     pub mod orders {
+//!         pub fn create_order(deps: &impl super::users::Users) {}
+//!     }
+//! }
+//!
+//! fn takes_domain(deps: &impl Domain) {
+//!     deps.create_user();
+//!     deps.create_order();
+//! }
+//! # fn main() {}
+//! ```
+//! As supertraits, the submodule traits let a bound on `Domain` stand for the whole module tree.
+//! A function that only needs a part of the tree can depend on a submodule trait instead, like `create_order` above.
+//! With `recursive = flat`, the submodule traits are only re-exported, and `Domain` just has the functions of the module itself.
+//! Each submodule gets its own mock API, named after its trait (e.g. `UsersMock`), when the parent module has a `mock_api`.
+//! Private submodules, and submodules with their own `entrait` attribute, are left untouched.
+//!
 //!
 //! # Testing
 //! ## Trait mocking with `Unimock`
@@ -334,7 +415,8 @@
 //! Here we actually have a trait `GetFoo` that is implemented two times: for `Impl<T> where T: GetFoo` and for `Config`.
 //! The first implementation is delegating to the other one.
 //!
-//! For making this work with _any_ downstream application type, we just have to manually implement `GetFoo` for that application:
+//! For making this work with _any_ downstream application type, we just have to implement `GetFoo` for that application,
+//! either with `#[derive(Wire)]` (see [Wiring the application](#wiring-the-application)) or manually:
 //!
 //! ```rust
 //! # mod some_upstream_crate {
@@ -569,6 +651,122 @@
 //!
 //!
 //!
+//! ### Wiring the application
+//! Instead of implementing the traits above by hand, the application type can derive [Wire].
+//! Each `#[wire(..)]` field attribute generates the impl for one delegation mode:
+//!
+//! ```rust
+//! # mod upstream {
+//! #     use entrait::*;
+//! #     pub struct Config { pub foo: String }
+//! #     #[entrait_export(pub GetFoo, leaf_accessor)]
+//! #     fn get_foo(config: &Config) -> &str {
+//! #         &config.foo
+//! #     }
+//! # }
+//! # use entrait::*;
+//! # #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+//! # pub trait Repository {
+//! #     fn fetch(&self) -> i32;
+//! # }
+//! # pub struct PgRepository;
+//! # #[entrait]
+//! # impl RepositoryImpl for PgRepository {
+//! #     fn fetch<D>(deps: &D) -> i32 { 42 }
+//! # }
+//! #[derive(Wire)]
+//! struct App {
+//!     #[wire(leaf = upstream::GetFoo)]
+//!     config: upstream::Config,
+//!     #[wire(delegate = DelegateRepository)]
+//!     repository: PgRepository,
+//! }
+//!
+//! entrait::assert_app!(App: upstream::GetFoo, Repository);
+//! ```
+//!
+//! Leaf traits are wired through `{Trait}Leaf`, an accessor trait generated with the `leaf_accessor` option,
+//! i.e. `#[entrait(pub GetFoo, leaf_accessor)]`. It is implemented for leaf traits without `delegate_by` or type parameters, with only `&self` methods.
+//! The option is off by default, as the trait is then implemented for every type implementing `{Trait}Leaf`,
+//! which rules out other blanket impls of the leaf trait.
+//!
+//! [`assert_app!`](assert_app) checks that the wiring is complete, by asserting that `Impl<App>` implements the listed entrypoints.
+//! Each entrypoint is checked separately, and a missing implementation is reported as an error naming the trait or accessor trait that `App` lacks.
+//!
+//! On Rust 1.78 or later, the traits generated by entrait carry `#[diagnostic::on_unimplemented]` messages,
+//! explaining what the application type needs for the current delegation mode, e.g. that `App` should implement `DelegateRepository<App>`.
+//! A hand-written `#[diagnostic::on_unimplemented]` on an entraited trait is kept instead.
+//!
+//! For example, an application type that doesn't implement a leaf trait is reported with "`App` does not implement `Clock`",
+//! noting that "`Clock` is implemented for `Impl<T>` when `T` implements `Clock`":
+//!
+//! ```compile_fail,E0277
+//! # use entrait::*;
+//! #[entrait]
+//! pub trait Clock {
+//!     fn now(&self) -> u64;
+//! }
+//!
+//! struct App;
+//!
+//! entrait::assert_app!(App: Clock);
+//! ```
+//!
+//! With `delegate_by = Arc`, the message is "`App` does not hand out the implementation that `Mailer` is delegated to",
+//! noting that `App` should implement the `MailerArc` accessor trait, or derive `Wire`:
+//!
+//! ```compile_fail,E0277
+//! # use entrait::*;
+//! #[entrait(MailerImpl, delegate_by = Arc)]
+//! pub trait Mailer {
+//!     fn send(&self, to: &str);
+//! }
+//!
+//! struct App;
+//!
+//! entrait::assert_app!(App: Mailer);
+//! ```
+//!
+//! ### Request-scoped context
+//! Per-request data like the current user, a trace ID or a database transaction is added with [`scope`](Scope::scope),
+//! which returns an `Impl<Scoped<App, Ctx>>` holding a clone of the application and the context.
+//! Traits implemented for `Impl<App>` keep working on the scoped value, as the traits generated by entrait forward through [Scoped] to the application.
+//! Leaf traits marked with `#[entrait(context)]` are instead taken from the context:
+//!
+//! ```rust
+//! # use entrait::*;
+//! #[entrait(context)]
+//! pub trait CurrentUser {
+//!     fn current_user(&self) -> &str;
+//! }
+//!
+//! #[entrait(context)]
+//! pub struct Request {
+//!     #[entrait(TraceId)]
+//!     trace_id: u32,
+//! }
+//!
+//! impl CurrentUser for Request {
+//!     fn current_user(&self) -> &str {
+//!         "ferris"
+//!     }
+//! }
+//!
+//! #[entrait(Audit)]
+//! fn audit(deps: &(impl CurrentUser + TraceId), action: &str) -> String {
+//!     format!("{} did {action} in {}", deps.current_user(), deps.trace_id())
+//! }
+//!
+//! #[derive(Clone)]
+//! struct App;
+//!
+//! let request = Impl::new(App).scope(Request { trace_id: 7 });
+//! assert_eq!("ferris did login in 7", request.audit("login"));
+//! ```
+//!
+//! The `Select{Trait}` trait of `delegate_by = DelegateTrait(A, B)` can be implemented for `Scoped<App, Ctx>`, to select the implementation by e.g. the tenant of the request.
+//!
+//!
 //! # Options and features
 //!
 //! #### Trait visibility
@@ -585,8 +783,49 @@
 //! #### async support
 //! Zero-cost, static-dispatch `async` works out of the box[^1].
 //!
-//! When dynamic dispatch is needed, for example in combination with `delegate_by=ref`, entrait understands the `#[async_trait]` attribute when applied _after_ the entrait macro.
-//! Entrait will re-apply that macro to the various generated impl blocks as needed.
+//! A function or trait method written as `fn f(..) -> impl Future<Output = T> + Send` is treated just like `async fn f(..) -> T`:
+//! it gets the same `Send` handling and the same mock support.
+//! This does not apply to `'static` futures, which are not allowed to borrow their inputs.
+//!
+//! When dynamic dispatch is needed, for example in combination with `delegate_by=ref`, entrait generates a dyn-compatible
+//! _shadow_ of the delegation target, named `Dyn{Trait}`, in which every `async fn` returns a boxed future.
+//! The shadow is implemented for every implementation of the original trait, so implementations keep using plain `async fn`s.
+//! Only the dynamic call boxes its future:
+//!
+//! ```rust
+//! # use entrait::*;
+//! #[entrait(RepositoryImpl, delegate_by=ref)]
+//! pub trait Repository {
+//!     async fn fetch(&self) -> i32;
+//! }
+//!
+//! pub struct MyRepository;
+//!
+//! #[entrait(ref)]
+//! impl RepositoryImpl for MyRepository {
+//!     async fn fetch<D>(deps: &D) -> i32 {
+//!         42
+//!     }
+//! }
+//!
+//! struct App(Box<dyn DynRepositoryImpl<App> + Send + Sync>);
+//!
+//! impl AsRef<dyn DynRepositoryImpl<Self> + Sync> for App {
+//!     fn as_ref(&self) -> &(dyn DynRepositoryImpl<Self> + Sync) {
+//!         self.0.as_ref()
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let app = Impl::new(App(Box::new(MyRepository)));
+//! # let _ = app.fetch();
+//! # }
+//! ```
+//!
+//! The same applies to `delegate_by = Arc`, `Box` and `Swap`, whose accessor traits hand out `dyn DynTraitImpl<Self>`.
+//!
+//! Alternatively, entrait understands the `#[async_trait]` attribute when applied _after_ the entrait macro.
+//! Entrait will then re-apply that macro to the various generated impl blocks as needed, and no shadow is generated.
 //!
 //! ##### async `Send`-ness
 //! Similar to `async_trait`, entrait generates a [Send]-bound on futures by default.
@@ -659,12 +898,12 @@
 //!
 //! * Representing a _graph_ of objects (even if acyclic) in Rust usually requires reference counting/heap allocation.
 //! * Each "dependency" abstraction often contains a lot of different functionality.
-//!     As an example, consider [DDD](https://en.wikipedia.org/wiki/Domain-driven_design)-based applications consisting of `DomainServices`.
-//!     There will typically be one such class per domain object, with a lot of methods in each.
-//!     This results in dependency graphs with fewer nodes overall, but the number of possible _call graphs_ is much larger.
-//!     A common problem with this is that the _actual dependencies_—the functions actually getting called—are encapsulated
-//!         and hidden away from public interfaces.
-//!     To construct valid dependency mocks in unit tests, a developer will have to read through full function bodies instead of looking at signatures.
+//!   As an example, consider [DDD](https://en.wikipedia.org/wiki/Domain-driven_design)-based applications consisting of `DomainServices`.
+//!   There will typically be one such class per domain object, with a lot of methods in each.
+//!   This results in dependency graphs with fewer nodes overall, but the number of possible _call graphs_ is much larger.
+//!   A common problem with this is that the _actual dependencies_—the functions actually getting called—are encapsulated
+//!   and hidden away from public interfaces.
+//!   To construct valid dependency mocks in unit tests, a developer will have to read through full function bodies instead of looking at signatures.
 //!
 //! `entrait` solves this by:
 //!
@@ -716,7 +955,7 @@ mod macros {
 /// ```
 ///
 /// * `$visibility`: Optional visibility specifier for the generated trait.
-///     See the [Rust documentation](https://doc.rust-lang.org/reference/visibility-and-privacy.html) for valid values.
+///   See the [Rust documentation](https://doc.rust-lang.org/reference/visibility-and-privacy.html) for valid values.
 /// * `$TraitIdent`: Any valid Rust identifier that starts with an upper-case character, used as the name of the new trait.
 ///
 /// with options:
@@ -726,6 +965,119 @@ mod macros {
 /// fn ...
 /// ```
 ///
+/// #### Generic parameters
+/// Apart from the dependency, the generic type and const parameters of the function become parameters of the generated trait.
+/// With the `method_generics` option, they are instead kept on the generated trait method,
+/// so that one trait bound covers every instantiation.
+/// This includes type parameters, const generics, where clauses and argument-position `impl Trait`:
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(Save, method_generics)]
+/// fn save<D, K>(deps: &D, item: impl std::fmt::Debug, key: K)
+/// where
+///     K: AsRef<str>,
+/// {}
+///
+/// fn save_both(deps: &impl Save) {
+///     deps.save(1, "a");
+///     deps.save("two", String::from("b"));
+/// }
+/// ```
+///
+/// The mocking libraries require generic type parameters and `impl Trait` parameters to be `'static`,
+/// and don't support const generics. Mockall doesn't support `impl Trait` parameters.
+/// The macro reports an error when a mock can't be generated for such a method.
+///
+/// #### Mutable dependencies
+/// A dependency taken by `&mut` reference generates a `&mut self` trait method,
+/// and the implementation for [Impl] requires mutable access to the application:
+///
+/// ```rust
+/// # use entrait::*;
+/// struct State {
+///     count: u32,
+/// }
+///
+/// #[entrait(Increment)]
+/// fn increment(state: &mut State) -> u32 {
+///     state.count += 1;
+///     state.count
+/// }
+///
+/// #[entrait(IncrementTwice)]
+/// fn increment_twice(deps: &mut impl Increment) -> u32 {
+///     deps.increment();
+///     deps.increment()
+/// }
+///
+/// let mut app = Impl::new(State { count: 0 });
+/// assert_eq!(2, app.increment_twice());
+/// ```
+///
+/// `&mut self` methods can't be delegated with `delegate_by = ref`, since the delegation target is only available by shared reference.
+/// Unimock can mock `&mut self` methods, but not unmock them.
+///
+/// #### `Arc` dependencies
+/// Spawning background tasks requires owned, `'static` dependencies.
+/// A generic dependency taken as `Arc<impl Trait>` generates a `self: Arc<Self>` trait method:
+///
+/// ```rust
+/// # use entrait::*;
+/// use std::sync::Arc;
+///
+/// #[entrait(Notify)]
+/// fn notify(deps: &impl std::any::Any, job_id: u32) {}
+///
+/// #[entrait(Schedule)]
+/// fn schedule(deps: Arc<impl Notify + Send + Sync + 'static>, job_id: u32) {
+///     std::thread::spawn(move || deps.notify(job_id)).join().unwrap();
+/// }
+///
+/// let app = Arc::new(Impl::new(()));
+/// app.schedule(42);
+/// ```
+///
+/// Entraited traits with `self: Arc<Self>` methods must use `delegate_by = DelegateTrait` to be implemented for [Impl].
+///
+/// #### Delegation
+/// Functions (and modules) accept `delegate_by` like traits do, to make the function a swappable default implementation.
+/// A `TraitImpl<T>` trait is generated with the function as a provided method, and the application picks its implementation through `delegate_by`.
+/// [Impl] still has to satisfy the dependency bounds of the function:
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(pub SendEmail, delegate_by = DelegateSendEmail)]
+/// fn send_email(deps: &impl std::any::Any, to: &str) -> String {
+///     format!("smtp:{to}")
+/// }
+///
+/// // Selects the default implementation:
+/// pub struct Smtp;
+/// impl<T> SendEmailImpl<T> for Smtp {}
+///
+/// pub struct Fake;
+/// #[entrait]
+/// impl SendEmailImpl for Fake {
+///     fn send_email<D>(_: &D, to: &str) -> String {
+///         format!("fake:{to}")
+///     }
+/// }
+///
+/// impl DelegateSendEmail<Self> for () {
+///     type Target = Smtp;
+/// }
+///
+/// impl DelegateSendEmail<Self> for bool {
+///     type Target = Fake;
+/// }
+///
+/// assert_eq!("smtp:a", Impl::new(()).send_email("a"));
+/// assert_eq!("fake:a", Impl::new(true).send_email("a"));
+/// ```
+///
+/// `delegate_by = ref` works in the same way. For modules, the generated traits are re-exported from the module.
+///
 /// ## For modules
 /// Using the attribute on a module is used to group several non-private functions into one trait.
 /// Only non-private functions are considered by the macro.
@@ -738,6 +1090,68 @@ mod macros {
 /// }
 /// ```
 ///
+/// Functions inside the module may have their own `#[entrait(..)]` attribute, with these options:
+///
+/// | Option                | Description                                                              |
+/// | --------------------- | ------------------------------------------------------------------------ |
+/// | `skip`                | Leave the function out of the trait.                                     |
+/// | `rename = ident`      | Use another name for the trait method.                                   |
+/// | `no_deps`             | The function has no dependency parameter, like the module-level option.  |
+/// | `?Send`               | Opt out of `Send` futures for this function only.                        |
+/// | `mock_api = Ident`    | Name for this function's unimock `MockFn`, re-exported from the module.  |
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(pub Users)]
+/// mod users {
+///     # use std::any::Any;
+///     #[entrait(rename = fetch_user)]
+///     pub fn get_user(deps: &impl Any, id: u32) -> String {
+///         format!("user{id}")
+///     }
+///
+///     #[entrait(skip)]
+///     pub fn user_key(id: u32) -> String {
+///         format!("user:{id}")
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_eq!("user1", Impl::new(()).fetch_user(1));
+/// # }
+/// ```
+///
+/// A per-function `mock_api` is an alias into the module's unimock mock API,
+/// so it is an error when the module does not use `unimock`, e.g. when mocking with `mockall` only:
+///
+/// ```compile_fail
+/// # use entrait::*;
+/// #[entrait(pub Users, unimock = false, mockall)]
+/// mod users {
+///     #[entrait(mock_api = FetchUserMock)]
+///     pub fn fetch_user(deps: &impl std::any::Any, id: u32) -> String {
+///         format!("user{id}")
+///     }
+/// }
+/// ```
+///
+/// Private functions are not part of the trait, so per-function options on them are an error as well:
+///
+/// ```compile_fail
+/// # use entrait::*;
+/// #[entrait(pub Users)]
+/// mod users {
+///     pub fn fetch_user(deps: &impl std::any::Any, id: u32) -> String {
+///         user_key(id)
+///     }
+///
+///     #[entrait(skip)]
+///     fn user_key(id: u32) -> String {
+///         format!("user:{id}")
+///     }
+/// }
+/// ```
+///
 ///
 /// ## For traits
 /// When used with a trait, the macro will only provide a delegating implementation for [Impl] that delegates to another trait implementation.
@@ -789,6 +1203,310 @@ mod macros {
 /// trait Foo {}
 /// ```
 ///
+/// ##### Example 4
+/// Leaf dependency, through [Deref](::core::ops::Deref) (delegation bounds: `T: Deref, T::Target: Foo`):
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(delegate_by = Deref)]
+/// trait Foo {}
+/// ```
+/// `Impl<Arc<dyn Foo + Send + Sync>>` implements `Foo`, for example. The dispatch is static when the target is not a `dyn` trait object.
+///
+/// ##### Example 5
+/// Leaf dependency, dynamic dispatch through a smart pointer (delegation bound: `T: MailerArc<T>`):
+/// ```rust
+/// # use entrait::*;
+/// use std::sync::Arc;
+///
+/// #[entrait(pub Mailer, delegate_by = Arc)]
+/// fn send(deps: &impl std::any::Any, to: &str) -> String {
+///     format!("smtp:{to}")
+/// }
+///
+/// pub struct App {
+///     mailer: Arc<dyn MailerImpl<App> + Send + Sync>,
+/// }
+///
+/// // Generated by `delegate_by = Arc`:
+/// impl MailerArc<Self> for App {
+///     fn mailer_arc(&self) -> &Arc<dyn MailerImpl<Self> + Send + Sync> {
+///         &self.mailer
+///     }
+/// }
+///
+/// struct Smtp;
+/// impl<T> MailerImpl<T> for Smtp {}
+///
+/// let app = Impl::new(App { mailer: Arc::new(Smtp) });
+/// assert_eq!("smtp:a", app.send("a"));
+/// let mailer: Arc<_> = app.mailer_arc().clone();
+/// ```
+/// The generated accessor trait hands out the `Arc<dyn Trait + Send + Sync>`, so it can be cloned when ownership is needed.
+/// `delegate_by = Box` does the same with a `Box`, using a `TraitBox` trait with a `trait_box` method.
+///
+/// ##### Example 6
+/// Internal dependency, implementation selected at runtime with static dispatch (delegation bound: `T: AsRepoChoice`):
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(RepoImpl, delegate_by = enum(PgRepo, MemRepo))]
+/// pub trait Repo {
+///     async fn fetch(&self, id: u32) -> String;
+/// }
+///
+/// pub struct PgRepo;
+/// #[entrait(ref)]
+/// impl RepoImpl for PgRepo {
+///     async fn fetch<D>(_: &D, id: u32) -> String { format!("pg/{id}") }
+/// }
+///
+/// pub struct MemRepo;
+/// #[entrait(ref)]
+/// impl RepoImpl for MemRepo {
+///     async fn fetch<D>(_: &D, id: u32) -> String { format!("mem/{id}") }
+/// }
+///
+/// struct App {
+///     repo: RepoChoice,
+/// }
+///
+/// // Generated by `delegate_by = enum(..)`:
+/// impl AsRepoChoice for App {
+///     fn repo_choice(&self) -> &RepoChoice {
+///         &self.repo
+///     }
+/// }
+///
+/// let app = Impl::new(App { repo: RepoChoice::MemRepo(MemRepo) });
+/// ```
+/// The generated `RepoChoice` enum has a variant for each implementation, named after its type.
+/// Implementations with the same type name in different modules need their own variant names, as in `enum(Pg = pg::Repo, Mem = mem::Repo)`.
+/// It implements `RepoImpl<T>` by matching on the variant, so `async fn`s need no boxing.
+///
+/// ##### Example 7
+/// Internal dependency, implementation swappable while the application is running (delegation bound: `T: RateLimitSwap<T>`):
+/// ```rust
+/// # use entrait::*;
+/// use std::sync::{Arc, RwLock};
+///
+/// #[entrait(RateLimitImpl, delegate_by = Swap)]
+/// pub trait RateLimit {
+///     fn limit(&self) -> u32;
+/// }
+///
+/// pub struct Strict;
+/// #[entrait(ref)]
+/// impl RateLimitImpl for Strict {
+///     fn limit<D>(_: &D) -> u32 { 1 }
+/// }
+///
+/// pub struct Lenient;
+/// #[entrait(ref)]
+/// impl RateLimitImpl for Lenient {
+///     fn limit<D>(_: &D) -> u32 { 100 }
+/// }
+///
+/// struct App {
+///     rate_limit: RwLock<Arc<dyn RateLimitImpl<App> + Send + Sync>>,
+/// }
+///
+/// // Generated by `delegate_by = Swap`:
+/// impl RateLimitSwap<Self> for App {
+///     fn rate_limit_swap(&self) -> &RwLock<Arc<dyn RateLimitImpl<Self> + Send + Sync>> {
+///         &self.rate_limit
+///     }
+/// }
+///
+/// let app = Impl::new(App { rate_limit: RwLock::new(Arc::new(Strict)) });
+/// assert_eq!(1, app.limit());
+/// let strict = app.swap_rate_limit(Arc::new(Lenient));
+/// assert_eq!(100, app.limit());
+/// ```
+/// The implementations are the same as with `delegate_by=ref`, so switching between the two needs no changes to them.
+/// Each call clones the current `Arc` out of the lock before calling it, so a swap never waits for calls in progress,
+/// which finish with the implementation they started with.
+/// The generated `swap_rate_limit` method replaces the implementation and returns the previous one.
+/// Methods returning data borrowed from their inputs, i.e. with an elided or non-`'static` lifetime, can't be delegated this way.
+/// As the lock is a `std::sync::RwLock`, `Swap` requires `std`, unlike the other delegation modes.
+///
+/// ##### Example 8
+/// Internal dependency, implementation selected per call from context in `Impl<T>` (delegation bound: `T: DelegateRepo<T>`):
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(RepoImpl, delegate_by = DelegateRepo(PgRepo, MemRepo))]
+/// pub trait Repo {
+///     fn fetch(&self, id: u32) -> String;
+/// }
+///
+/// pub struct PgRepo;
+/// #[entrait]
+/// impl RepoImpl for PgRepo {
+///     fn fetch<D>(_: &D, id: u32) -> String { format!("pg/{id}") }
+/// }
+///
+/// pub struct MemRepo;
+/// #[entrait]
+/// impl RepoImpl for MemRepo {
+///     fn fetch<D>(_: &D, id: u32) -> String { format!("mem/{id}") }
+/// }
+///
+/// struct App {
+///     tenant: &'static str,
+/// }
+///
+/// impl DelegateRepo<Self> for App {
+///     type Target = RepoSelector;
+/// }
+///
+/// // `SelectRepo` is generated by `delegate_by = DelegateRepo(PgRepo, MemRepo)`, and called on every call:
+/// impl SelectRepo for App {
+///     fn select_repo(deps: &Impl<Self>) -> RepoSelection {
+///         match deps.tenant {
+///             "acme" => RepoSelection::PgRepo,
+///             _ => RepoSelection::MemRepo,
+///         }
+///     }
+/// }
+///
+/// assert_eq!("pg/1", Impl::new(App { tenant: "acme" }).fetch(1));
+/// assert_eq!("mem/1", Impl::new(App { tenant: "demo" }).fetch(1));
+/// ```
+/// Listing the candidates generates a `RepoSelection` enum with a variant for each of them, named like the variants of `enum(..)`, a `SelectRepo` trait for `T`,
+/// and a `RepoSelector` delegation target which calls the selected candidate. The candidates are statically known, so nothing is boxed,
+/// and `DelegateRepo` can still point to any other implementation. Methods without `self` can't be delegated this way.
+///
+/// #### Associated types and constants
+/// Associated types and constants are forwarded from the delegation target, i.e. from `T` for leaf dependencies,
+/// from `DelegateFoo<T>::Target` using static dispatch, or from `T::Target` with `delegate_by = Deref`.
+/// They are not supported with `enum(..)` or candidates selected per call, where the implementations may disagree on them.
+///
+/// With `delegate_by=ref`, `Arc`, `Box` or `Swap`, the target is a `dyn` trait object, which can't leave associated types unspecified.
+/// They are pinned in the object type with `#[entrait(dyn = ...)]`, e.g. `dyn RepositoryImpl<T, Error = std::io::Error>`.
+/// Associated constants can't be part of a trait object, so they are not supported in these modes.
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(RepositoryImpl, delegate_by = ref)]
+/// pub trait Repository {
+///     #[entrait(dyn = std::io::Error)]
+///     # #[entrait(mock = std::io::Error)]
+///     type Error;
+///
+///     fn fetch(&self) -> Result<i32, Self::Error>;
+/// }
+///
+/// struct App;
+///
+/// impl AsRef<dyn RepositoryImpl<Self, Error = std::io::Error>> for App {
+///     // ..
+///     # fn as_ref(&self) -> &(dyn RepositoryImpl<Self, Error = std::io::Error> + 'static) { unimplemented!() }
+/// }
+/// ```
+///
+/// Mock implementations need to know which types and values to use. These are given with `#[entrait(mock = ...)]`:
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+/// pub trait Repository {
+///     #[entrait(mock = String)]
+///     type Error;
+///     #[entrait(mock = 10)]
+///     const MAX_BATCH: usize;
+///
+///     fn fetch(&self) -> Result<i32, Self::Error>;
+/// }
+///
+/// pub struct PgRepository;
+///
+/// #[entrait]
+/// impl RepositoryImpl for PgRepository {
+///     type Error = std::io::Error;
+///     const MAX_BATCH: usize = 100;
+///
+///     fn fetch<D>(_deps: &D) -> Result<i32, std::io::Error> {
+///         Ok(42)
+///     }
+/// }
+/// ```
+///
+/// Mockall always uses the default value of an associated constant, so it must have one.
+///
+/// #### Default methods
+/// Provided methods keep their default bodies in all delegation modes.
+/// With `delegate_by`, they are also provided methods of the delegation target trait,
+/// so implementations of `TraitImpl` may omit them:
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(GreeterImpl, delegate_by = DelegateGreeter)]
+/// pub trait Greeter {
+///     fn name(&self) -> String;
+///
+///     fn greet(&self) -> String {
+///         format!("Hello, {}!", self.name())
+///     }
+/// }
+///
+/// pub struct English;
+///
+/// #[entrait]
+/// impl GreeterImpl for English {
+///     fn name<D>(_deps: &D) -> String {
+///         "world".to_string()
+///     }
+/// }
+///
+/// impl DelegateGreeter<Self> for () {
+///     type Target = English;
+/// }
+///
+/// assert_eq!("Hello, world!", Impl::new(()).greet());
+/// ```
+///
+/// The default body of an `async` method has to produce a `Send` future, unless using `?Send`.
+/// It usually needs a `Sync` supertrait for borrowing `self` across an `.await`.
+///
+/// #### Generic traits
+/// Traits may have lifetime, type and const parameters.
+/// With `delegate_by`, the generated traits take the `T` of `Impl<T>` after the lifetimes, followed by the trait's own parameters.
+/// That way, each app type may select its own implementation per instantiation of the trait:
+///
+/// ```rust
+/// # use entrait::*;
+/// #[derive(Debug, PartialEq)]
+/// pub struct User(String);
+///
+/// #[entrait(StoreImpl, delegate_by = DelegateStore)]
+/// pub trait Store<K, V> {
+///     fn get(&self, key: K) -> Option<V>;
+/// }
+///
+/// pub struct UserStore;
+///
+/// #[entrait]
+/// impl StoreImpl<String, User> for UserStore {
+///     fn get<D>(_deps: &D, key: String) -> Option<User> {
+///         Some(User(key))
+///     }
+/// }
+///
+/// impl DelegateStore<Self, String, User> for () {
+///     type Target = UserStore;
+/// }
+///
+/// assert_eq!(Some(User("alice".to_string())), Impl::new(()).get("alice".to_string()));
+/// ```
+///
+/// With `delegate_by = ref`, the type parameters have to be `'static`, as the `TraitImpl` is used as a `dyn` trait object.
+///
+/// #### Supertraits
+/// The `Impl<T>` implementation of an entraited trait requires `Impl<T>` to implement the supertraits of the trait,
+/// which may in turn be delegated elsewhere.
+/// This allows trait hierarchies like `trait Admin: Users + Audit` to be inverted.
+///
+/// The generated `TraitImpl` inherits the `Send`, `Sync`, `Unpin` and lifetime supertraits,
+/// so that e.g. `dyn TraitImpl<T>` is `Send + Sync` when the entraited trait is.
+///
 ///
 /// ## For impl blocks
 /// When used on an impl block, the macro will generate a delegating implementation for a _delegation trait_ "`TraitImpl`" generated with `#[entrait(TraitImpl)] trait Trait {}`.
@@ -837,6 +1555,45 @@ mod macros {
 /// assert_eq!(42, Impl::new(App).foo(21));
 /// ```
 ///
+/// ##### Concrete dependencies
+/// A method in the impl block may take a concrete dependency instead of a generic one.
+/// The concrete value is projected out of the application type through [AsRef](::core::convert::AsRef),
+/// so the generated implementation requires `T: AsRef<Concrete>`:
+///
+/// ```rust
+/// # use entrait::*;
+/// # #[entrait(TraitImpl, delegate_by = DelegateTrait)]
+/// # trait Trait {
+/// #     fn foo(&self, arg: i32) -> i32;
+/// # }
+/// pub struct Config {
+///     factor: i32,
+/// }
+///
+/// pub struct MyType;
+///
+/// #[entrait]
+/// impl TraitImpl for MyType {
+///     fn foo(config: &Config, arg: i32) -> i32 {
+///         arg * config.factor
+///     }
+/// }
+///
+/// struct App(Config);
+///
+/// impl AsRef<Config> for App {
+///     fn as_ref(&self) -> &Config {
+///         &self.0
+///     }
+/// }
+///
+/// impl DelegateTrait<Self> for App {
+///     type Target = MyType;
+/// }
+///
+/// assert_eq!(42, Impl::new(App(Config { factor: 2 })).foo(21));
+/// ```
+///
 /// ##### `dyn trait` delegation with `AsRef`:
 /// The only attribute parameter currently supported on impl blocks is adding the `ref` keyword, to indicate that the delegation strategy uses dynamic dispatch through `AsRef`:
 ///
@@ -860,11 +1617,72 @@ mod macros {
 /// #### Syntax
 /// ```no_compile
 /// #[entrait(ref?)]
-/// impl TraitPath for Type {
+/// impl $generics? TraitPath for Type $where_clause? {
 ///     ...
 /// }
 /// ```
 ///
+/// The generics and where clause of the impl block are kept on the inherent impl block, and also applied to the generated trait implementation.
+///
+/// ## For structs
+/// When used with a struct, the macro generates an accessor trait for each field that has its own `#[entrait(..)]` attribute.
+/// Each accessor is a [concrete leaf dependency](crate#concrete-dependencies) returning a reference to the field,
+/// and works the same way as an entraited `fn` taking `&Struct` as its only parameter.
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait]
+/// pub struct Config {
+///     #[entrait(pub GetDbUrl)]
+///     db_url: String,
+///     #[entrait(pub GetPageSize)]
+///     page_size: usize,
+/// }
+///
+/// # fn main() {
+/// let app = Impl::new(Config { db_url: "postgres://".into(), page_size: 20 });
+/// assert_eq!("postgres://", app.db_url());
+/// assert_eq!(&20, app.page_size());
+/// # }
+/// ```
+///
+/// #### Syntax
+/// ```no_compile
+/// #[entrait($option, ...)]
+/// struct Struct {
+///     #[entrait($visibility? $TraitIdent, $field_option, ...)]
+///     field: Type,
+/// }
+/// ```
+///
+/// The struct options `export`, `unimock` and `mockall` apply to every field.
+/// Each field accepts the same options as an entraited `fn`, including `mock_api`, except `no_deps` and `?Send`.
+/// Generic structs are not supported.
+///
+/// ## For `const` and `static` items
+/// When used with a `const` or `static`, the macro generates a trait with one method returning the value,
+/// so that it can be overridden by mocks.
+/// The method is named after the item in lower case.
+/// A `const` is returned by value, and a `static` is returned as a `&'static` reference.
+///
+/// ```rust
+/// # use entrait::*;
+/// # use std::time::Duration;
+/// #[entrait(pub RequestTimeout)]
+/// const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+///
+/// #[entrait(pub ServiceName)]
+/// static SERVICE_NAME: &str = "my-service";
+///
+/// # fn main() {
+/// let app = Impl::new(());
+/// assert_eq!(Duration::from_secs(5), app.request_timeout());
+/// assert_eq!("my-service", *app.service_name());
+/// # }
+/// ```
+///
+/// The syntax and options are the same as for functions, except that `no_deps` is implied, and `?Send` is not supported.
+///
 ///
 ///
 /// # Options
@@ -873,11 +1691,16 @@ mod macros {
 /// | Option              | Type                      | Target             | Default     | Description         |
 /// | ------------------- | ------------------------- | ------------------ | ----------- | ------------------- |
 /// | `no_deps`           | `bool`                    | `fn`               | `false`     | Disables the dependency parameter, so that the first parameter is just interpreted as a normal function parameter. Useful for reducing noise in some situations. |
-/// | `export`            | `bool`                    | `fn`+`mod`         | `false`     | If mocks are generated, exports these mocks even in release builds. Only relevant for libraries. |
+/// | `concrete`          | `bool`                    | `fn`+`mod`         | `false`     | Requires every function to depend on the same concrete type, and generates a leaf trait implemented for that type. Allows concrete dependencies in modules. |
+/// | `context`           | `bool`                    | `fn`+`mod`+`trait`+`struct` | `false`     | Marks a leaf trait as request-scoped context: [Scoped] takes it from its context instead of from the application. Requires a concrete dependency for `fn`s and `mod`s. |
+/// | `leaf_accessor`     | `bool`                    | `fn`+`mod`+`trait`+`struct` | `false`     | Generates the `{Trait}Leaf` accessor trait of a leaf trait, through which the trait is implemented for any type handing out an implementation of it. Used by `#[wire(leaf = Trait)]`. Requires a concrete dependency for `fn`s and `mod`s. |
+/// | `recursive`         | `bool`/`flat`             | `mod`              | `false`     | Generates a nested trait for each non-private inline submodule, used as supertraits of the module's trait. With `flat`, the nested traits are only re-exported. |
+/// | `method_generics`   | `bool`                    | `fn`+`mod`         | `false`     | Keeps the generic parameters of functions on the trait methods, instead of making them parameters of the trait. |
+/// | `export`            | `bool`                    | `fn`+`mod`+`struct` | `false`     | If mocks are generated, exports these mocks even in release builds. Only relevant for libraries. |
 /// | `mock_api`          | `ident`                   | `fn`+`mod`+`trait` |             | The identifier to use for mock APIs (for libraries that support custom identifiers. The `unimock` library requires this to be explicitly specified. |
-/// | `unimock`           | `bool`                    | `fn`+`mod`+`trait` | `false`[^1] | Used to turn _off_ unimock implementation when the `unimock` _feature_ is enabled. |
-/// | `mockall`           | `bool`                    | `fn`+`mod`+`trait` | `false`     | Enable mockall mocks. |
-/// | `delegate_by`       | `Self`/`ref`/custom ident | `trait`            | `Self`      | Controls the generated `Impl<T>` delegation of this trait. `Self` generates a `T: Trait` bound. `ref` generates a [`T: AsRef<dyn Trait>`](::core::convert::AsRef) bound. `Borrow` is deprecated and uses the [core::borrow::Borrow] trait. Any other value generates a new trait with that name which controls the delegation. |
+/// | `unimock`           | `bool`                    | `fn`+`mod`+`trait`+`struct` | `false`[^1] | Used to turn _off_ unimock implementation when the `unimock` _feature_ is enabled. |
+/// | `mockall`           | `bool`                    | `fn`+`mod`+`trait`+`struct` | `false`     | Enable mockall mocks. |
+/// | `delegate_by`       | `Self`/`ref`/`Deref`/`Arc`/`Box`/`Swap`/`enum(..)`/custom ident | `fn`+`mod`+`trait` | `Self`      | Controls the generated `Impl<T>` delegation of this trait. `Self` generates a `T: Trait` bound. `ref` generates a [`T: AsRef<dyn Trait>`](::core::convert::AsRef) bound. `Deref` generates a [`T: Deref`](::core::ops::Deref) bound, with the target implementing the trait. `Arc` and `Box` generate an accessor trait for `T`, handing out the smart pointer to a `dyn Trait + Send + Sync`. `Swap` generates an accessor trait handing out a `std::sync::RwLock<Arc<dyn Trait + Send + Sync>>`, with a provided method swapping the implementation, and requires `std`. `enum(A, B)` generates an enum of the listed implementations of the impl trait, and an accessor trait for `T` handing it out. `Borrow` is deprecated and uses the [core::borrow::Borrow] trait. Any other value generates a new trait with that name which controls the delegation. `DelegateTrait(A, B)` additionally generates a selector target, calling the implementation that `T` selects on each call. |
 /// | `?Send`             | `true`                    | `fn`+`mod`+`trait` | `false`     | Opts out of `Send` bounds for Future outputs from `async` functions in generated traits.|
 ///
 /// [^1]: Enabled by default by turning on the `unimock` cargo feature.
@@ -890,9 +1713,263 @@ pub use macros::entrait;
 /// A good way to reduce noise can to to import it as `use entrait::entrait_export as entrait;`.
 pub use macros::entrait_export;
 
+/// Derive macro wiring the fields of an application struct to the traits that [Impl] delegates to.
+///
+/// Each `#[wire(..)]` attribute on a field generates one impl for the struct:
+///
+/// | Attribute                               | Generated impl |
+/// | --------------------------------------- | -------------- |
+/// | `#[wire(leaf = Trait)]`                 | `TraitLeaf`, the accessor trait generated for leaf traits with the `leaf_accessor` option, with the field as the implementation of `Trait`. |
+/// | `#[wire(ref = dyn Trait)]`              | [`AsRef<dyn Trait>`](::core::convert::AsRef), for `delegate_by = ref`. Add `, deref` when the field is a smart pointer to the implementation, like `Box` or `Arc`. |
+/// | `#[wire(Borrow = dyn Trait)]`           | [`Borrow<dyn Trait>`](::core::borrow::Borrow), for `delegate_by = Borrow`. Accepts `, deref` like `ref`. |
+/// | `#[wire(Deref)]`                        | [`Deref`](::core::ops::Deref) to the field, for `delegate_by = Deref`. |
+/// | `#[wire(Arc = Trait)]`                  | `TraitArc`, the accessor trait of `delegate_by = Arc`. |
+/// | `#[wire(Box = Trait)]`                  | `TraitBox`, the accessor trait of `delegate_by = Box`. |
+/// | `#[wire(Swap = Trait)]`                 | `TraitSwap`, the accessor trait of `delegate_by = Swap`. |
+/// | `#[wire(enum = Trait)]`                 | `AsTraitChoice`, the accessor trait of `delegate_by = enum(..)`. |
+/// | `#[wire(delegate = DelegateTrait)]`     | `DelegateTrait<Self>` with the field type as `Target`, for custom `delegate_by` traits. Use `target = Type` for another target. |
+///
+/// Generic arguments of the trait are passed on to the generated accessor traits, i.e. `#[wire(Arc = Mailer<Self>)]` when `Mailer` has an impl trait.
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(delegate_by = ref)]
+/// pub trait ReadConfig {
+///     fn read_config(&self) -> &str;
+/// }
+///
+/// #[entrait(leaf_accessor)]
+/// pub trait System {
+///     fn current_time(&self) -> u128;
+/// }
+///
+/// pub struct Config(String);
+///
+/// impl ReadConfig for Config {
+///     fn read_config(&self) -> &str {
+///         &self.0
+///     }
+/// }
+///
+/// pub struct Clock;
+///
+/// impl System for Clock {
+///     fn current_time(&self) -> u128 {
+///         42
+///     }
+/// }
+///
+/// #[derive(Wire)]
+/// struct App {
+///     #[wire(ref = dyn ReadConfig, deref)]
+///     config: Box<dyn ReadConfig + Sync>,
+///     #[wire(leaf = System)]
+///     clock: Clock,
+/// }
+///
+/// let app = Impl::new(App {
+///     config: Box::new(Config("config".to_string())),
+///     clock: Clock,
+/// });
+/// assert_eq!("config", app.read_config());
+/// assert_eq!(42, app.current_time());
+/// ```
+pub use entrait_macros::Wire;
+
+/// Asserts at compile time that [`Impl<App>`](Impl) implements each of the listed entrypoint traits.
+///
+/// Every entrypoint is checked separately, so a missing dependency is reported once per entrypoint needing it,
+/// at the root of its chain of unsatisfied bounds.
+/// On Rust 1.78 or later, the error names the trait or accessor trait that the application type has to implement.
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+/// pub trait Repository {
+///     fn fetch(&self) -> i32;
+/// }
+///
+/// #[entrait(pub FetchTwice)]
+/// fn fetch_twice(deps: &impl Repository) -> i32 {
+///     deps.fetch() * 2
+/// }
+///
+/// pub struct PgRepository;
+///
+/// #[entrait]
+/// impl RepositoryImpl for PgRepository {
+///     fn fetch<D>(_: &D) -> i32 {
+///         21
+///     }
+/// }
+///
+/// #[derive(Wire)]
+/// struct App {
+///     #[wire(delegate = DelegateRepository)]
+///     repository: PgRepository,
+/// }
+///
+/// entrait::assert_app!(App: FetchTwice, Repository);
+/// ```
+///
+/// An application type that does not select an implementation of `RepositoryImpl` fails to compile,
+/// with an error pointing out that it should implement `DelegateRepository<App>`:
+///
+/// ```compile_fail
+/// # use entrait::*;
+/// # #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+/// # pub trait Repository {
+/// #     fn fetch(&self) -> i32;
+/// # }
+/// # #[entrait(pub FetchTwice)]
+/// # fn fetch_twice(deps: &impl Repository) -> i32 {
+/// #     deps.fetch() * 2
+/// # }
+/// struct App;
+///
+/// entrait::assert_app!(App: FetchTwice);
+/// ```
+#[macro_export]
+macro_rules! assert_app {
+    ($app:ty: $($entrypoint:path),+ $(,)?) => {
+        $(
+            const _: fn() = || {
+                fn entrypoint<T: ?Sized + $entrypoint>() {}
+                entrypoint::<$crate::Impl<$app>>();
+            };
+        )+
+    };
+}
+
+/// An application together with request-scoped context, like the current user, a trace ID or a database transaction.
+///
+/// Created by [`Scope::scope`], which clones the application, so it should be cheap to clone, i.e. with its state behind an `Arc`.
+/// Traits implemented for [`Impl<App>`](Impl) keep working on `Impl<Scoped<App, Ctx>>`:
+/// the traits generated by entrait forward through `Scoped` to the application,
+/// except for leaf traits marked with `#[entrait(context)]`, which are taken from the context.
+///
+/// ```rust
+/// # use entrait::*;
+/// #[entrait]
+/// pub trait System {
+///     fn current_time(&self) -> u128;
+/// }
+///
+/// #[entrait(context)]
+/// pub trait CurrentUser {
+///     fn current_user(&self) -> &str;
+/// }
+///
+/// #[entrait(Greet)]
+/// fn greet(deps: &(impl System + CurrentUser)) -> String {
+///     format!("hello {} at {}", deps.current_user(), deps.current_time())
+/// }
+///
+/// #[derive(Clone)]
+/// struct App;
+///
+/// impl System for App {
+///     fn current_time(&self) -> u128 {
+///         42
+///     }
+/// }
+///
+/// struct Request {
+///     user: String,
+/// }
+///
+/// impl CurrentUser for Request {
+///     fn current_user(&self) -> &str {
+///         &self.user
+///     }
+/// }
+///
+/// let app = Impl::new(App);
+/// let request = app.scope(Request { user: "ferris".to_string() });
+/// assert_eq!("hello ferris at 42", request.greet());
+/// assert_eq!(42, request.current_time());
+/// ```
+///
+/// `Impl<Scoped<App, Ctx>>` implements a generated trait by forwarding to [`Impl<App>`](Impl) through [`app_impl`](Scoped::app_impl),
+/// so that an implementation handed out by the application as e.g. `dyn TraitImpl<App>` is called with the `Impl<App>` it expects.
+/// The traits of `delegate_by = DelegateTrait` and the accessor traits of `delegate_by = enum(..)` are instead implemented by `Scoped` itself,
+/// which lets the `Select{Trait}` trait of the selector pick the implementation per scope.
+/// `Scoped` also forwards [`Deref`](::core::ops::Deref) to the application, for `delegate_by = Deref`.
+/// Traits with type parameters are not forwarded, as other crates could implement them for `Scoped`.
+pub struct Scoped<T, C> {
+    app: Impl<T>,
+    ctx: C,
+}
+
+impl<T, C> Scoped<T, C> {
+    pub fn new(app: T, ctx: C) -> Self {
+        Self {
+            app: Impl::new(app),
+            ctx,
+        }
+    }
+
+    /// The application
+    pub fn app(&self) -> &T {
+        &self.app
+    }
+
+    /// The application as [`Impl<T>`](Impl), which the scoped value forwards to
+    pub fn app_impl(&self) -> &Impl<T> {
+        &self.app
+    }
+
+    pub fn app_impl_mut(&mut self) -> &mut Impl<T> {
+        &mut self.app
+    }
+
+    /// The request-scoped context
+    pub fn ctx(&self) -> &C {
+        &self.ctx
+    }
+
+    pub fn ctx_mut(&mut self) -> &mut C {
+        &mut self.ctx
+    }
+
+    pub fn into_parts(self) -> (T, C) {
+        (self.app.into_inner(), self.ctx)
+    }
+}
+
+impl<T: ::core::ops::Deref, C> ::core::ops::Deref for Scoped<T, C> {
+    type Target = T::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.app
+    }
+}
+
+/// Scoping of [`Impl<T>`](Impl) with request-scoped context, see [Scoped].
+pub trait Scope<T> {
+    /// A clone of the application, scoped with `ctx`
+    fn scope<C>(&self, ctx: C) -> Impl<Scoped<T, C>>;
+}
+
+impl<T: Clone> Scope<T> for Impl<T> {
+    fn scope<C>(&self, ctx: C) -> Impl<Scoped<T, C>> {
+        Impl::new(Scoped::new(T::clone(self), ctx))
+    }
+}
+
 /// Re-exported from the [implementation] crate.
 pub use ::implementation::Impl;
 
+/// Allocation types used by generated code, so that it also works in `no_std` crates using `alloc`
+#[doc(hidden)]
+pub mod __alloc {
+    extern crate alloc;
+
+    pub use alloc::boxed::Box;
+    #[cfg(target_has_atomic = "ptr")]
+    pub use alloc::sync::Arc;
+}
+
 /// Optional mock re-exports for macros
 #[cfg(feature = "unimock")]
 #[doc(hidden)]
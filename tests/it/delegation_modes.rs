@@ -80,3 +80,773 @@ mod borrow_dyn_with_async_trait {
         app.foo().await;
     }
 }
+
+mod deref_sync {
+    use super::*;
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(Foo)]
+    fn foo(deps: &impl Mailer) -> String {
+        deps.send("foo")
+    }
+
+    #[entrait(delegate_by = Deref)]
+    trait Mailer {
+        fn send(&self, msg: &str) -> String;
+    }
+
+    struct Smtp;
+
+    impl Mailer for Smtp {
+        fn send(&self, msg: &str) -> String {
+            format!("smtp:{msg}")
+        }
+    }
+
+    struct App {
+        mailer: Smtp,
+    }
+
+    impl std::ops::Deref for App {
+        type Target = Smtp;
+
+        fn deref(&self) -> &Smtp {
+            &self.mailer
+        }
+    }
+
+    #[test]
+    fn test_deref_to_concrete_and_dyn() {
+        assert_eq!("smtp:foo", Impl::new(App { mailer: Smtp }).foo());
+
+        let app: Impl<Arc<dyn Mailer + Send + Sync>> = Impl::new(Arc::new(Smtp));
+        assert_is_sync(&app);
+        assert_eq!("smtp:foo", app.foo());
+    }
+}
+
+mod deref_async {
+    use super::*;
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(Foo)]
+    async fn foo(deps: &impl Mailer) -> String {
+        deps.send("foo").await
+    }
+
+    #[entrait(MailerImpl, delegate_by = Deref)]
+    pub trait Mailer {
+        async fn send(&self, msg: &str) -> String;
+    }
+
+    pub struct Smtp;
+
+    #[entrait(ref)]
+    impl MailerImpl for Smtp {
+        async fn send<D>(_: &D, msg: &str) -> String {
+            format!("smtp:{msg}")
+        }
+    }
+
+    struct App(Arc<Smtp>);
+
+    impl std::ops::Deref for App {
+        type Target = Smtp;
+
+        fn deref(&self) -> &Smtp {
+            &self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_deref() {
+        let app = Impl::new(App(Arc::new(Smtp)));
+
+        assert_is_send(&app.foo());
+        assert_eq!("smtp:foo", app.foo().await);
+    }
+}
+
+mod arc_sync {
+    use super::*;
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(Foo)]
+    fn foo(deps: &impl Mailer) -> String {
+        deps.send("foo")
+    }
+
+    #[entrait(delegate_by = Arc)]
+    pub trait Mailer {
+        fn send(&self, msg: &str) -> String;
+    }
+
+    struct Smtp;
+
+    impl Mailer for Smtp {
+        fn send(&self, msg: &str) -> String {
+            format!("smtp:{msg}")
+        }
+    }
+
+    struct App {
+        mailer: Arc<dyn Mailer + Send + Sync>,
+    }
+
+    impl MailerArc for App {
+        fn mailer_arc(&self) -> &Arc<dyn Mailer + Send + Sync> {
+            &self.mailer
+        }
+    }
+
+    #[test]
+    fn test_arc_accessor() {
+        let app = Impl::new(App {
+            mailer: Arc::new(Smtp),
+        });
+
+        assert_is_send(&app);
+        assert_is_sync(&app);
+        assert_eq!("smtp:foo", app.foo());
+
+        let mailer = app.mailer_arc().clone();
+        std::thread::spawn(move || assert_eq!("smtp:bar", mailer.send("bar")))
+            .join()
+            .unwrap();
+    }
+}
+
+mod arc_async_with_impl_trait {
+    use super::*;
+    use async_trait::*;
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(Foo)]
+    async fn foo(deps: &impl Mailer) -> String {
+        deps.send("foo").await
+    }
+
+    #[entrait(MailerImpl, delegate_by = Arc)]
+    #[async_trait]
+    pub trait Mailer {
+        async fn send(&self, msg: &str) -> String;
+    }
+
+    pub struct Smtp;
+
+    #[entrait(ref)]
+    #[async_trait]
+    impl MailerImpl for Smtp {
+        async fn send<D>(_: &D, msg: &str) -> String {
+            format!("smtp:{msg}")
+        }
+    }
+
+    struct App {
+        mailer: Arc<dyn MailerImpl<Self> + Send + Sync>,
+    }
+
+    impl MailerArc<Self> for App {
+        fn mailer_arc(&self) -> &Arc<dyn MailerImpl<Self> + Send + Sync> {
+            &self.mailer
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_arc() {
+        let app = Impl::new(App {
+            mailer: Arc::new(Smtp),
+        });
+
+        assert_is_send(&app.foo());
+        assert_eq!("smtp:foo", app.foo().await);
+    }
+}
+
+mod boxed_fn {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(pub SendEmail, delegate_by = Box)]
+    fn send_email(deps: &impl std::any::Any, to: &str) -> String {
+        format!("smtp:{to}")
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct App(Box<dyn SendEmailImpl<Self> + Send + Sync>);
+
+    impl SendEmailBox<Self> for App {
+        fn send_email_box(&self) -> &Box<dyn SendEmailImpl<Self> + Send + Sync> {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn test_box_accessor() {
+        let app = Impl::new(App(Box::new(Smtp)));
+
+        assert_is_sync(&app);
+        assert_eq!("smtp:a", app.send_email("a"));
+    }
+}
+
+mod enum_dispatch {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(Foo)]
+    fn foo(deps: &impl Repo) -> String {
+        deps.fetch(1)
+    }
+
+    #[entrait(RepoImpl, delegate_by = enum(PgRepo, mem::MemRepo))]
+    pub trait Repo {
+        fn fetch(&self, id: u32) -> String;
+
+        fn count(&self) -> usize {
+            0
+        }
+    }
+
+    pub struct PgRepo {
+        url: String,
+    }
+
+    impl<T> RepoImpl<T> for PgRepo {
+        fn fetch(&self, _: &Impl<T>, id: u32) -> String {
+            format!("{}/{id}", self.url)
+        }
+    }
+
+    mod mem {
+        use entrait::*;
+
+        pub struct MemRepo;
+
+        #[entrait(ref)]
+        impl super::RepoImpl for MemRepo {
+            fn fetch<D>(_: &D, id: u32) -> String {
+                format!("mem/{id}")
+            }
+
+            fn count<D>(_: &D) -> usize {
+                1
+            }
+        }
+    }
+
+    struct App {
+        repo: RepoChoice,
+    }
+
+    impl AsRepoChoice for App {
+        fn repo_choice(&self) -> &RepoChoice {
+            &self.repo
+        }
+    }
+
+    fn app(config: &str) -> Impl<App> {
+        Impl::new(App {
+            repo: match config {
+                "pg" => RepoChoice::PgRepo(PgRepo {
+                    url: "pg".to_string(),
+                }),
+                _ => RepoChoice::MemRepo(mem::MemRepo),
+            },
+        })
+    }
+
+    #[test]
+    fn test_select_at_runtime() {
+        assert_eq!("pg/1", app("pg").foo());
+        assert_eq!(0, app("pg").count());
+        assert_eq!("mem/1", app("mem").foo());
+        assert_eq!(1, app("mem").count());
+    }
+}
+
+mod enum_dispatch_named_variants {
+    use entrait::*;
+
+    #[entrait(RepoImpl, delegate_by = enum(Pg = pg::Repository, Mem = mem::Repository))]
+    pub trait Repo {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    mod pg {
+        pub struct Repository;
+
+        #[entrait::entrait(ref)]
+        impl super::RepoImpl for Repository {
+            fn fetch<D>(_: &D, id: u32) -> String {
+                format!("pg/{id}")
+            }
+        }
+    }
+
+    mod mem {
+        pub struct Repository;
+
+        #[entrait::entrait(ref)]
+        impl super::RepoImpl for Repository {
+            fn fetch<D>(_: &D, id: u32) -> String {
+                format!("mem/{id}")
+            }
+        }
+    }
+
+    struct App(RepoChoice);
+
+    impl AsRepoChoice for App {
+        fn repo_choice(&self) -> &RepoChoice {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn test_same_type_names() {
+        assert_eq!(
+            "pg/1",
+            Impl::new(App(RepoChoice::Pg(pg::Repository))).fetch(1)
+        );
+        assert_eq!(
+            "mem/2",
+            Impl::new(App(RepoChoice::Mem(mem::Repository))).fetch(2)
+        );
+    }
+}
+
+mod enum_dispatch_async {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(Foo)]
+    async fn foo(deps: &impl Repo) -> String {
+        deps.fetch(1).await
+    }
+
+    #[entrait(RepoImpl, delegate_by = enum(PgRepo, MemRepo))]
+    pub trait Repo {
+        async fn fetch(&self, id: u32) -> String;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait(ref)]
+    impl RepoImpl for PgRepo {
+        async fn fetch<D>(_: &D, id: u32) -> String {
+            format!("pg/{id}")
+        }
+    }
+
+    pub struct MemRepo;
+
+    #[entrait(ref)]
+    impl RepoImpl for MemRepo {
+        async fn fetch<D>(_: &D, id: u32) -> String {
+            format!("mem/{id}")
+        }
+    }
+
+    struct App(RepoChoice);
+
+    impl AsRepoChoice for App {
+        fn repo_choice(&self) -> &RepoChoice {
+            &self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_enum_dispatch() {
+        let app = Impl::new(App(RepoChoice::PgRepo(PgRepo)));
+
+        assert_is_send(&app.foo());
+        assert_eq!("pg/1", app.foo().await);
+        assert_eq!(
+            "mem/1",
+            Impl::new(App(RepoChoice::MemRepo(MemRepo))).foo().await
+        );
+    }
+}
+
+mod dyn_async_without_async_trait {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(Foo)]
+    async fn foo(deps: &(impl Bar + Repo)) -> String {
+        format!("{}:{}", deps.bar().await, deps.fetch("a").await)
+    }
+
+    #[entrait(delegate_by = ref)]
+    trait Bar: Sync + 'static {
+        async fn bar(&self) -> u32;
+    }
+
+    #[entrait(RepoImpl, delegate_by = ref)]
+    pub trait Repo {
+        async fn fetch(&self, id: &str) -> String;
+
+        fn count(&self) -> usize;
+    }
+
+    struct Baz;
+
+    impl Bar for Baz {
+        async fn bar(&self) -> u32 {
+            42
+        }
+    }
+
+    pub struct PgRepo;
+
+    #[entrait(ref)]
+    impl RepoImpl for PgRepo {
+        async fn fetch<D>(_: &D, id: &str) -> String {
+            format!("pg/{id}")
+        }
+
+        fn count<D>(_: &D) -> usize {
+            1
+        }
+    }
+
+    struct App {
+        bar: Baz,
+        repo: Box<dyn DynRepoImpl<Self> + Send + Sync>,
+    }
+
+    impl AsRef<dyn DynBar> for App {
+        fn as_ref(&self) -> &dyn DynBar {
+            &self.bar
+        }
+    }
+
+    impl AsRef<dyn DynRepoImpl<Self> + Sync> for App {
+        fn as_ref(&self) -> &(dyn DynRepoImpl<Self> + Sync) {
+            self.repo.as_ref()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dyn_async() {
+        let app = Impl::new(App {
+            bar: Baz,
+            repo: Box::new(PgRepo),
+        });
+
+        assert_is_send(&app.foo());
+        assert_eq!("42:pg/a", app.foo().await);
+        assert_eq!(1, app.count());
+    }
+}
+
+mod arc_async_without_async_trait {
+    use super::*;
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(pub SendEmail, delegate_by = Arc)]
+    async fn send_email(deps: &impl std::any::Any, to: &str) -> String {
+        format!("smtp:{to}")
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct App(Arc<dyn DynSendEmailImpl<Self> + Send + Sync>);
+
+    impl SendEmailArc<Self> for App {
+        fn send_email_arc(&self) -> &Arc<dyn DynSendEmailImpl<Self> + Send + Sync> {
+            &self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_arc() {
+        let app = Impl::new(App(Arc::new(Smtp)));
+
+        assert_is_send(&app.send_email("a"));
+        assert_eq!("smtp:a", app.send_email("a").await);
+    }
+}
+
+mod swap_sync {
+    use super::*;
+    use entrait::*;
+    use std::sync::{Arc, RwLock};
+
+    #[entrait(Foo)]
+    fn foo(deps: &impl RateLimit) -> u32 {
+        deps.limit()
+    }
+
+    #[entrait(RateLimitImpl, delegate_by = Swap)]
+    pub trait RateLimit {
+        fn limit(&self) -> u32;
+
+        // Not borrowed from the implementation, so it can outlive a swap
+        fn name(&self) -> &'static str;
+    }
+
+    pub struct Strict;
+    pub struct Lenient;
+
+    #[entrait(ref)]
+    impl RateLimitImpl for Strict {
+        fn limit<D>(_: &D) -> u32 {
+            1
+        }
+
+        fn name<D>(_: &D) -> &'static str {
+            "strict"
+        }
+    }
+
+    #[entrait(ref)]
+    impl RateLimitImpl for Lenient {
+        fn limit<D>(_: &D) -> u32 {
+            100
+        }
+
+        fn name<D>(_: &D) -> &'static str {
+            "lenient"
+        }
+    }
+
+    struct App {
+        rate_limit: RwLock<Arc<dyn RateLimitImpl<Self> + Send + Sync>>,
+    }
+
+    impl RateLimitSwap<Self> for App {
+        fn rate_limit_swap(&self) -> &RwLock<Arc<dyn RateLimitImpl<Self> + Send + Sync>> {
+            &self.rate_limit
+        }
+    }
+
+    #[test]
+    fn test_swap_while_shared() {
+        let app = Arc::new(Impl::new(App {
+            rate_limit: RwLock::new(Arc::new(Strict)),
+        }));
+
+        assert_is_sync(&app);
+        assert_eq!(1, app.foo());
+        let name = app.name();
+
+        let shared = app.clone();
+        let previous = std::thread::spawn(move || shared.swap_rate_limit(Arc::new(Lenient)))
+            .join()
+            .unwrap();
+
+        assert_eq!(100, app.foo());
+        assert_eq!(100, app.limit());
+        assert_eq!(1, previous.limit(&app));
+        assert_eq!(("strict", "lenient"), (name, app.name()));
+    }
+}
+
+mod swap_async_fn {
+    use super::*;
+    use entrait::*;
+    use std::sync::{Arc, RwLock};
+
+    #[entrait(pub SendEmail, delegate_by = Swap)]
+    async fn send_email(deps: &impl std::any::Any, to: &str) -> String {
+        format!("smtp:{to}")
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct Mock;
+
+    impl<T: Sync> SendEmailImpl<T> for Mock {
+        async fn send_email(&self, _: &Impl<T>, to: &str) -> String {
+            format!("mock:{to}")
+        }
+    }
+
+    #[derive(Wire)]
+    pub struct App(
+        #[wire(Swap = SendEmail<Self>)] RwLock<Arc<dyn DynSendEmailImpl<Self> + Send + Sync>>,
+    );
+
+    #[tokio::test]
+    async fn test_async_swap() {
+        let app = Impl::new(App(RwLock::new(Arc::new(Smtp))));
+
+        assert_is_send(&app.send_email("a"));
+        assert_eq!("smtp:a", app.send_email("a").await);
+
+        app.swap_send_email(Arc::new(Mock));
+        assert_eq!("mock:b", app.send_email("b").await);
+    }
+}
+
+mod select_sync {
+    use entrait::*;
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository(PgRepo, MemRepo))]
+    pub trait Repository {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait]
+    impl RepositoryImpl for PgRepo {
+        fn fetch<D>(_: &D, id: u32) -> String {
+            format!("pg/{id}")
+        }
+    }
+
+    pub struct MemRepo;
+
+    #[entrait]
+    impl RepositoryImpl for MemRepo {
+        fn fetch<D>(_: &D, id: u32) -> String {
+            format!("mem/{id}")
+        }
+    }
+
+    struct App {
+        tenant: &'static str,
+    }
+
+    impl DelegateRepository<Self> for App {
+        type Target = RepositorySelector;
+    }
+
+    impl SelectRepository for App {
+        fn select_repository(deps: &Impl<Self>) -> RepositorySelection {
+            match deps.tenant {
+                "acme" => RepositorySelection::PgRepo,
+                _ => RepositorySelection::MemRepo,
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_per_tenant() {
+        assert_eq!("pg/1", Impl::new(App { tenant: "acme" }).fetch(1));
+        assert_eq!("mem/1", Impl::new(App { tenant: "demo" }).fetch(1));
+    }
+}
+
+mod select_named_variants {
+    use entrait::*;
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository(Pg = pg::Repo, Mem = mem::Repo))]
+    pub trait Repository {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    mod pg {
+        pub struct Repo;
+
+        #[entrait::entrait]
+        impl super::RepositoryImpl for Repo {
+            fn fetch<D>(_: &D, id: u32) -> String {
+                format!("pg/{id}")
+            }
+        }
+    }
+
+    mod mem {
+        pub struct Repo;
+
+        #[entrait::entrait]
+        impl super::RepositoryImpl for Repo {
+            fn fetch<D>(_: &D, id: u32) -> String {
+                format!("mem/{id}")
+            }
+        }
+    }
+
+    struct App {
+        in_memory: bool,
+    }
+
+    impl DelegateRepository<Self> for App {
+        type Target = RepositorySelector;
+    }
+
+    impl SelectRepository for App {
+        fn select_repository(deps: &Impl<Self>) -> RepositorySelection {
+            if deps.in_memory {
+                RepositorySelection::Mem
+            } else {
+                RepositorySelection::Pg
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_type_names() {
+        assert_eq!("pg/1", Impl::new(App { in_memory: false }).fetch(1));
+        assert_eq!("mem/1", Impl::new(App { in_memory: true }).fetch(1));
+    }
+}
+
+mod select_async_fn {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(pub SendEmail, delegate_by = DelegateSendEmail(Smtp, Fake))]
+    async fn send_email(deps: &impl std::any::Any, to: &str) -> String {
+        format!("smtp:{to}")
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct Fake;
+
+    #[entrait]
+    impl SendEmailImpl for Fake {
+        async fn send_email<D>(_: &D, to: &str) -> String {
+            format!("fake:{to}")
+        }
+    }
+
+    #[derive(Wire)]
+    pub struct App {
+        #[wire(delegate = DelegateSendEmail, target = SendEmailSelector)]
+        dry_run: bool,
+    }
+
+    impl SelectSendEmail for App {
+        fn select_send_email(deps: &Impl<Self>) -> SendEmailSelection {
+            if deps.dry_run {
+                SendEmailSelection::Fake
+            } else {
+                SendEmailSelection::Smtp
+            }
+        }
+    }
+
+    entrait::assert_app!(App: SendEmail);
+
+    #[tokio::test]
+    async fn test_async_select() {
+        let app = Impl::new(App { dry_run: false });
+
+        assert_is_send(&app.send_email("a"));
+        assert_eq!("smtp:a", app.send_email("a").await);
+        assert_eq!(
+            "fake:b",
+            Impl::new(App { dry_run: true }).send_email("b").await
+        );
+    }
+}
@@ -195,3 +195,799 @@ mod issue_29 {
         assert_eq!("foo", app.foo("foo"));
     }
 }
+
+mod concrete_deps_in_impl_block {
+    use entrait::*;
+
+    pub struct Pool {
+        value: i32,
+    }
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+    pub trait Repository {
+        fn fetch(&self) -> i32;
+        fn fetch_generic(&self) -> i32;
+    }
+
+    pub struct PoolRepository;
+
+    #[entrait]
+    impl RepositoryImpl for PoolRepository {
+        fn fetch(pool: &Pool) -> i32 {
+            pool.value
+        }
+
+        fn fetch_generic(deps: &impl super::Baz) -> i32 {
+            deps.baz()
+        }
+    }
+
+    struct App {
+        pool: Pool,
+    }
+
+    impl AsRef<Pool> for App {
+        fn as_ref(&self) -> &Pool {
+            &self.pool
+        }
+    }
+
+    impl DelegateRepository<Self> for App {
+        type Target = PoolRepository;
+    }
+
+    #[test]
+    fn test_static() {
+        let app = Impl::new(App {
+            pool: Pool { value: 1337 },
+        });
+
+        assert_eq!(1337, app.fetch());
+        assert_eq!(42, app.fetch_generic());
+    }
+
+    mod dynamic {
+        use super::Pool;
+        use entrait::*;
+
+        #[entrait(RepositoryImpl, delegate_by = ref)]
+        pub trait Repository {
+            fn fetch(&self) -> i32;
+        }
+
+        pub struct PoolRepository;
+
+        #[entrait(ref)]
+        impl RepositoryImpl for PoolRepository {
+            fn fetch(pool: &Pool) -> i32 {
+                pool.value
+            }
+        }
+
+        struct App {
+            pool: Pool,
+            repository: PoolRepository,
+        }
+
+        impl AsRef<Pool> for App {
+            fn as_ref(&self) -> &Pool {
+                &self.pool
+            }
+        }
+
+        impl AsRef<dyn RepositoryImpl<Self>> for App {
+            fn as_ref(&self) -> &dyn RepositoryImpl<Self> {
+                &self.repository
+            }
+        }
+
+        #[test]
+        fn test_dynamic() {
+            let app = Impl::new(App {
+                pool: Pool { value: 42 },
+                repository: PoolRepository,
+            });
+
+            assert_eq!(42, app.fetch());
+        }
+    }
+}
+
+mod generic_impl_block {
+    use entrait::*;
+    use std::marker::PhantomData;
+
+    pub trait Codec: 'static {
+        fn decode(input: &str) -> i32;
+    }
+
+    pub struct Decimal;
+
+    impl Codec for Decimal {
+        fn decode(input: &str) -> i32 {
+            input.parse().unwrap()
+        }
+    }
+
+    pub struct Hex;
+
+    impl Codec for Hex {
+        fn decode(input: &str) -> i32 {
+            i32::from_str_radix(input, 16).unwrap()
+        }
+    }
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+    pub trait Repository {
+        fn fetch(&self, input: &str) -> i32;
+    }
+
+    pub struct CodecRepository<C>(PhantomData<C>);
+
+    #[entrait]
+    impl<C: Codec> RepositoryImpl for CodecRepository<C> {
+        fn fetch<D>(deps: &D, input: &str) -> i32 {
+            C::decode(input)
+        }
+    }
+
+    impl DelegateRepository<Self> for () {
+        type Target = CodecRepository<Hex>;
+    }
+
+    impl DelegateRepository<Self> for bool {
+        type Target = CodecRepository<Decimal>;
+    }
+
+    #[test]
+    fn test_static() {
+        assert_eq!(42, Impl::new(()).fetch("2a"));
+        assert_eq!(42, Impl::new(true).fetch("42"));
+    }
+
+    mod dynamic {
+        use super::{Codec, Decimal};
+        use entrait::*;
+        use std::marker::PhantomData;
+
+        #[entrait(RepositoryImpl, delegate_by = ref)]
+        pub trait Repository {
+            fn fetch(&self, input: &str) -> i32;
+        }
+
+        pub struct CodecRepository<C>(PhantomData<C>);
+
+        #[entrait(ref)]
+        impl<C> RepositoryImpl for CodecRepository<C>
+        where
+            C: Codec + Sync,
+        {
+            fn fetch(deps: &impl super::super::Baz, input: &str) -> i32 {
+                C::decode(input) + deps.baz()
+            }
+        }
+
+        struct App(CodecRepository<Decimal>);
+
+        impl AsRef<dyn RepositoryImpl<Self>> for App {
+            fn as_ref(&self) -> &dyn RepositoryImpl<Self> {
+                &self.0
+            }
+        }
+
+        #[test]
+        fn test_dynamic() {
+            let app = Impl::new(App(CodecRepository(PhantomData)));
+            assert_eq!(1379, app.fetch("1337"));
+        }
+    }
+}
+
+mod arc_static {
+    use std::sync::Arc;
+
+    use entrait::*;
+
+    #[entrait(ScheduleImpl, delegate_by = DelegateSchedule)]
+    pub trait Schedule {
+        fn schedule(self: Arc<Self>) -> i32;
+    }
+
+    pub struct Scheduler;
+
+    #[entrait]
+    impl ScheduleImpl for Scheduler {
+        fn schedule(deps: Arc<impl super::Baz + Send + Sync + 'static>) -> i32 {
+            std::thread::spawn(move || deps.baz()).join().unwrap()
+        }
+    }
+
+    impl DelegateSchedule<Self> for () {
+        type Target = Scheduler;
+    }
+
+    #[test]
+    fn test() {
+        let app = Arc::new(Impl::new(()));
+        assert_eq!(42, app.schedule());
+    }
+}
+
+mod assoc_items_static {
+    use entrait::*;
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+    pub trait Repository {
+        #[entrait(mock = ())]
+        type Error: std::fmt::Debug;
+        #[entrait(mock = 10)]
+        const MAX_BATCH: usize;
+
+        fn fetch(&self) -> Result<i32, Self::Error>;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait]
+    impl RepositoryImpl for PgRepo {
+        type Error = std::fmt::Error;
+        const MAX_BATCH: usize = 100;
+
+        fn fetch<D>(_: &D) -> Result<i32, std::fmt::Error> {
+            Ok(42)
+        }
+    }
+
+    impl DelegateRepository<Self> for () {
+        type Target = PgRepo;
+    }
+
+    #[test]
+    fn test() {
+        let app = Impl::new(());
+        assert_eq!(Ok(42), app.fetch());
+        assert_eq!(100, <Impl<()> as Repository>::MAX_BATCH);
+    }
+}
+
+mod assoc_items_dyn {
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(RepositoryImpl, delegate_by = ref)]
+    pub trait Repository {
+        #[entrait(dyn = std::fmt::Error)]
+        #[entrait(mock = ())]
+        type Error: std::fmt::Debug;
+
+        fn fetch(&self) -> Result<i32, Self::Error>;
+    }
+
+    #[entrait(MailerImpl, delegate_by = Arc)]
+    pub trait Mailer {
+        #[entrait(dyn = String)]
+        #[entrait(mock = String)]
+        type Receipt;
+
+        async fn send(&self, to: &str) -> Self::Receipt;
+    }
+
+    #[entrait(delegate_by = ref)]
+    pub trait ReadConfig {
+        #[entrait(dyn = u16)]
+        #[entrait(mock = u16)]
+        type Port;
+
+        fn port(&self) -> Self::Port;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait(ref)]
+    impl RepositoryImpl for PgRepo {
+        type Error = std::fmt::Error;
+
+        fn fetch<D>(_: &D) -> Result<i32, std::fmt::Error> {
+            Ok(42)
+        }
+    }
+
+    pub struct Smtp;
+
+    #[entrait(ref)]
+    impl MailerImpl for Smtp {
+        type Receipt = String;
+
+        async fn send<D>(_: &D, to: &str) -> String {
+            format!("sent to {to}")
+        }
+    }
+
+    pub struct Config;
+
+    impl ReadConfig for Config {
+        type Port = u16;
+
+        fn port(&self) -> u16 {
+            80
+        }
+    }
+
+    struct App {
+        mailer: Arc<dyn DynMailerImpl<Self, Receipt = String> + Send + Sync>,
+    }
+
+    impl AsRef<dyn RepositoryImpl<Self, Error = std::fmt::Error>> for App {
+        fn as_ref(&self) -> &(dyn RepositoryImpl<Self, Error = std::fmt::Error> + 'static) {
+            &PgRepo
+        }
+    }
+
+    impl MailerArc<Self> for App {
+        fn mailer_arc(&self) -> &Arc<dyn DynMailerImpl<Self, Receipt = String> + Send + Sync> {
+            &self.mailer
+        }
+    }
+
+    impl AsRef<dyn ReadConfig<Port = u16>> for App {
+        fn as_ref(&self) -> &(dyn ReadConfig<Port = u16> + 'static) {
+            &Config
+        }
+    }
+
+    #[tokio::test]
+    async fn test() {
+        let app = Impl::new(App {
+            mailer: Arc::new(Smtp),
+        });
+        assert_eq!(Ok(42), app.fetch());
+        assert_eq!("sent to a", app.send("a").await);
+        assert_eq!(80, app.port());
+    }
+}
+
+mod default_methods_static {
+    use entrait::*;
+
+    #[entrait(GreeterImpl, delegate_by = DelegateGreeter)]
+    pub trait Greeter {
+        fn name(&self) -> String;
+
+        fn greet(&self, punctuation: &str) -> String {
+            format!("Hello, {}{punctuation}", self.name())
+        }
+    }
+
+    pub struct English;
+
+    #[entrait]
+    impl GreeterImpl for English {
+        fn name<D>(_: &D) -> String {
+            "world".to_string()
+        }
+    }
+
+    pub struct Terse;
+
+    #[entrait]
+    impl GreeterImpl for Terse {
+        fn name<D>(_: &D) -> String {
+            "world".to_string()
+        }
+
+        fn greet<D>(_: &D, _punctuation: &str) -> String {
+            "Hi".to_string()
+        }
+    }
+
+    impl DelegateGreeter<Self> for () {
+        type Target = English;
+    }
+
+    impl DelegateGreeter<Self> for bool {
+        type Target = Terse;
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!("Hello, world!", Impl::new(()).greet("!"));
+        assert_eq!("Hi", Impl::new(true).greet("!"));
+    }
+}
+
+mod default_methods_dyn {
+    use entrait::*;
+
+    #[entrait(GreeterImpl, delegate_by = ref)]
+    pub trait Greeter {
+        fn name(&self) -> String;
+
+        fn greet(&self) -> String {
+            format!("Hello, {}", self.name())
+        }
+    }
+
+    pub struct English;
+
+    #[entrait(ref)]
+    impl GreeterImpl for English {
+        fn name<D>(_: &D) -> String {
+            "world".to_string()
+        }
+    }
+
+    struct App(Box<dyn GreeterImpl<Self> + Sync>);
+
+    impl AsRef<dyn GreeterImpl<Self>> for App {
+        fn as_ref(&self) -> &dyn GreeterImpl<Self> {
+            self.0.as_ref()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!("Hello, world", Impl::new(App(Box::new(English))).greet());
+    }
+}
+
+mod generic_trait_static {
+    use entrait::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct User(String);
+
+    #[entrait(StoreImpl, delegate_by = DelegateStore)]
+    pub trait Store<K, V> {
+        fn get(&self, key: K) -> Option<V>;
+    }
+
+    pub struct UserStore;
+
+    #[entrait]
+    impl StoreImpl<String, User> for UserStore {
+        fn get<D>(_: &D, key: String) -> Option<User> {
+            Some(User(key))
+        }
+    }
+
+    pub struct GuestStore;
+
+    #[entrait]
+    impl StoreImpl<String, User> for GuestStore {
+        fn get<D>(_: &D, _key: String) -> Option<User> {
+            Some(User("guest".to_string()))
+        }
+    }
+
+    pub struct EchoStore;
+
+    #[entrait]
+    impl<K> StoreImpl<K, K> for EchoStore {
+        fn get<D>(_: &D, key: K) -> Option<K> {
+            Some(key)
+        }
+    }
+
+    impl DelegateStore<Self, String, User> for () {
+        type Target = UserStore;
+    }
+
+    impl DelegateStore<Self, u32, u32> for () {
+        type Target = EchoStore;
+    }
+
+    impl DelegateStore<Self, String, User> for bool {
+        type Target = GuestStore;
+    }
+
+    #[test]
+    fn test_selected_per_app() {
+        assert_eq!(
+            Some(User("alice".to_string())),
+            Impl::new(()).get("alice".to_string())
+        );
+        assert_eq!(
+            Some(User("guest".to_string())),
+            Impl::new(true).get("alice".to_string())
+        );
+        assert_eq!(Some(42), Store::<u32, u32>::get(&Impl::new(()), 42));
+    }
+}
+
+mod generic_trait_dyn {
+    use entrait::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct User(String);
+
+    #[entrait(StoreImpl, delegate_by = ref)]
+    pub trait Store<K, V> {
+        fn get(&self, key: K) -> Option<V>;
+    }
+
+    pub struct UserStore;
+
+    #[entrait(ref)]
+    impl StoreImpl<String, User> for UserStore {
+        fn get<D>(_: &D, key: String) -> Option<User> {
+            Some(User(key))
+        }
+    }
+
+    struct App(Box<dyn StoreImpl<App, String, User> + Sync>);
+
+    impl AsRef<dyn StoreImpl<Self, String, User>> for App {
+        fn as_ref(&self) -> &dyn StoreImpl<Self, String, User> {
+            self.0.as_ref()
+        }
+    }
+
+    #[test]
+    fn test_dynamic() {
+        let app = Impl::new(App(Box::new(UserStore)));
+        assert_eq!(Some(User("bob".to_string())), app.get("bob".to_string()));
+    }
+}
+
+mod lifetime_and_const_params {
+    use entrait::*;
+
+    #[entrait(PickImpl, delegate_by = DelegatePick, unimock = false)]
+    pub trait Pick<'a, T: 'a, const N: usize> {
+        fn pick(&self, items: &'a [T; N]) -> &'a T;
+    }
+
+    pub struct Last;
+
+    #[entrait]
+    impl<'a, T: 'a, const N: usize> PickImpl<'a, T, N> for Last {
+        fn pick<D>(_: &D, items: &'a [T; N]) -> &'a T {
+            &items[N - 1]
+        }
+    }
+
+    impl<'a, T: 'a, const N: usize> DelegatePick<'a, Self, T, N> for () {
+        type Target = Last;
+    }
+
+    #[test]
+    fn test_static() {
+        assert_eq!(&3, Impl::new(()).pick(&[1, 2, 3]));
+    }
+
+    mod dynamic {
+        use entrait::*;
+
+        #[entrait(PickImpl, delegate_by = ref, unimock = false)]
+        pub trait Pick<'a, T: 'a, const N: usize> {
+            fn pick(&self, items: &'a [T; N]) -> &'a T;
+        }
+
+        pub struct First;
+
+        #[entrait(ref)]
+        impl<'a, T: 'a, const N: usize> PickImpl<'a, T, N> for First {
+            fn pick<D>(_: &D, items: &'a [T; N]) -> &'a T {
+                &items[0]
+            }
+        }
+
+        struct App(First);
+
+        impl<'a> AsRef<dyn PickImpl<'a, Self, u8, 2>> for App {
+            fn as_ref(&self) -> &(dyn PickImpl<'a, Self, u8, 2> + 'static) {
+                &self.0
+            }
+        }
+
+        #[test]
+        fn test_dynamic() {
+            assert_eq!(&1, Impl::new(App(First)).pick(&[1, 2]));
+        }
+    }
+}
+
+mod supertraits_static {
+    use entrait::*;
+
+    #[entrait(UsersImpl, delegate_by = DelegateUsers)]
+    pub trait Users {
+        fn list_users(&self) -> Vec<String>;
+    }
+
+    #[entrait(AdminImpl, delegate_by = DelegateAdmin)]
+    pub trait Admin: Users + super::Baz + Send + Sync {
+        fn promote(&self) -> String;
+    }
+
+    pub struct MyUsers;
+
+    #[entrait]
+    impl UsersImpl for MyUsers {
+        fn list_users<D>(_: &D) -> Vec<String> {
+            vec!["alice".to_string()]
+        }
+    }
+
+    pub struct MyAdmin;
+
+    #[entrait]
+    impl AdminImpl for MyAdmin {
+        fn promote(deps: &(impl Users + super::Baz)) -> String {
+            format!("{}:{}", deps.list_users()[0], deps.baz())
+        }
+    }
+
+    impl DelegateUsers<Self> for () {
+        type Target = MyUsers;
+    }
+
+    impl DelegateAdmin<Self> for () {
+        type Target = MyAdmin;
+    }
+
+    fn promote_first(admin: &impl Admin) -> String {
+        format!("{} of {}", admin.promote(), admin.list_users().len())
+    }
+
+    #[test]
+    fn test_static() {
+        assert_eq!("alice:42 of 1", promote_first(&Impl::new(())));
+    }
+}
+
+mod supertraits_dyn {
+    use entrait::*;
+
+    #[entrait(AdminImpl, delegate_by = ref)]
+    pub trait Admin: super::Baz + Send + Sync {
+        fn promote(&self) -> i32;
+    }
+
+    pub struct MyAdmin;
+
+    #[entrait(ref)]
+    impl AdminImpl for MyAdmin {
+        fn promote(deps: &impl super::Baz) -> i32 {
+            deps.baz() + 1
+        }
+    }
+
+    // `AdminImpl` inherits the `Send + Sync` supertraits
+    struct App(Box<dyn AdminImpl<App>>);
+
+    impl AsRef<dyn AdminImpl<Self>> for App {
+        fn as_ref(&self) -> &dyn AdminImpl<Self> {
+            self.0.as_ref()
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn test_dynamic() {
+        let app = Impl::new(App(Box::new(MyAdmin)));
+        assert_send_sync(&app);
+        assert_eq!(43, app.promote());
+    }
+}
+
+mod fn_delegation_static {
+    use entrait::*;
+
+    #[entrait(pub SendEmail, delegate_by = DelegateSendEmail)]
+    fn send_email(deps: &impl super::Baz, to: &str) -> String {
+        format!("{}@{to}", deps.baz())
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct Fake;
+
+    #[entrait]
+    impl SendEmailImpl for Fake {
+        fn send_email<D>(_: &D, to: &str) -> String {
+            format!("fake@{to}")
+        }
+    }
+
+    impl DelegateSendEmail<Self> for () {
+        type Target = Smtp;
+    }
+
+    impl DelegateSendEmail<Self> for bool {
+        type Target = Fake;
+    }
+
+    #[test]
+    fn test_default_and_swapped() {
+        assert_eq!("42@a", Impl::new(()).send_email("a"));
+        assert_eq!("fake@a", Impl::new(true).send_email("a"));
+    }
+}
+
+mod fn_delegation_dyn {
+    use entrait::*;
+
+    #[entrait(pub SendEmail, delegate_by = ref)]
+    fn send_email(deps: &impl super::Baz, to: &str) -> String {
+        format!("{}@{to}", deps.baz())
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    pub struct Fake;
+
+    #[entrait(ref)]
+    impl SendEmailImpl for Fake {
+        fn send_email<D>(_: &D, to: &str) -> String {
+            format!("fake@{to}")
+        }
+    }
+
+    struct App(Box<dyn SendEmailImpl<App> + Sync>);
+
+    impl AsRef<dyn SendEmailImpl<Self>> for App {
+        fn as_ref(&self) -> &dyn SendEmailImpl<Self> {
+            self.0.as_ref()
+        }
+    }
+
+    #[test]
+    fn test_default_and_swapped() {
+        assert_eq!("42@a", Impl::new(App(Box::new(Smtp))).send_email("a"));
+        assert_eq!("fake@a", Impl::new(App(Box::new(Fake))).send_email("a"));
+    }
+}
+
+mod module_delegation {
+    use entrait::*;
+
+    #[entrait(pub Accounts, delegate_by = DelegateAccounts)]
+    mod accounts {
+        pub fn balance(deps: &impl super::super::Baz, account: u32) -> i32 {
+            deps.baz() + account as i32
+        }
+
+        pub async fn owner<D>(_: &D, account: u32) -> String {
+            format!("owner of {account}")
+        }
+    }
+
+    pub struct Ledger;
+
+    impl<T> AccountsImpl<T> for Ledger {}
+
+    pub struct Frozen;
+
+    #[entrait]
+    impl AccountsImpl for Frozen {
+        fn balance<D>(_: &D, _account: u32) -> i32 {
+            0
+        }
+
+        async fn owner<D>(_: &D, _account: u32) -> String {
+            "nobody".to_string()
+        }
+    }
+
+    impl DelegateAccounts<Self> for () {
+        type Target = Ledger;
+    }
+
+    impl DelegateAccounts<Self> for bool {
+        type Target = Frozen;
+    }
+
+    #[tokio::test]
+    async fn test_default_and_swapped() {
+        assert_eq!(43, Impl::new(()).balance(1));
+        assert_eq!("owner of 1", Impl::new(()).owner(1).await);
+        assert_eq!(0, Impl::new(true).balance(1));
+        assert_eq!("nobody", Impl::new(true).owner(1).await);
+    }
+}
@@ -5,7 +5,9 @@
 mod delegation_modes;
 mod dependency_inversion;
 mod mockall;
+mod scope;
 mod simple;
+mod wire;
 
 #[cfg(feature = "unimock")]
 mod unimock;
@@ -21,6 +21,59 @@ mod basic {
     }
 }
 
+mod generic_method {
+    use entrait::*;
+
+    #[entrait(Describe, method_generics, mockall)]
+    fn describe<T: std::fmt::Debug + 'static>(_deps: &(), value: T) -> String {
+        format!("{value:?}")
+    }
+
+    #[test]
+    fn test() {
+        let mut mock = MockDescribe::new();
+        mock.expect_describe::<i32>()
+            .return_const("mocked".to_string());
+
+        assert_eq!("mocked", mock.describe(42));
+    }
+}
+
+mod const_value {
+    use entrait::*;
+
+    #[entrait(PageSize, mockall)]
+    const PAGE_SIZE: usize = 20;
+
+    #[test]
+    fn test() {
+        let mut mock = MockPageSize::new();
+        mock.expect_page_size().return_const(10_usize);
+
+        assert_eq!(10, mock.page_size());
+        assert_eq!(20, Impl::new(()).page_size());
+    }
+}
+
+mod mut_deps {
+    use entrait::*;
+
+    #[entrait(Increment, mockall)]
+    fn increment(counter: &mut u32) -> u32 {
+        *counter += 1;
+        *counter
+    }
+
+    #[test]
+    fn test() {
+        let mut mock = MockIncrement::new();
+        mock.expect_increment().return_const(42_u32);
+
+        assert_eq!(42, mock.increment());
+        assert_eq!(1, Impl::new(0_u32).increment());
+    }
+}
+
 mod entrait_for_trait {
     use entrait::*;
 
@@ -37,3 +90,25 @@ mod entrait_for_trait {
         assert_eq!(42, mock.method());
     }
 }
+
+mod entrait_for_trait_assoc_items {
+    use entrait::*;
+
+    #[entrait(mockall)]
+    trait Trait {
+        #[entrait(mock = i32)]
+        type Output;
+        const FACTOR: i32 = 2;
+
+        fn method(&self) -> Self::Output;
+    }
+
+    #[test]
+    fn test() {
+        let mut mock = MockTrait::new();
+        mock.expect_method().return_const(42);
+
+        assert_eq!(42, mock.method());
+        assert_eq!(2, <MockTrait as Trait>::FACTOR);
+    }
+}
@@ -0,0 +1,322 @@
+fn assert_is_send<T: Send>(_: &T) {}
+
+mod leaf_and_context {
+    use super::*;
+    use entrait::*;
+
+    #[entrait(leaf_accessor)]
+    pub trait System {
+        fn current_time(&self) -> u128;
+    }
+
+    #[entrait(context)]
+    pub trait CurrentUser {
+        fn current_user(&self) -> &str;
+    }
+
+    #[entrait(context)]
+    pub struct Request {
+        #[entrait(TraceId)]
+        trace_id: u32,
+        user: String,
+    }
+
+    impl CurrentUser for Request {
+        fn current_user(&self) -> &str {
+            &self.user
+        }
+    }
+
+    #[entrait(Greet)]
+    async fn greet(deps: &(impl System + CurrentUser + TraceId)) -> String {
+        format!(
+            "{} at {} in {}",
+            deps.current_user(),
+            deps.current_time(),
+            deps.trace_id()
+        )
+    }
+
+    #[derive(Clone)]
+    pub struct Clock;
+
+    impl System for Clock {
+        fn current_time(&self) -> u128 {
+            42
+        }
+    }
+
+    #[derive(Clone, Wire)]
+    struct App {
+        #[wire(leaf = System)]
+        clock: Clock,
+    }
+
+    entrait::assert_app!(Scoped<App, Request>: Greet, System, CurrentUser, TraceId);
+
+    #[tokio::test]
+    async fn test_scoped_leaf_and_context() {
+        let app = Impl::new(App { clock: Clock });
+        let request = app.scope(Request {
+            trace_id: 7,
+            user: "ferris".to_string(),
+        });
+
+        assert_is_send(&request.greet());
+        assert_eq!("ferris at 42 in 7", request.greet().await);
+        assert_eq!(42, request.current_time());
+        assert_eq!(7, request.ctx().trace_id);
+    }
+}
+
+mod delegation {
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository(PgRepo, MemRepo))]
+    pub trait Repository {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    #[entrait(delegate_by = ref)]
+    pub trait ReadConfig {
+        fn read_config(&self) -> &str;
+    }
+
+    #[entrait(delegate_by = Arc)]
+    pub trait Notify {
+        fn notify(&self) -> String;
+    }
+
+    #[entrait(CacheImpl, delegate_by = enum(NoCache))]
+    pub trait Cache {
+        fn hit(&self) -> bool;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait]
+    impl RepositoryImpl for PgRepo {
+        fn fetch<D>(_: &D, id: u32) -> String {
+            format!("pg/{id}")
+        }
+    }
+
+    pub struct MemRepo;
+
+    #[entrait]
+    impl RepositoryImpl for MemRepo {
+        fn fetch<D>(_: &D, id: u32) -> String {
+            format!("mem/{id}")
+        }
+    }
+
+    pub struct Config(&'static str);
+
+    impl ReadConfig for Config {
+        fn read_config(&self) -> &str {
+            self.0
+        }
+    }
+
+    impl Notify for Config {
+        fn notify(&self) -> String {
+            format!("notify {}", self.0)
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct NoCache;
+
+    #[entrait(ref)]
+    impl CacheImpl for NoCache {
+        fn hit<D>(_: &D) -> bool {
+            false
+        }
+    }
+
+    #[derive(Clone, Wire)]
+    struct App {
+        #[wire(delegate = DelegateRepository, target = RepositorySelector)]
+        #[wire(ref = dyn ReadConfig, deref)]
+        config: Arc<Config>,
+        #[wire(Arc = Notify)]
+        notify: Arc<dyn Notify + Send + Sync>,
+        #[wire(enum = Cache)]
+        cache: CacheChoice,
+    }
+
+    impl Clone for CacheChoice {
+        fn clone(&self) -> Self {
+            match self {
+                Self::NoCache(no_cache) => Self::NoCache(no_cache.clone()),
+            }
+        }
+    }
+
+    pub struct Tenant(&'static str);
+
+    impl SelectRepository for App {
+        fn select_repository(_: &Impl<Self>) -> RepositorySelection {
+            RepositorySelection::MemRepo
+        }
+    }
+
+    // The tenant is only known in the scope of a request:
+    impl SelectRepository for Scoped<App, Tenant> {
+        fn select_repository(deps: &Impl<Self>) -> RepositorySelection {
+            match deps.ctx().0 {
+                "acme" => RepositorySelection::PgRepo,
+                _ => RepositorySelection::MemRepo,
+            }
+        }
+    }
+
+    entrait::assert_app!(Scoped<App, Tenant>: Repository, ReadConfig, Notify, Cache);
+
+    #[test]
+    fn test_scoped_delegation() {
+        let config = Arc::new(Config("config"));
+        let app = Impl::new(App {
+            config: config.clone(),
+            notify: config,
+            cache: CacheChoice::NoCache(NoCache),
+        });
+
+        assert_eq!("mem/1", app.fetch(1));
+        assert_eq!("pg/1", app.scope(Tenant("acme")).fetch(1));
+        assert_eq!("mem/1", app.scope(Tenant("demo")).fetch(1));
+
+        let request = app.scope(Tenant("acme"));
+        assert_eq!("config", request.read_config());
+        assert_eq!("notify config", request.notify());
+        assert!(!request.hit());
+    }
+}
+
+mod impl_trait_delegation {
+    use super::*;
+    use entrait::*;
+    use std::sync::{Arc, RwLock};
+
+    #[entrait]
+    pub trait System {
+        fn current_time(&self) -> u128;
+    }
+
+    #[entrait(FetchImpl, delegate_by = ref)]
+    pub trait Fetch {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    #[entrait(LookupImpl, delegate_by = Borrow)]
+    pub trait Lookup {
+        fn lookup(&self) -> String;
+    }
+
+    #[entrait(MailerImpl, delegate_by = Arc)]
+    pub trait Mailer {
+        async fn send(&self, to: &str) -> String;
+    }
+
+    #[entrait(CacheImpl, delegate_by = Box)]
+    pub trait Cache {
+        fn hit(&self) -> bool;
+    }
+
+    #[entrait(RateLimitImpl, delegate_by = Swap)]
+    pub trait RateLimit {
+        fn limit(&self) -> u128;
+    }
+
+    pub struct Pg;
+
+    #[entrait(ref)]
+    impl FetchImpl for Pg {
+        fn fetch(deps: &impl System, id: u32) -> String {
+            format!("pg/{id} at {}", deps.current_time())
+        }
+    }
+
+    #[entrait(ref)]
+    impl LookupImpl for Pg {
+        fn lookup(deps: &impl Fetch) -> String {
+            deps.fetch(2)
+        }
+    }
+
+    pub struct Smtp;
+
+    #[entrait(ref)]
+    impl MailerImpl for Smtp {
+        async fn send(deps: &impl System, to: &str) -> String {
+            format!("smtp:{to} at {}", deps.current_time())
+        }
+    }
+
+    #[entrait(ref)]
+    impl CacheImpl for Smtp {
+        fn hit<D>(_: &D) -> bool {
+            true
+        }
+    }
+
+    #[entrait(ref)]
+    impl RateLimitImpl for Smtp {
+        fn limit(deps: &impl System) -> u128 {
+            deps.current_time() * 2
+        }
+    }
+
+    #[derive(Wire)]
+    struct App {
+        #[wire(ref = dyn FetchImpl<Self>, deref)]
+        #[wire(Borrow = dyn LookupImpl<Self>, deref)]
+        pg: Arc<Pg>,
+        #[wire(Arc = Mailer<Self>)]
+        mailer: Arc<dyn DynMailerImpl<Self> + Send + Sync>,
+        #[wire(Box = Cache<Self>)]
+        cache: Box<dyn CacheImpl<Self> + Send + Sync>,
+        #[wire(Swap = RateLimit<Self>)]
+        rate_limit: RwLock<Arc<dyn RateLimitImpl<Self> + Send + Sync>>,
+    }
+
+    impl Clone for App {
+        fn clone(&self) -> Self {
+            Self {
+                pg: self.pg.clone(),
+                mailer: self.mailer.clone(),
+                cache: Box::new(Smtp),
+                rate_limit: RwLock::new(self.rate_limit.read().unwrap().clone()),
+            }
+        }
+    }
+
+    impl System for App {
+        fn current_time(&self) -> u128 {
+            42
+        }
+    }
+
+    pub struct Tenant;
+
+    entrait::assert_app!(Scoped<App, Tenant>: Fetch, Lookup, Mailer, Cache, RateLimit);
+
+    #[tokio::test]
+    async fn test_scoped_impl_trait_delegation() {
+        let app = Impl::new(App {
+            pg: Arc::new(Pg),
+            mailer: Arc::new(Smtp),
+            cache: Box::new(Smtp),
+            rate_limit: RwLock::new(Arc::new(Smtp)),
+        });
+        let request = app.scope(Tenant);
+
+        assert_eq!("pg/1 at 42", request.fetch(1));
+        assert_eq!("pg/2 at 42", request.lookup());
+        assert_is_send(&request.send("a"));
+        assert_eq!("smtp:a at 42", request.send("a").await);
+        assert!(request.hit());
+        assert_eq!(84, request.limit());
+    }
+}
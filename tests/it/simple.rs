@@ -152,6 +152,37 @@ mod test_entrait_for_trait {
         assert_eq!(1337, Impl::new(()).method0(0));
         assert_eq!(42, Impl::new("app").method0(0));
     }
+
+    #[derive(Clone)]
+    pub struct Config(u16);
+
+    #[entrait]
+    pub trait ReadConfig {
+        fn port(&self) -> u16;
+    }
+
+    // A blanket impl of a leaf trait does not conflict with the generated impls
+    impl<T: AsRef<Config>> ReadConfig for T {
+        fn port(&self) -> u16 {
+            self.as_ref().0
+        }
+    }
+
+    #[derive(Clone)]
+    struct App(Config);
+
+    impl AsRef<Config> for App {
+        fn as_ref(&self) -> &Config {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn leaf_trait_should_allow_blanket_impls() {
+        let app = Impl::new(App(Config(80)));
+        assert_eq!(80, app.port());
+        assert_eq!(80, app.scope(()).port());
+    }
 }
 
 mod module {
@@ -229,6 +260,321 @@ mod module {
     // The macro cannot just append a another `::super`, because `pub(super::super)` is invalid syntax.
 }
 
+mod module_fn_attributes {
+    use entrait::*;
+
+    #[entrait(pub Users)]
+    mod users {
+        use std::any::Any;
+
+        #[entrait(rename = fetch_user)]
+        pub fn get_user(_deps: &impl Any, id: u32) -> String {
+            format!("user{id}")
+        }
+
+        #[entrait(skip)]
+        pub fn helper(id: u32) -> u32 {
+            id * 2
+        }
+
+        #[entrait(no_deps)]
+        pub fn user_count() -> u32 {
+            helper(21)
+        }
+
+        #[entrait(?Send)]
+        pub async fn local_user(_deps: &impl Any) -> std::rc::Rc<u32> {
+            std::rc::Rc::new(7)
+        }
+
+        // A standalone invocation on a private function is not a per-function option
+        #[entrait::entrait(pub PrivateHelper)]
+        fn private_helper(_deps: &impl Any) -> u32 {
+            3
+        }
+
+        pub fn uses_private_helper(deps: &impl PrivateHelper) -> u32 {
+            deps.private_helper()
+        }
+    }
+
+    fn takes_users(deps: &impl Users) -> String {
+        format!("{}:{}", deps.fetch_user(1), deps.user_count())
+    }
+
+    #[tokio::test]
+    async fn test_module_fn_attributes() {
+        let app = Impl::new(());
+        assert_eq!("user1:42", takes_users(&app));
+        assert_eq!(42, users::user_count());
+        assert_eq!(7, *app.local_user().await);
+        assert_eq!(3, app.uses_private_helper());
+    }
+}
+
+mod recursive_module {
+    use entrait::*;
+
+    #[entrait(pub Domain, recursive)]
+    mod domain {
+        use std::any::Any;
+
+        pub fn version(_deps: &impl Any) -> u32 {
+            1
+        }
+
+        pub mod users {
+            pub fn user_name(_deps: &impl std::any::Any, id: u32) -> String {
+                format!("user{id}")
+            }
+
+            pub mod user_roles {
+                pub fn is_admin(_deps: &impl std::any::Any, id: u32) -> bool {
+                    id == 0
+                }
+            }
+        }
+
+        pub mod order_lines {
+            pub fn line_count(deps: &impl super::users::Users, id: u32) -> usize {
+                deps.user_name(id).len()
+            }
+        }
+
+        mod private {
+            pub fn not_included() {}
+        }
+
+        #[entrait::entrait(pub Standalone)]
+        pub mod standalone {
+            pub fn standalone(_deps: &impl std::any::Any) {}
+        }
+    }
+
+    fn takes_domain(deps: &impl Domain) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            deps.version(),
+            deps.user_name(1),
+            deps.is_admin(1),
+            deps.line_count(42)
+        )
+    }
+
+    #[test]
+    fn test_recursive_module() {
+        let app = Impl::new(());
+        assert_eq!("1:user1:false:6", takes_domain(&app));
+        domain::Standalone::standalone(&app);
+    }
+
+    pub trait Marker {}
+
+    impl Marker for () {}
+
+    #[entrait(pub Flat, recursive = flat)]
+    mod flat {
+        pub fn top(_deps: &impl super::Marker) -> u32 {
+            1
+        }
+
+        pub mod nested {
+            pub fn nested(_deps: &impl super::super::Marker) -> u32 {
+                2
+            }
+        }
+    }
+
+    // Not a supertrait, so `Flat` can be implemented without `Nested`
+    struct OnlyTop;
+
+    impl Flat for OnlyTop {
+        fn top(&self) -> u32 {
+            3
+        }
+    }
+
+    fn takes_flat(deps: &(impl Flat + flat::Nested)) -> u32 {
+        deps.top() + deps.nested()
+    }
+
+    #[test]
+    fn test_flat_recursive_module() {
+        assert_eq!(3, takes_flat(&()));
+        assert_eq!(3, OnlyTop.top());
+    }
+
+    #[entrait(NotRecursive)]
+    mod not_recursive {
+        pub fn foo(_deps: &impl std::any::Any) {}
+
+        pub mod inner {
+            pub fn bar() {}
+        }
+    }
+
+    #[test]
+    fn test_nested_module_is_kept() {
+        Impl::new(()).foo();
+        not_recursive::inner::bar();
+    }
+}
+
+mod concrete_module {
+    use entrait::*;
+
+    pub struct Config {
+        db_url: String,
+        page_size: usize,
+    }
+
+    #[entrait(pub ConfigAccess, concrete)]
+    mod config_access {
+        use super::Config;
+
+        pub fn db_url(config: &Config) -> &str {
+            &config.db_url
+        }
+
+        pub fn page_size(config: &Config) -> usize {
+            config.page_size
+        }
+
+        #[entrait(no_deps)]
+        pub fn max_page_size() -> usize {
+            100
+        }
+    }
+
+    #[entrait(PageSizeTimesTwo)]
+    fn page_size_times_two(deps: &impl ConfigAccess) -> usize {
+        deps.page_size() * 2
+    }
+
+    #[test]
+    fn test_concrete_module() {
+        let app = Impl::new(Config {
+            db_url: "postgres://".to_string(),
+            page_size: 21,
+        });
+
+        assert_eq!("postgres://", app.db_url());
+        assert_eq!(42, app.page_size_times_two());
+        assert_eq!(100, app.max_page_size());
+    }
+}
+
+mod struct_accessors {
+    use entrait::*;
+
+    #[entrait]
+    pub struct Config {
+        #[entrait(pub GetDbUrl)]
+        db_url: String,
+        #[entrait(GetPageSize)]
+        page_size: usize,
+        _not_entraited: (),
+    }
+
+    #[entrait(DescribeConfig)]
+    fn describe_config(deps: &(impl GetDbUrl + GetPageSize)) -> String {
+        format!("{}?page_size={}", deps.db_url(), deps.page_size())
+    }
+
+    #[test]
+    fn test_struct_accessors() {
+        let app = Impl::new(Config {
+            db_url: "postgres://".to_string(),
+            page_size: 20,
+            _not_entraited: (),
+        });
+
+        assert_eq!("postgres://?page_size=20", app.describe_config());
+    }
+}
+
+mod generic_method_params {
+    use entrait::*;
+    use std::fmt::Debug;
+
+    #[entrait(Save, method_generics)]
+    fn save<D, K>(_deps: &D, item: impl Debug + Send, key: K) -> String
+    where
+        K: AsRef<str>,
+    {
+        format!("{}={item:?}", key.as_ref())
+    }
+
+    #[entrait(Checksum, method_generics)]
+    fn checksum<const N: usize>(_deps: &impl std::any::Any, bytes: [u8; N]) -> usize {
+        bytes.iter().map(|byte| *byte as usize).sum::<usize>() + N
+    }
+
+    #[entrait(pub Store, method_generics)]
+    mod store {
+        pub fn put<T: ToString>(_deps: &impl std::any::Any, value: T) -> String {
+            value.to_string()
+        }
+    }
+
+    // The type params are on the methods, so one trait bound is enough for any type
+    fn takes_save(deps: &(impl Save + Checksum + Store)) -> String {
+        format!(
+            "{} {} {} {} {}",
+            deps.save(1, "a"),
+            deps.save("two", String::from("b")),
+            deps.checksum([1, 2]),
+            deps.put(42),
+            deps.put('c'),
+        )
+    }
+
+    #[test]
+    fn test_generic_methods() {
+        assert_eq!("a=1 b=\"two\" 5 42 c", takes_save(&Impl::new(())));
+    }
+
+    // Without `method_generics`, the const param becomes a param of the trait
+    #[entrait(HoistedChecksum)]
+    fn hoisted_checksum<const N: usize>(_deps: &impl std::any::Any, bytes: [u8; N]) -> usize {
+        bytes.iter().map(|byte| *byte as usize).sum::<usize>() + N
+    }
+
+    fn takes_hoisted_checksum(deps: &impl HoistedChecksum<3>) -> usize {
+        deps.hoisted_checksum([1, 2, 3])
+    }
+
+    #[test]
+    fn test_hoisted_const_generics() {
+        assert_eq!(9, takes_hoisted_checksum(&Impl::new(())));
+    }
+}
+
+mod const_and_static {
+    use entrait::*;
+    use std::time::Duration;
+
+    #[entrait(pub RequestTimeout)]
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[entrait(pub ServiceName)]
+    static SERVICE_NAME: &str = "entrait";
+
+    #[entrait(Describe)]
+    fn describe(deps: &(impl RequestTimeout + ServiceName)) -> String {
+        format!(
+            "{}:{}",
+            deps.service_name(),
+            deps.request_timeout().as_secs()
+        )
+    }
+
+    #[test]
+    fn test_const_and_static() {
+        assert_eq!("entrait:5", Impl::new(()).describe());
+    }
+}
+
 #[expect(unexpected_cfgs)]
 mod cfg_attributes {
     use entrait::*;
@@ -271,3 +617,49 @@ mod future_send_opt_out {
         Rc::new(42)
     }
 }
+
+mod mut_deps {
+    use entrait::*;
+
+    pub struct State {
+        count: u32,
+    }
+
+    #[entrait(Increment)]
+    fn increment(state: &mut State) -> u32 {
+        state.count += 1;
+        state.count
+    }
+
+    #[entrait(Count)]
+    fn count(state: &State) -> u32 {
+        state.count
+    }
+
+    #[entrait(IncrementBy)]
+    fn increment_by(deps: &mut impl Increment, n: u32) -> u32 {
+        for _ in 1..n {
+            deps.increment();
+        }
+        deps.increment()
+    }
+
+    #[entrait(pub Counter)]
+    mod counter {
+        pub fn bump(deps: &mut impl super::Increment) -> u32 {
+            deps.increment()
+        }
+
+        pub fn current(deps: &impl super::Count) -> u32 {
+            deps.count()
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut app = Impl::new(State { count: 0 });
+        assert_eq!(3, app.increment_by(3));
+        assert_eq!(4, app.bump());
+        assert_eq!(4, app.current());
+    }
+}
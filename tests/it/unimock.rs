@@ -254,6 +254,49 @@ mod future_send_bound_for_tokio_spawn {
     }
 }
 
+mod arc_deps_for_tokio_spawn {
+    use std::sync::Arc;
+
+    use entrait::*;
+    use unimock::*;
+
+    pub struct Job(i32);
+
+    #[entrait(Schedule, mock_api = ScheduleMock)]
+    async fn schedule(deps: Arc<impl Notify + Send + Sync + 'static>, job: Job) -> i32 {
+        tokio::spawn(async move { deps.notify(job.0).await })
+            .await
+            .unwrap()
+    }
+
+    #[entrait(Notify, mock_api = NotifyMock)]
+    async fn notify<T>(_: &T, n: i32) -> i32 {
+        n + 1
+    }
+
+    #[tokio::test]
+    async fn test_impl() {
+        let app = Arc::new(implementation::Impl::new(()));
+        assert_eq!(42, app.schedule(Job(41)).await);
+    }
+
+    #[tokio::test]
+    async fn test_partial_unmocked() {
+        let deps = Arc::new(Unimock::new_partial(
+            NotifyMock.each_call(matching!(41)).returns(0),
+        ));
+        assert_eq!(0, deps.schedule(Job(41)).await);
+    }
+
+    #[tokio::test]
+    async fn test_mock() {
+        let deps = Arc::new(Unimock::new(
+            ScheduleMock.each_call(matching!(_)).returns(42),
+        ));
+        assert_eq!(42, deps.schedule(Job(0)).await);
+    }
+}
+
 mod more_async {
     use entrait::*;
     use unimock::*;
@@ -294,6 +337,70 @@ mod more_async {
     }
 }
 
+mod future_returning_fns {
+    #![allow(clippy::manual_async_fn)]
+
+    use entrait::*;
+    use std::future::Future;
+    use std::rc::Rc;
+    use unimock::*;
+
+    struct State(u32);
+
+    #[entrait(Foo)]
+    fn foo<D: Bar + Sync>(deps: &D, add: u32) -> impl Future<Output = u32> + Send + '_ {
+        async move { deps.bar().await + add }
+    }
+
+    #[entrait(Bar, mock_api = BarMock)]
+    fn bar(state: &State) -> impl Future<Output = u32> + Send + '_ {
+        async move { state.0 }
+    }
+
+    #[entrait(LocalRc, ?Send)]
+    fn local_rc<D>(_: &D) -> impl Future<Output = Rc<u32>> {
+        async { Rc::new(1) }
+    }
+
+    #[entrait(mock_api = GreeterMock)]
+    pub trait Greeter: Sync {
+        fn name(&self) -> impl Future<Output = String> + Send;
+
+        fn greet(&self) -> impl Future<Output = String> + Send {
+            async move { format!("Hello, {}", self.name().await) }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_impl() {
+        let state = Impl::new(State(40));
+        assert_eq!(42, state.foo(2).await);
+        assert_eq!(1, *state.local_rc().await);
+    }
+
+    #[tokio::test]
+    async fn test_mock() {
+        let deps = Unimock::new(BarMock.each_call(matching!()).returns(40_u32));
+        assert_eq!(42, foo(&deps, 2).await);
+    }
+
+    #[tokio::test]
+    async fn test_mock_answers() {
+        let deps = Unimock::new(BarMock.each_call(matching!()).answers(&|_| 40));
+        assert_eq!(42, deps.foo(2).await);
+    }
+
+    #[tokio::test]
+    async fn test_default_body() {
+        let deps = Unimock::new_partial(
+            GreeterMock::name
+                .each_call(matching!())
+                .returns("mock".to_string()),
+        );
+        assert_eq!("Hello, mock", deps.greet().await);
+    }
+}
+
 mod async_no_deps_etc {
     use entrait::*;
     use unimock::*;
@@ -326,6 +433,7 @@ mod async_no_deps_etc {
     }
 
     #[entrait(Borrow2)]
+    #[allow(clippy::needless_lifetimes)]
     async fn borrow2<'a, 'b>(_: &'a impl Bar, _arg: &'b i32) -> &'a i32 {
         panic!()
     }
@@ -435,6 +543,62 @@ mod generics {
     }
 }
 
+mod generic_method_params {
+    use entrait::*;
+    use std::fmt::Debug;
+    use unimock::*;
+
+    #[entrait(Save, method_generics, mock_api = SaveMock)]
+    fn save<D, K: AsRef<str> + 'static>(
+        _deps: &D,
+        item: impl Debug + Send + 'static,
+        key: K,
+    ) -> String {
+        format!("{}={item:?}", key.as_ref())
+    }
+
+    fn takes_save(deps: &impl Save) -> String {
+        deps.save(42, "key")
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(
+            SaveMock
+                .with_types::<&str, i32>()
+                .each_call(matching!(42, "key"))
+                .returns("mocked".to_string()),
+        );
+        assert_eq!("mocked", takes_save(&deps));
+    }
+
+    #[test]
+    fn test_unmocked() {
+        assert_eq!("key=42", takes_save(&Unimock::new_partial(())));
+    }
+}
+
+mod mut_deps {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(Increment, mock_api = IncrementMock)]
+    fn increment(_: &mut impl std::any::Any) -> u32 {
+        unimplemented!()
+    }
+
+    #[entrait(IncrementTwice)]
+    fn increment_twice(deps: &mut impl Increment) -> u32 {
+        deps.increment() + deps.increment()
+    }
+
+    #[test]
+    fn test_mock() {
+        let mut deps = Unimock::new(IncrementMock.each_call(matching!()).returns(21_u32));
+        assert_eq!(42, increment_twice(&mut deps));
+    }
+}
+
 mod destructuring_params {
     use entrait::entrait;
 
@@ -489,6 +653,124 @@ mod entrait_for_trait_unimock {
     }
 }
 
+mod entrait_for_trait_assoc_items {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(mock_api=TraitMock)]
+    pub trait Trait {
+        #[entrait(mock = String)]
+        type Error;
+        #[entrait(mock = 10)]
+        const MAX_BATCH: usize;
+
+        fn fetch(&self) -> Result<i32, Self::Error>;
+    }
+
+    struct State;
+
+    impl Trait for State {
+        type Error = ();
+        const MAX_BATCH: usize = 100;
+
+        fn fetch(&self) -> Result<i32, ()> {
+            Ok(42)
+        }
+    }
+
+    #[test]
+    fn test_impl() {
+        assert_eq!(Ok(42), Impl::new(State).fetch());
+        assert_eq!(100, <Impl<State> as Trait>::MAX_BATCH);
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(
+            TraitMock::fetch
+                .each_call(matching!())
+                .returns(Err("error".to_string())),
+        );
+        assert_eq!(Err("error".to_string()), deps.fetch());
+        assert_eq!(10, <Unimock as Trait>::MAX_BATCH);
+    }
+}
+
+mod entrait_for_trait_default_methods {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(mock_api=TraitMock)]
+    pub trait Trait {
+        fn name(&self) -> String;
+
+        fn greet(&self) -> String {
+            format!("Hello, {}", self.name())
+        }
+    }
+
+    struct State;
+
+    impl Trait for State {
+        fn name(&self) -> String {
+            "state".to_string()
+        }
+    }
+
+    #[test]
+    fn test_impl() {
+        assert_eq!("Hello, state", Impl::new(State).greet());
+    }
+
+    #[test]
+    fn test_partial_mock_calls_default_body() {
+        let deps = Unimock::new_partial(
+            TraitMock::name
+                .each_call(matching!())
+                .returns("mock".to_string()),
+        );
+        assert_eq!("Hello, mock", deps.greet());
+    }
+}
+
+mod fn_delegate_by {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(pub SendEmail, delegate_by = DelegateSendEmail, mock_api = SendEmailMock)]
+    fn send_email<D>(_: &D, to: &str) -> String {
+        format!("sent to {to}")
+    }
+
+    #[entrait(Notify, mock_api = NotifyMock)]
+    fn notify(deps: &impl SendEmail) -> String {
+        deps.send_email("admin")
+    }
+
+    pub struct Smtp;
+
+    impl<T> SendEmailImpl<T> for Smtp {}
+
+    impl DelegateSendEmail<Self> for () {
+        type Target = Smtp;
+    }
+
+    #[test]
+    fn test_impl() {
+        assert_eq!("sent to admin", Impl::new(()).notify());
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(
+            SendEmailMock
+                .each_call(matching!("admin"))
+                .returns("mocked".to_string()),
+        );
+        assert_eq!("mocked", notify(&deps));
+    }
+}
+
 mod naming_conflict_between_fn_and_param {
     use entrait::*;
 
@@ -524,6 +806,177 @@ mod module {
     }
 }
 
+mod module_fn_mock_api {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(pub Users)]
+    pub mod users {
+        use std::any::Any;
+
+        #[entrait(mock_api = FetchUserMock)]
+        pub fn fetch_user(_deps: &impl Any, id: u32) -> String {
+            format!("user{id}")
+        }
+
+        #[entrait(rename = count, mock_api = CountMock)]
+        pub fn user_count(_deps: &impl Any) -> u32 {
+            0
+        }
+    }
+
+    fn takes_users(deps: &impl Users) -> String {
+        format!("{}:{}", deps.fetch_user(1), deps.count())
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new((
+            users::FetchUserMock
+                .each_call(matching!(1))
+                .returns("mocked".to_string()),
+            users::CountMock.each_call(matching!()).returns(42_u32),
+        ));
+        assert_eq!("mocked:42", takes_users(&deps));
+    }
+}
+
+mod recursive_module {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(pub Domain, recursive, mock_api = DomainMock)]
+    pub mod domain {
+        pub mod users {
+            pub fn user_name(_deps: &impl std::any::Any, id: u32) -> String {
+                format!("user{id}")
+            }
+        }
+
+        pub mod orders {
+            pub fn order_count(deps: &impl super::users::Users, id: u32) -> usize {
+                deps.user_name(id).len()
+            }
+        }
+    }
+
+    fn takes_domain(deps: &impl Domain) -> usize {
+        deps.order_count(1)
+    }
+
+    #[test]
+    fn test_mock_submodule() {
+        let deps = Unimock::new(
+            domain::orders::OrdersMock::order_count
+                .each_call(matching!(1))
+                .returns(42_usize),
+        );
+        assert_eq!(42, takes_domain(&deps));
+    }
+
+    #[test]
+    fn test_partial_mock_submodule() {
+        let deps = Unimock::new_partial(
+            domain::users::UsersMock::user_name
+                .each_call(matching!(1))
+                .returns("mocked".to_string()),
+        );
+        assert_eq!(6, takes_domain(&deps));
+    }
+}
+
+mod concrete_module {
+    use entrait::*;
+    use unimock::*;
+
+    pub struct Config {
+        page_size: usize,
+    }
+
+    #[entrait(pub ConfigAccess, concrete, mock_api = ConfigAccessMock)]
+    mod config_access {
+        use super::Config;
+
+        pub fn page_size(config: &Config) -> usize {
+            config.page_size
+        }
+    }
+
+    #[entrait(PageSizeTimesTwo)]
+    fn page_size_times_two(deps: &impl ConfigAccess) -> usize {
+        deps.page_size() * 2
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(
+            config_access::ConfigAccessMock::page_size
+                .each_call(matching!())
+                .returns(21_usize),
+        );
+        assert_eq!(42, page_size_times_two(&deps));
+    }
+
+    #[test]
+    fn test_impl() {
+        assert_eq!(
+            84,
+            Impl::new(Config { page_size: 42 }).page_size_times_two()
+        );
+    }
+}
+
+mod struct_accessors {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait]
+    pub struct Config {
+        #[entrait(pub GetDbUrl, mock_api = GetDbUrlMock)]
+        db_url: String,
+    }
+
+    #[entrait(ConnectionString)]
+    fn connection_string(deps: &impl GetDbUrl) -> String {
+        format!("{}/db", deps.db_url())
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(
+            GetDbUrlMock
+                .each_call(matching!())
+                .returns("mocked://".to_string()),
+        );
+        assert_eq!("mocked:///db", connection_string(&deps));
+    }
+}
+
+mod const_value {
+    use entrait::*;
+    use unimock::*;
+
+    #[entrait(pub PageSize, mock_api = PageSizeMock)]
+    const PAGE_SIZE: usize = 20;
+
+    #[entrait(PageCount)]
+    fn page_count(deps: &impl PageSize, items: usize) -> usize {
+        items.div_ceil(deps.page_size())
+    }
+
+    #[test]
+    fn test_mock() {
+        let deps = Unimock::new(PageSizeMock.each_call(matching!()).returns(10_usize));
+        assert_eq!(5, page_count(&deps, 42));
+    }
+
+    #[test]
+    fn test_unmocked() {
+        let deps = Unimock::new_partial(());
+        assert_eq!(3, page_count(&deps, 42));
+    }
+}
+
 mod module_async {
     use entrait::*;
 
@@ -0,0 +1,285 @@
+mod leaf {
+    use entrait::*;
+
+    mod upstream {
+        use entrait::*;
+
+        pub struct Config {
+            pub foo: String,
+        }
+
+        #[entrait(pub GetFoo, leaf_accessor)]
+        fn get_foo(config: &Config) -> &str {
+            &config.foo
+        }
+    }
+
+    #[entrait(Greet)]
+    fn greet(deps: &(impl upstream::GetFoo + System)) -> String {
+        format!("{} at {}", deps.get_foo(), deps.current_time())
+    }
+
+    #[entrait(leaf_accessor)]
+    pub trait System {
+        fn current_time(&self) -> u128;
+
+        async fn sleep(&self, millis: u64) -> u64;
+    }
+
+    pub struct Clock;
+
+    impl System for Clock {
+        fn current_time(&self) -> u128 {
+            42
+        }
+
+        async fn sleep(&self, millis: u64) -> u64 {
+            millis
+        }
+    }
+
+    #[derive(Wire)]
+    struct App {
+        #[wire(leaf = upstream::GetFoo)]
+        config: upstream::Config,
+        #[wire(leaf = System)]
+        clock: Clock,
+    }
+
+    entrait::assert_app!(App: Greet, System, upstream::GetFoo);
+
+    fn app() -> Impl<App> {
+        Impl::new(App {
+            config: upstream::Config {
+                foo: "foo".to_string(),
+            },
+            clock: Clock,
+        })
+    }
+
+    #[test]
+    fn test_leaf() {
+        assert_eq!("foo at 42", app().greet());
+    }
+
+    #[tokio::test]
+    async fn test_async_leaf() {
+        assert_eq!(10, app().sleep(10).await);
+    }
+}
+
+mod by_ref {
+    use entrait::*;
+    use std::sync::Arc;
+
+    #[entrait(delegate_by = ref)]
+    pub trait ReadConfig {
+        fn read_config(&self) -> &str;
+    }
+
+    #[entrait(delegate_by = Borrow)]
+    pub trait Log {
+        fn log(&self) -> String;
+    }
+
+    #[entrait(delegate_by = Deref)]
+    pub trait Count {
+        fn count(&self) -> usize;
+    }
+
+    pub struct Config(&'static str);
+
+    impl ReadConfig for Config {
+        fn read_config(&self) -> &str {
+            self.0
+        }
+    }
+
+    impl Log for Config {
+        fn log(&self) -> String {
+            format!("log {}", self.0)
+        }
+    }
+
+    pub struct Counter(usize);
+
+    impl Count for Counter {
+        fn count(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[derive(Wire)]
+    struct App {
+        #[wire(ref = dyn ReadConfig, deref)]
+        config: Box<dyn ReadConfig + Sync>,
+        #[wire(Borrow = dyn Log)]
+        log: Config,
+        #[wire(Deref)]
+        counter: Counter,
+    }
+
+    #[derive(Wire)]
+    struct TupleApp(#[wire(ref = dyn ReadConfig, deref)] Arc<Config>);
+
+    entrait::assert_app!(App: ReadConfig, Log, Count);
+    entrait::assert_app!(TupleApp: ReadConfig);
+
+    #[test]
+    fn test_ref_borrow_and_deref() {
+        let app = Impl::new(App {
+            config: Box::new(Config("boxed")),
+            log: Config("borrowed"),
+            counter: Counter(3),
+        });
+
+        assert_eq!("boxed", app.read_config());
+        assert_eq!("log borrowed", app.log());
+        assert_eq!(3, app.count());
+        assert_eq!(
+            "arc",
+            Impl::new(TupleApp(Arc::new(Config("arc")))).read_config()
+        );
+    }
+}
+
+mod accessors {
+    use entrait::*;
+    use std::sync::{Arc, RwLock};
+
+    #[entrait(MailerImpl, delegate_by = Arc)]
+    pub trait Mailer {
+        fn send(&self, to: &str) -> String;
+    }
+
+    #[entrait(delegate_by = Box)]
+    pub trait Notify {
+        fn notify(&self) -> String;
+    }
+
+    #[entrait(delegate_by = Swap)]
+    pub trait Token {
+        fn token(&self) -> String;
+    }
+
+    #[entrait(RepoImpl, delegate_by = enum(PgRepo))]
+    pub trait Repo {
+        fn fetch(&self, id: u32) -> String;
+    }
+
+    pub struct Smtp;
+
+    #[entrait(ref)]
+    impl MailerImpl for Smtp {
+        fn send<D>(_: &D, to: &str) -> String {
+            format!("mail to {to}")
+        }
+    }
+
+    impl Notify for Smtp {
+        fn notify(&self) -> String {
+            "notified".to_string()
+        }
+    }
+
+    pub struct Secret(&'static str);
+
+    impl Token for Secret {
+        fn token(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    pub struct PgRepo;
+
+    #[entrait(ref)]
+    impl RepoImpl for PgRepo {
+        fn fetch<D>(_: &D, id: u32) -> String {
+            format!("pg/{id}")
+        }
+    }
+
+    #[derive(Wire)]
+    struct App {
+        #[wire(Arc = Mailer<Self>)]
+        mailer: Arc<dyn MailerImpl<App> + Send + Sync>,
+        #[wire(Box = Notify)]
+        notify: Box<dyn Notify + Send + Sync>,
+        #[wire(Swap = Token)]
+        token: RwLock<Arc<dyn Token + Send + Sync>>,
+        #[wire(enum = Repo)]
+        repo: RepoChoice,
+    }
+
+    entrait::assert_app!(App: Mailer, Notify, Token, Repo,);
+
+    #[test]
+    fn test_accessors() {
+        let app = Impl::new(App {
+            mailer: Arc::new(Smtp),
+            notify: Box::new(Smtp),
+            token: RwLock::new(Arc::new(Secret("old"))),
+            repo: RepoChoice::PgRepo(PgRepo),
+        });
+
+        assert_eq!("mail to admin", app.send("admin"));
+        assert_eq!("notified", app.notify());
+        assert_eq!("pg/1", app.fetch(1));
+
+        app.swap_token(Arc::new(Secret("new")));
+        assert_eq!("new", app.token());
+    }
+}
+
+mod delegate {
+    use entrait::*;
+
+    #[entrait(RepositoryImpl, delegate_by = DelegateRepository)]
+    pub trait Repository {
+        fn fetch(&self) -> i32;
+    }
+
+    #[entrait(CacheImpl, delegate_by = DelegateCache)]
+    pub trait Cache {
+        fn hit(&self) -> bool;
+    }
+
+    pub struct PgRepo;
+
+    #[entrait]
+    impl RepositoryImpl for PgRepo {
+        fn fetch<D>(_: &D) -> i32 {
+            42
+        }
+    }
+
+    pub struct NoCache;
+
+    #[entrait]
+    impl CacheImpl for NoCache {
+        fn hit<D>(_: &D) -> bool {
+            false
+        }
+    }
+
+    #[derive(Wire)]
+    struct App<C: Sync + 'static> {
+        #[wire(delegate = DelegateRepository)]
+        repo: PgRepo,
+        #[wire(delegate = DelegateCache<Self>, target = NoCache)]
+        cache: C,
+    }
+
+    entrait::assert_app!(App<()>: Repository, Cache);
+
+    #[test]
+    fn test_delegate() {
+        let app = Impl::new(App {
+            repo: PgRepo,
+            cache: (),
+        });
+
+        assert_eq!(42, app.fetch());
+        assert!(!app.hit());
+    }
+}